@@ -0,0 +1,455 @@
+use derive_more::Display;
+use dioxus::prelude::*;
+use shared::language::get_local_text;
+
+use crate::elements::{
+    button::Button,
+    input::{Input, Options},
+    Appearance,
+};
+use crate::icons::Icon;
+
+#[derive(Eq, PartialEq, Clone, Copy, Display)]
+pub enum Category {
+    #[display(fmt = "smileys")]
+    Smileys,
+    #[display(fmt = "people")]
+    People,
+    #[display(fmt = "nature")]
+    Nature,
+    #[display(fmt = "food")]
+    Food,
+    #[display(fmt = "activities")]
+    Activities,
+    #[display(fmt = "travel")]
+    Travel,
+    #[display(fmt = "objects")]
+    Objects,
+    #[display(fmt = "symbols")]
+    Symbols,
+}
+
+const CATEGORIES: [(Category, &str); 8] = [
+    (Category::Smileys, "😀"),
+    (Category::People, "👋"),
+    (Category::Nature, "🐶"),
+    (Category::Food, "🍕"),
+    (Category::Activities, "⚽"),
+    (Category::Travel, "🚗"),
+    (Category::Objects, "💡"),
+    (Category::Symbols, "❤️"),
+];
+
+// (emoji, shortcode, category)
+const EMOJIS: &[(&str, &str, Category)] = &[
+    ("😀", "grinning", Category::Smileys),
+    ("😃", "smiley", Category::Smileys),
+    ("😄", "smile", Category::Smileys),
+    ("😁", "grin", Category::Smileys),
+    ("😆", "laughing", Category::Smileys),
+    ("😅", "sweat_smile", Category::Smileys),
+    ("🤣", "rofl", Category::Smileys),
+    ("😂", "joy", Category::Smileys),
+    ("🙂", "slightly_smiling_face", Category::Smileys),
+    ("🙃", "upside_down_face", Category::Smileys),
+    ("😉", "wink", Category::Smileys),
+    ("😊", "blush", Category::Smileys),
+    ("😇", "innocent", Category::Smileys),
+    ("🥰", "smiling_face_with_hearts", Category::Smileys),
+    ("😍", "heart_eyes", Category::Smileys),
+    ("🤩", "star_struck", Category::Smileys),
+    ("😘", "kissing_heart", Category::Smileys),
+    ("😋", "yum", Category::Smileys),
+    ("😛", "stuck_out_tongue", Category::Smileys),
+    ("😜", "stuck_out_tongue_winking_eye", Category::Smileys),
+    ("🤪", "zany_face", Category::Smileys),
+    ("🤔", "thinking", Category::Smileys),
+    ("🤨", "raised_eyebrow", Category::Smileys),
+    ("😐", "neutral_face", Category::Smileys),
+    ("😑", "expressionless", Category::Smileys),
+    ("😶", "no_mouth", Category::Smileys),
+    ("😏", "smirk", Category::Smileys),
+    ("😒", "unamused", Category::Smileys),
+    ("🙄", "roll_eyes", Category::Smileys),
+    ("😬", "grimacing", Category::Smileys),
+    ("😌", "relieved", Category::Smileys),
+    ("😔", "pensive", Category::Smileys),
+    ("😴", "sleeping", Category::Smileys),
+    ("😷", "mask", Category::Smileys),
+    ("🤯", "exploding_head", Category::Smileys),
+    ("🥳", "partying_face", Category::Smileys),
+    ("😎", "sunglasses", Category::Smileys),
+    ("🤓", "nerd_face", Category::Smileys),
+    ("😕", "confused", Category::Smileys),
+    ("😮", "open_mouth", Category::Smileys),
+    ("😲", "astonished", Category::Smileys),
+    ("😳", "flushed", Category::Smileys),
+    ("🥺", "pleading_face", Category::Smileys),
+    ("😢", "cry", Category::Smileys),
+    ("😭", "sob", Category::Smileys),
+    ("😱", "scream", Category::Smileys),
+    ("😤", "triumph", Category::Smileys),
+    ("😡", "rage", Category::Smileys),
+    ("🤬", "cursing_face", Category::Smileys),
+    ("💀", "skull", Category::Smileys),
+    ("💩", "poop", Category::Smileys),
+    ("🤡", "clown_face", Category::Smileys),
+    ("👻", "ghost", Category::Smileys),
+    ("👽", "alien", Category::Smileys),
+    ("🤖", "robot", Category::Smileys),
+    ("👋", "wave", Category::People),
+    ("🤚", "raised_back_of_hand", Category::People),
+    ("✋", "raised_hand", Category::People),
+    ("👌", "ok_hand", Category::People),
+    ("🤌", "pinched_fingers", Category::People),
+    ("✌️", "v", Category::People),
+    ("🤞", "crossed_fingers", Category::People),
+    ("🤟", "love_you_gesture", Category::People),
+    ("🤘", "metal", Category::People),
+    ("🤙", "call_me_hand", Category::People),
+    ("👈", "point_left", Category::People),
+    ("👉", "point_right", Category::People),
+    ("👆", "point_up_2", Category::People),
+    ("👇", "point_down", Category::People),
+    ("👍", "thumbsup", Category::People),
+    ("👎", "thumbsdown", Category::People),
+    ("✊", "fist", Category::People),
+    ("👊", "punch", Category::People),
+    ("👏", "clap", Category::People),
+    ("🙌", "raised_hands", Category::People),
+    ("👐", "open_hands", Category::People),
+    ("🤝", "handshake", Category::People),
+    ("🙏", "pray", Category::People),
+    ("💪", "muscle", Category::People),
+    ("👀", "eyes", Category::People),
+    ("🧠", "brain", Category::People),
+    ("🤷", "shrug", Category::People),
+    ("🤦", "facepalm", Category::People),
+    ("🙋", "raising_hand", Category::People),
+    ("🙅", "no_good", Category::People),
+    ("🐶", "dog", Category::Nature),
+    ("🐱", "cat", Category::Nature),
+    ("🐭", "mouse", Category::Nature),
+    ("🐰", "rabbit", Category::Nature),
+    ("🦊", "fox_face", Category::Nature),
+    ("🐻", "bear", Category::Nature),
+    ("🐼", "panda_face", Category::Nature),
+    ("🐨", "koala", Category::Nature),
+    ("🐯", "tiger", Category::Nature),
+    ("🦁", "lion", Category::Nature),
+    ("🐸", "frog", Category::Nature),
+    ("🐵", "monkey_face", Category::Nature),
+    ("🐔", "chicken", Category::Nature),
+    ("🐧", "penguin", Category::Nature),
+    ("🦄", "unicorn", Category::Nature),
+    ("🐝", "bee", Category::Nature),
+    ("🦋", "butterfly", Category::Nature),
+    ("🐢", "turtle", Category::Nature),
+    ("🐙", "octopus", Category::Nature),
+    ("🐳", "whale", Category::Nature),
+    ("🌵", "cactus", Category::Nature),
+    ("🌲", "evergreen_tree", Category::Nature),
+    ("🍀", "four_leaf_clover", Category::Nature),
+    ("🌸", "cherry_blossom", Category::Nature),
+    ("🌹", "rose", Category::Nature),
+    ("🌻", "sunflower", Category::Nature),
+    ("🌞", "sun_with_face", Category::Nature),
+    ("🌙", "crescent_moon", Category::Nature),
+    ("⭐", "star", Category::Nature),
+    ("🔥", "fire", Category::Nature),
+    ("🌈", "rainbow", Category::Nature),
+    ("❄️", "snowflake", Category::Nature),
+    ("🍏", "green_apple", Category::Food),
+    ("🍎", "apple", Category::Food),
+    ("🍌", "banana", Category::Food),
+    ("🍉", "watermelon", Category::Food),
+    ("🍇", "grapes", Category::Food),
+    ("🍓", "strawberry", Category::Food),
+    ("🍒", "cherries", Category::Food),
+    ("🍑", "peach", Category::Food),
+    ("🥑", "avocado", Category::Food),
+    ("🌶️", "hot_pepper", Category::Food),
+    ("🥐", "croissant", Category::Food),
+    ("🧀", "cheese", Category::Food),
+    ("🍔", "hamburger", Category::Food),
+    ("🍟", "fries", Category::Food),
+    ("🍕", "pizza", Category::Food),
+    ("🌮", "taco", Category::Food),
+    ("🍣", "sushi", Category::Food),
+    ("🍜", "ramen", Category::Food),
+    ("🍩", "doughnut", Category::Food),
+    ("🍪", "cookie", Category::Food),
+    ("🎂", "birthday", Category::Food),
+    ("🍫", "chocolate_bar", Category::Food),
+    ("🍿", "popcorn", Category::Food),
+    ("☕", "coffee", Category::Food),
+    ("🍵", "tea", Category::Food),
+    ("🍺", "beer", Category::Food),
+    ("🍷", "wine_glass", Category::Food),
+    ("🥂", "clinking_glasses", Category::Food),
+    ("⚽", "soccer", Category::Activities),
+    ("🏀", "basketball", Category::Activities),
+    ("🏈", "football", Category::Activities),
+    ("⚾", "baseball", Category::Activities),
+    ("🎾", "tennis", Category::Activities),
+    ("🏐", "volleyball", Category::Activities),
+    ("🎱", "8ball", Category::Activities),
+    ("🏓", "ping_pong", Category::Activities),
+    ("⛳", "golf", Category::Activities),
+    ("🎣", "fishing_pole_and_fish", Category::Activities),
+    ("🎿", "ski", Category::Activities),
+    ("🏆", "trophy", Category::Activities),
+    ("🥇", "1st_place_medal", Category::Activities),
+    ("🎮", "video_game", Category::Activities),
+    ("🎲", "game_die", Category::Activities),
+    ("🧩", "jigsaw", Category::Activities),
+    ("🎨", "art", Category::Activities),
+    ("🎤", "microphone", Category::Activities),
+    ("🎧", "headphones", Category::Activities),
+    ("🎸", "guitar", Category::Activities),
+    ("🎹", "musical_keyboard", Category::Activities),
+    ("🎉", "tada", Category::Activities),
+    ("🎁", "gift", Category::Activities),
+    ("🚗", "car", Category::Travel),
+    ("🚕", "taxi", Category::Travel),
+    ("🚌", "bus", Category::Travel),
+    ("🚓", "police_car", Category::Travel),
+    ("🚑", "ambulance", Category::Travel),
+    ("🚲", "bike", Category::Travel),
+    ("🛴", "kick_scooter", Category::Travel),
+    ("🚂", "steam_locomotive", Category::Travel),
+    ("✈️", "airplane", Category::Travel),
+    ("🚀", "rocket", Category::Travel),
+    ("🛸", "flying_saucer", Category::Travel),
+    ("⛵", "sailboat", Category::Travel),
+    ("🚢", "ship", Category::Travel),
+    ("🗺️", "world_map", Category::Travel),
+    ("🏔️", "mountain_snow", Category::Travel),
+    ("🏖️", "beach_umbrella", Category::Travel),
+    ("🏝️", "desert_island", Category::Travel),
+    ("🏠", "house", Category::Travel),
+    ("🏢", "office", Category::Travel),
+    ("🗽", "statue_of_liberty", Category::Travel),
+    ("🌍", "earth_africa", Category::Travel),
+    ("⌚", "watch", Category::Objects),
+    ("📱", "iphone", Category::Objects),
+    ("💻", "computer", Category::Objects),
+    ("⌨️", "keyboard", Category::Objects),
+    ("🖥️", "desktop_computer", Category::Objects),
+    ("🖨️", "printer", Category::Objects),
+    ("📷", "camera", Category::Objects),
+    ("🎥", "movie_camera", Category::Objects),
+    ("📞", "telephone_receiver", Category::Objects),
+    ("📺", "tv", Category::Objects),
+    ("⏰", "alarm_clock", Category::Objects),
+    ("🔋", "battery", Category::Objects),
+    ("💡", "bulb", Category::Objects),
+    ("🔦", "flashlight", Category::Objects),
+    ("💸", "money_with_wings", Category::Objects),
+    ("💰", "moneybag", Category::Objects),
+    ("💎", "gem", Category::Objects),
+    ("🔧", "wrench", Category::Objects),
+    ("🔨", "hammer", Category::Objects),
+    ("⚙️", "gear", Category::Objects),
+    ("🔒", "lock", Category::Objects),
+    ("🔑", "key", Category::Objects),
+    ("📦", "package", Category::Objects),
+    ("📎", "paperclip", Category::Objects),
+    ("📌", "pushpin", Category::Objects),
+    ("📝", "memo", Category::Objects),
+    ("📅", "date", Category::Objects),
+    ("📚", "books", Category::Objects),
+    ("❤️", "heart", Category::Symbols),
+    ("🧡", "orange_heart", Category::Symbols),
+    ("💛", "yellow_heart", Category::Symbols),
+    ("💚", "green_heart", Category::Symbols),
+    ("💙", "blue_heart", Category::Symbols),
+    ("💜", "purple_heart", Category::Symbols),
+    ("🖤", "black_heart", Category::Symbols),
+    ("💔", "broken_heart", Category::Symbols),
+    ("💯", "100", Category::Symbols),
+    ("✅", "white_check_mark", Category::Symbols),
+    ("❌", "x", Category::Symbols),
+    ("❓", "question", Category::Symbols),
+    ("❗", "exclamation", Category::Symbols),
+    ("⚠️", "warning", Category::Symbols),
+    ("🚫", "no_entry_sign", Category::Symbols),
+    ("♻️", "recycle", Category::Symbols),
+    ("✨", "sparkles", Category::Symbols),
+    ("💤", "zzz", Category::Symbols),
+    ("💬", "speech_balloon", Category::Symbols),
+    ("🔔", "bell", Category::Symbols),
+    ("🎵", "musical_note", Category::Symbols),
+    ("➕", "heavy_plus_sign", Category::Symbols),
+    ("➖", "heavy_minus_sign", Category::Symbols),
+    ("🆗", "ok", Category::Symbols),
+    ("🆒", "cool", Category::Symbols),
+    ("🆕", "new", Category::Symbols),
+];
+
+// finds the emojis whose shortcode contains the query. a leading ':' is ignored
+pub fn search(query: &str) -> Vec<&'static str> {
+    let query = query.trim().trim_start_matches(':').to_lowercase();
+    EMOJIS
+        .iter()
+        .filter(|(_, shortcode, _)| shortcode.contains(&query))
+        .map(|(emoji, _, _)| *emoji)
+        .collect()
+}
+
+fn in_category(category: Category) -> Vec<&'static str> {
+    EMOJIS
+        .iter()
+        .filter(|(_, _, c)| *c == category)
+        .map(|(emoji, _, _)| *emoji)
+        .collect()
+}
+
+#[derive(Props)]
+pub struct Props<'a> {
+    // the most recently used emojis, most recent first
+    #[props(optional)]
+    recents: Option<Vec<String>>,
+    onselect: EventHandler<'a, String>,
+    onclose: EventHandler<'a>,
+}
+
+#[allow(non_snake_case)]
+pub fn EmojiPicker<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    let query = use_state(cx, String::new);
+    let category = use_state(cx, || Category::Smileys);
+    let recents = cx.props.recents.clone().unwrap_or_default();
+
+    let emojis = if query.get().trim().is_empty() {
+        in_category(*category.get())
+    } else {
+        search(query.get())
+    };
+
+    cx.render(rsx!(
+        div {
+            class: "emoji-picker",
+            aria_label: "emoji-picker",
+            div {
+                class: "emoji-picker-header",
+                Input {
+                    focus: true,
+                    placeholder: get_local_text("emoji-picker.search-placeholder"),
+                    aria_label: "emoji-search-input".into(),
+                    icon: Icon::MagnifyingGlass,
+                    options: Options {
+                        with_clear_btn: true,
+                        ..Options::default()
+                    },
+                    onchange: move |(v, _): (String, bool)| query.set(v),
+                },
+                Button {
+                    small: true,
+                    icon: Icon::XMark,
+                    appearance: Appearance::Secondary,
+                    onpress: move |_| cx.props.onclose.call(()),
+                },
+            },
+            (!recents.is_empty() && query.get().trim().is_empty()).then(|| rsx!(
+                p {
+                    class: "emoji-section-label",
+                    get_local_text("emoji-picker.recent")
+                },
+                div {
+                    class: "emojis",
+                    recents.iter().map(|emoji| {
+                        let selected = emoji.clone();
+                        rsx!(
+                            div {
+                                key: "recent-{emoji}",
+                                class: "emoji",
+                                onclick: move |_| cx.props.onselect.call(selected.clone()),
+                                "{emoji}"
+                            }
+                        )
+                    })
+                },
+            )),
+            query.get().trim().is_empty().then(|| rsx!(
+                p {
+                    class: "emoji-section-label",
+                    get_local_text(&format!("emoji-picker.{}", category.get()))
+                },
+            )),
+            div {
+                class: "emojis",
+                emojis.into_iter().map(|emoji| rsx!(
+                    div {
+                        key: "{emoji}",
+                        class: "emoji",
+                        onclick: move |_| cx.props.onselect.call(emoji.to_string()),
+                        "{emoji}"
+                    }
+                ))
+            },
+            div {
+                class: "emoji-categories",
+                CATEGORIES.iter().map(|(c, icon)| {
+                    let c = *c;
+                    rsx!(
+                        div {
+                            key: "{c}",
+                            class: format_args!("emoji-category {}", if *category.get() == c { "active" } else { "" }),
+                            aria_label: "emoji-category-{c}",
+                            onclick: move |_| {
+                                query.set(String::new());
+                                category.set(c);
+                            },
+                            "{icon}"
+                        }
+                    )
+                })
+            }
+        }
+    ))
+}
+
+#[derive(Props)]
+pub struct ReactionsProps<'a> {
+    // (emoji, number of reactions, whether the local user reacted)
+    reactions: Vec<(String, usize, bool)>,
+    #[props(optional)]
+    remote: Option<bool>,
+    onpress: EventHandler<'a, String>,
+}
+
+// the reactions of a message. pressing one toggles the user's own reaction
+#[allow(non_snake_case)]
+pub fn Reactions<'a>(cx: Scope<'a, ReactionsProps<'a>>) -> Element<'a> {
+    let remote = cx.props.remote.unwrap_or_default();
+
+    cx.render(rsx!(
+        div {
+            class: format_args!("reactions {}", if remote { "remote" } else { "" }),
+            cx.props.reactions.iter().map(|(emoji, count, reacted)| {
+                let pressed = emoji.clone();
+                rsx!(
+                    div {
+                        key: "{emoji}",
+                        class: format_args!("reaction {}", if *reacted { "reacted" } else { "" }),
+                        aria_label: "reaction",
+                        onclick: move |_| cx.props.onpress.call(pressed.clone()),
+                        "{emoji} {count}"
+                    }
+                )
+            })
+        }
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        assert_eq!(search(":thumbsup"), vec!["👍"]);
+        assert!(search("heart").contains(&"❤️"));
+        assert!(search("not_an_emoji").is_empty());
+    }
+}
@@ -7,15 +7,15 @@ use dioxus::prelude::*;
 use humansize::format_size;
 use humansize::DECIMAL;
 
-#[derive(PartialEq, Props)]
-pub struct Props {
+#[derive(Props)]
+pub struct Props<'a> {
     // The filename of the file
     #[props(optional)]
     filename: Option<String>,
 
     // The size of the file in bytes
     #[props(optional)]
-    filesize: Option<u32>,
+    filesize: Option<u64>,
 
     // The type of the file (e.g. "PDF", "JPEG")
     #[props(optional)]
@@ -28,6 +28,14 @@ pub struct Props {
     // The icon to use to represent the file
     #[props(optional)]
     icon: Option<Icon>,
+
+    // Whether the file is still being uploaded. Hides the download button
+    #[props(optional)]
+    uploading: Option<bool>,
+
+    // Called when the download button is pressed
+    #[props(optional)]
+    on_download: Option<EventHandler<'a>>,
 }
 
 pub fn get_icon(cx: &Scope<Props>) -> Icon {
@@ -37,20 +45,24 @@ pub fn get_icon(cx: &Scope<Props>) -> Icon {
 }
 
 #[allow(non_snake_case)]
-pub fn FileEmbed(cx: Scope<Props>) -> Element {
+pub fn FileEmbed<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     let filename = cx.props.filename.clone().unwrap_or_default();
     let kind = cx.props.kind.clone().unwrap_or_default();
     let filesize = cx.props.filesize.unwrap_or_default();
     let filesize_str = format_size(filesize, DECIMAL);
     let remote = cx.props.remote.unwrap_or_default();
+    let uploading = cx.props.uploading.unwrap_or_default();
 
     cx.render(rsx! (
         div {
             class: {
                 format_args!(
-                    "file-embed {}",
+                    "file-embed {} {}",
                     if remote {
                         "remote"
+                    } else { "" },
+                    if uploading {
+                        "uploading"
                     } else { "" }
                 )
             },
@@ -71,10 +83,18 @@ pub fn FileEmbed(cx: Scope<Props>) -> Element {
                     "{kind} - {filesize_str}"
                 }
             },
-            Button {
-                icon: Icon::ArrowDown,
-                appearance: Appearance::Primary,
-            }
+            (!uploading).then(|| rsx!(
+                Button {
+                    icon: Icon::ArrowDown,
+                    appearance: Appearance::Primary,
+                    aria_label: "download-file-button".into(),
+                    onpress: move |_| {
+                        if let Some(f) = cx.props.on_download.as_ref() {
+                            f.call(())
+                        }
+                    }
+                }
+            ))
         }
     ))
 }
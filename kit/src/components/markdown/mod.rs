@@ -0,0 +1,529 @@
+use dioxus::prelude::*;
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+use shared::language::get_local_text;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+use crate::elements::{button::Button, Appearance};
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME: Lazy<Theme> = Lazy::new(|| {
+    ThemeSet::load_defaults()
+        .themes
+        .remove("base16-ocean.dark")
+        .unwrap_or_default()
+});
+
+// only links with these schemes are rendered as links
+const SAFE_SCHEMES: [&str; 3] = ["https://", "http://", "mailto:"];
+// bare urls in text are turned into links if they start with one of these
+const URL_PREFIXES: [&str; 2] = ["https://", "http://"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Container {
+    Paragraph,
+    Heading(u8),
+    BlockQuote,
+    // the number of the first item, for ordered lists
+    List(Option<u64>),
+    Item,
+    Emphasis,
+    Strong,
+    Strikethrough,
+    // anything else, rendered without formatting
+    Span,
+}
+
+// markdown, parsed into a tree which can be rendered without passing any html through
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Text(String),
+    Code(String),
+    CodeBlock { lang: String, code: String },
+    Link { url: String, content: Vec<Node> },
+    SoftBreak,
+    HardBreak,
+    Rule,
+    Container { kind: Container, content: Vec<Node> },
+    // a mentioned user, shown by name. it isn't parsed as markdown, so names can't break the formatting
+    Mention(String),
+}
+
+enum Open {
+    Container(Container),
+    CodeBlock(String),
+    Link(String),
+}
+
+impl Open {
+    fn into_node(self, content: Vec<Node>) -> Node {
+        match self {
+            Open::Container(kind) => Node::Container { kind, content },
+            Open::CodeBlock(lang) => Node::CodeBlock {
+                lang,
+                code: content
+                    .into_iter()
+                    .filter_map(|n| match n {
+                        Node::Text(t) => Some(t),
+                        _ => None,
+                    })
+                    .collect(),
+            },
+            Open::Link(url) if is_safe_url(&url) => Node::Link { url, content },
+            Open::Link(_) => Node::Container {
+                kind: Container::Span,
+                content,
+            },
+        }
+    }
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let url = url.to_lowercase();
+    SAFE_SCHEMES.iter().any(|s| url.starts_with(s))
+}
+
+fn open_tag(tag: Tag) -> Open {
+    match tag {
+        Tag::Paragraph => Open::Container(Container::Paragraph),
+        Tag::Heading(level, _, _) => Open::Container(Container::Heading(level as u8)),
+        Tag::BlockQuote => Open::Container(Container::BlockQuote),
+        Tag::CodeBlock(CodeBlockKind::Fenced(lang)) => Open::CodeBlock(lang.to_string()),
+        Tag::CodeBlock(CodeBlockKind::Indented) => Open::CodeBlock(String::new()),
+        Tag::List(start) => Open::Container(Container::List(start)),
+        Tag::Item => Open::Container(Container::Item),
+        Tag::Emphasis => Open::Container(Container::Emphasis),
+        Tag::Strong => Open::Container(Container::Strong),
+        Tag::Strikethrough => Open::Container(Container::Strikethrough),
+        // images aren't loaded. they are shown as a link to the image
+        Tag::Link(_, url, _) | Tag::Image(_, url, _) => Open::Link(url.to_string()),
+        _ => Open::Container(Container::Span),
+    }
+}
+
+// splits text into text and links, for the urls in it
+fn split_urls(text: &str) -> Vec<Node> {
+    let mut nodes = vec![];
+    let mut rest = text;
+    while let Some(start) = URL_PREFIXES.iter().filter_map(|p| rest.find(p)).min() {
+        let (before, from) = rest.split_at(start);
+        let end = from.find(char::is_whitespace).unwrap_or(from.len());
+        // trailing punctuation usually ends the sentence rather than the url
+        let url = from[..end].trim_end_matches(|c| ".,;:!?)'\"".contains(c));
+        if !before.is_empty() {
+            nodes.push(Node::Text(before.to_string()));
+        }
+        nodes.push(Node::Link {
+            url: url.to_string(),
+            content: vec![Node::Text(url.to_string())],
+        });
+        rest = &from[url.len()..];
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    nodes
+}
+
+// replaces each (token, name) in the text with a mention of that name. in code, the name is shown as plain text
+pub fn replace_mentions(nodes: Vec<Node>, mentions: &[(String, String)]) -> Vec<Node> {
+    if mentions.is_empty() {
+        return nodes;
+    }
+    let replace_in_code = |code: String| {
+        mentions
+            .iter()
+            .fold(code, |code, (token, name)| code.replace(token, name))
+    };
+    nodes
+        .into_iter()
+        .flat_map(|node| match node {
+            Node::Text(text) => split_mentions(&text, mentions),
+            Node::Code(code) => vec![Node::Code(replace_in_code(code))],
+            Node::CodeBlock { lang, code } => vec![Node::CodeBlock {
+                lang,
+                code: replace_in_code(code),
+            }],
+            Node::Link { url, content } => vec![Node::Link {
+                url,
+                content: replace_mentions(content, mentions),
+            }],
+            Node::Container { kind, content } => vec![Node::Container {
+                kind,
+                content: replace_mentions(content, mentions),
+            }],
+            node => vec![node],
+        })
+        .collect()
+}
+
+// splits text into text and mentions
+fn split_mentions(text: &str, mentions: &[(String, String)]) -> Vec<Node> {
+    let mut nodes = vec![];
+    let mut rest = text;
+    while let Some((start, token, name)) = mentions
+        .iter()
+        .filter(|(token, _)| !token.is_empty())
+        .filter_map(|(token, name)| rest.find(token.as_str()).map(|idx| (idx, token, name)))
+        .min_by_key(|(idx, _, _)| *idx)
+    {
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        nodes.push(Node::Mention(name.clone()));
+        rest = &rest[start + token.len()..];
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    nodes
+}
+
+pub fn parse(text: &str) -> Vec<Node> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    // the open tags, with the nodes found inside of them so far. the root has no tag
+    let mut stack: Vec<(Option<Open>, Vec<Node>)> = vec![(None, vec![])];
+    // text can arrive in pieces. it's joined so that urls aren't split
+    let mut pending_text = String::new();
+
+    let flush = |stack: &mut Vec<(Option<Open>, Vec<Node>)>, pending_text: &mut String| {
+        if pending_text.is_empty() {
+            return;
+        }
+        let text = std::mem::take(pending_text);
+        if let Some((open, content)) = stack.last_mut() {
+            match open {
+                Some(Open::CodeBlock(_)) | Some(Open::Link(_)) => content.push(Node::Text(text)),
+                _ => content.extend(split_urls(&text)),
+            }
+        }
+    };
+
+    for event in Parser::new_ext(text, options) {
+        let node = match event {
+            Event::Text(t) => {
+                pending_text.push_str(&t);
+                continue;
+            }
+            // raw html is shown as the text it was written as
+            Event::Html(t) => {
+                pending_text.push_str(&t);
+                continue;
+            }
+            Event::Start(tag) => {
+                flush(&mut stack, &mut pending_text);
+                stack.push((Some(open_tag(tag)), vec![]));
+                continue;
+            }
+            Event::End(_) => {
+                flush(&mut stack, &mut pending_text);
+                if stack.len() < 2 {
+                    continue;
+                }
+                match stack.pop() {
+                    Some((Some(open), content)) => open.into_node(content),
+                    _ => continue,
+                }
+            }
+            Event::Code(t) => Node::Code(t.to_string()),
+            Event::SoftBreak => Node::SoftBreak,
+            Event::HardBreak => Node::HardBreak,
+            Event::Rule => Node::Rule,
+            Event::FootnoteReference(t) => Node::Text(format!("[^{t}]")),
+            Event::TaskListMarker(checked) => {
+                Node::Text(if checked { "[x] " } else { "[ ] " }.into())
+            }
+        };
+        flush(&mut stack, &mut pending_text);
+        if let Some((_, content)) = stack.last_mut() {
+            content.push(node);
+        }
+    }
+    flush(&mut stack, &mut pending_text);
+
+    stack.into_iter().next().map(|(_, c)| c).unwrap_or_default()
+}
+
+// (css color, text) for each highlighted piece of the code, if the language is known
+pub fn highlight(code: &str, lang: &str) -> Option<Vec<(String, String)>> {
+    let syntax = SYNTAXES.find_syntax_by_token(lang)?;
+    let mut highlighter = HighlightLines::new(syntax, &THEME);
+    let mut spans = vec![];
+    for line in LinesWithEndings::from(code) {
+        for (style, text) in highlighter.highlight_line(line, &SYNTAXES).ok()? {
+            let c = style.foreground;
+            spans.push((
+                format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b),
+                text.to_string(),
+            ));
+        }
+    }
+    Some(spans)
+}
+
+#[derive(PartialEq, Eq, Props)]
+pub struct Props {
+    text: String,
+    // (token, name) for each user who may be mentioned in the text. tokens are replaced by a mention of the name
+    #[props(optional)]
+    mentions: Option<Vec<(String, String)>>,
+}
+
+// renders markdown. html in the text is not rendered
+#[allow(non_snake_case)]
+pub fn Markdown(cx: Scope<Props>) -> Element {
+    let nodes = replace_mentions(
+        parse(&cx.props.text),
+        cx.props.mentions.as_deref().unwrap_or_default(),
+    );
+
+    cx.render(rsx!(
+        div {
+            class: "markdown",
+            Nodes {
+                nodes: nodes,
+            }
+        }
+    ))
+}
+
+#[derive(PartialEq, Eq, Props)]
+struct NodesProps {
+    nodes: Vec<Node>,
+}
+
+#[allow(non_snake_case)]
+fn Nodes(cx: Scope<NodesProps>) -> Element {
+    cx.render(rsx!(cx.props.nodes.iter().map(|node| match node {
+        Node::Text(text) => rsx!("{text}"),
+        Node::Code(code) => rsx!(code { class: "md-code", "{code}" }),
+        Node::CodeBlock { lang, code } => rsx!(CodeBlock {
+            lang: lang.clone(),
+            code: code.clone(),
+        }),
+        Node::Link { url, content } => rsx!(Link {
+            url: url.clone(),
+            content: content.clone(),
+        }),
+        Node::SoftBreak | Node::HardBreak => rsx!(br {}),
+        Node::Rule => rsx!(hr {}),
+        Node::Mention(name) => rsx!(span { class: "md-mention", "{name}" }),
+        Node::Container { kind, content } => {
+            let content = content.clone();
+            match kind {
+                Container::Paragraph => rsx!(p { class: "md-paragraph", Nodes { nodes: content } }),
+                Container::Heading(level) => rsx!(p {
+                    class: "md-heading md-heading-{level}",
+                    Nodes { nodes: content }
+                }),
+                Container::BlockQuote => rsx!(blockquote { Nodes { nodes: content } }),
+                Container::List(None) => rsx!(ul { Nodes { nodes: content } }),
+                Container::List(Some(start)) => rsx!(ol {
+                    start: "{start}",
+                    Nodes { nodes: content }
+                }),
+                Container::Item => rsx!(li { Nodes { nodes: content } }),
+                Container::Emphasis => rsx!(em { Nodes { nodes: content } }),
+                Container::Strong => rsx!(strong { Nodes { nodes: content } }),
+                Container::Strikethrough => rsx!(del { Nodes { nodes: content } }),
+                Container::Span => rsx!(span { Nodes { nodes: content } }),
+            }
+        }
+    })))
+}
+
+#[derive(PartialEq, Eq, Props)]
+struct CodeBlockProps {
+    lang: String,
+    code: String,
+}
+
+#[allow(non_snake_case)]
+fn CodeBlock(cx: Scope<CodeBlockProps>) -> Element {
+    let lang = &cx.props.lang;
+    let code = &cx.props.code;
+
+    cx.render(rsx!(
+        div {
+            class: "md-code-block",
+            (!lang.is_empty()).then(|| rsx!(
+                p {
+                    class: "md-code-lang",
+                    "{lang}"
+                }
+            )),
+            pre {
+                code {
+                    match highlight(code, lang) {
+                        Some(spans) => rsx!(spans.into_iter().map(|(color, text)| rsx!(
+                            span {
+                                style: "color: {color}",
+                                "{text}"
+                            }
+                        ))),
+                        None => rsx!("{code}"),
+                    }
+                }
+            }
+        }
+    ))
+}
+
+#[derive(PartialEq, Eq, Props)]
+struct LinkProps {
+    url: String,
+    content: Vec<Node>,
+}
+
+// links are opened in the browser once the user confirms
+#[allow(non_snake_case)]
+fn Link(cx: Scope<LinkProps>) -> Element {
+    let confirming = use_state(cx, || false);
+    let url = &cx.props.url;
+
+    cx.render(rsx!(
+        span {
+            class: "md-link-wrap",
+            a {
+                class: "md-link",
+                title: "{url}",
+                onclick: move |_| confirming.set(true),
+                Nodes {
+                    nodes: cx.props.content.clone(),
+                }
+            },
+            confirming.then(|| rsx!(
+                span {
+                    class: "md-link-confirm",
+                    p {
+                        get_local_text("messages.open-link")
+                    },
+                    p {
+                        class: "md-link-url",
+                        "{url}"
+                    },
+                    span {
+                        class: "md-link-controls",
+                        Button {
+                            small: true,
+                            text: get_local_text("messages.cancel"),
+                            appearance: Appearance::Secondary,
+                            onpress: move |_| confirming.set(false),
+                        },
+                        Button {
+                            small: true,
+                            text: get_local_text("messages.open"),
+                            appearance: Appearance::Primary,
+                            onpress: move |_| {
+                                confirming.set(false);
+                                let _ = open::that(&cx.props.url);
+                            },
+                        },
+                    }
+                }
+            ))
+        }
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_formatting() {
+        let nodes = parse("**bold** and `code`");
+        assert_eq!(
+            nodes,
+            vec![Node::Container {
+                kind: Container::Paragraph,
+                content: vec![
+                    Node::Container {
+                        kind: Container::Strong,
+                        content: vec![Node::Text("bold".into())],
+                    },
+                    Node::Text(" and ".into()),
+                    Node::Code("code".into()),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_html_is_text() {
+        let nodes = parse("<script>alert(1)</script>");
+        assert!(!format!("{nodes:?}").contains("Link"));
+        assert!(nodes.iter().all(|n| matches!(
+            n,
+            Node::Text(_)
+                | Node::Container {
+                    kind: Container::Paragraph,
+                    ..
+                }
+        )));
+    }
+
+    #[test]
+    fn test_parse_code_block() {
+        let nodes = parse("```rust\nfn main() {}\n```");
+        assert_eq!(
+            nodes,
+            vec![Node::CodeBlock {
+                lang: "rust".into(),
+                code: "fn main() {}\n".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unsafe_link_is_not_a_link() {
+        let nodes = parse("[click](javascript:alert(1))");
+        assert!(!format!("{nodes:?}").contains("Link"));
+    }
+
+    #[test]
+    fn test_replace_mentions() {
+        let mentions = vec![("<@did:key:a>".to_string(), "@*a_b`".to_string())];
+        let nodes = replace_mentions(parse("hi <@did:key:a>, see `<@did:key:a>`"), &mentions);
+        assert_eq!(
+            nodes,
+            vec![Node::Container {
+                kind: Container::Paragraph,
+                content: vec![
+                    Node::Text("hi ".into()),
+                    Node::Mention("@*a_b`".into()),
+                    Node::Text(", see ".into()),
+                    Node::Code("@*a_b`".into()),
+                ],
+            }]
+        );
+        // unknown tokens are left as they are
+        assert_eq!(
+            replace_mentions(vec![Node::Text("<@did:key:b>".into())], &mentions),
+            vec![Node::Text("<@did:key:b>".into())]
+        );
+    }
+
+    #[test]
+    fn test_split_urls() {
+        let nodes = split_urls("see https://satellite.im/docs.");
+        assert_eq!(
+            nodes,
+            vec![
+                Node::Text("see ".into()),
+                Node::Link {
+                    url: "https://satellite.im/docs".into(),
+                    content: vec![Node::Text("https://satellite.im/docs".into())],
+                },
+                Node::Text(".".into()),
+            ]
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use derive_more::Display;
 use dioxus::prelude::*;
+use shared::language::get_local_text;
+
+use crate::components::markdown::Markdown;
 
 #[derive(Eq, PartialEq, Clone, Copy, Display)]
 pub enum Order {
@@ -27,6 +30,10 @@ pub struct Props<'a> {
     #[props(optional)]
     with_text: Option<String>,
 
+    // An optional field that, if set, maps the mentions in the text to the names they're shown as. See `Markdown`.
+    #[props(optional)]
+    with_mentions: Option<Vec<(String, String)>>,
+
     // An optional field that, if set to true, will add a CSS class of "remote" to the div element.
     #[props(optional)]
     remote: Option<bool>,
@@ -36,6 +43,10 @@ pub struct Props<'a> {
     // If not set, the default value of Order::Last will be used.
     #[props(optional)]
     order: Option<Order>,
+
+    // An optional field that, if set to true, will show that the message was edited after it was sent.
+    #[props(optional)]
+    edited: Option<bool>,
 }
 
 #[allow(non_snake_case)]
@@ -44,6 +55,7 @@ pub fn Message<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     let loading = cx.props.loading.unwrap_or_default();
     let remote = cx.props.remote.unwrap_or_default();
     let order = cx.props.order.unwrap_or(Order::Last);
+    let edited = cx.props.edited.unwrap_or_default();
 
     cx.render(rsx! (
         div {
@@ -68,10 +80,19 @@ pub fn Message<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                     cx.props.with_content.as_ref(),
                 },
             )),
-            (cx.props.with_text.is_some()).then(|| rsx! (
-                p {
+            (!text.is_empty()).then(|| rsx! (
+                div {
                     class: "text",
-                    "{text}"
+                    Markdown {
+                        text: text.clone(),
+                        mentions: cx.props.with_mentions.clone().unwrap_or_default(),
+                    }
+                }
+            )),
+            edited.then(|| rsx! (
+                p {
+                    class: "edited",
+                    get_local_text("messages.edited")
                 }
             ))
         }
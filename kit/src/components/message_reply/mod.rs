@@ -1,6 +1,8 @@
 use derive_more::Display;
 use dioxus::prelude::*;
 
+use crate::components::markdown::Markdown;
+
 #[derive(Eq, PartialEq, Clone, Copy, Display)]
 pub enum Order {
     #[display(fmt = "message-first")]
@@ -22,6 +24,8 @@ pub struct Props<'a> {
     #[props(optional)]
     with_text: Option<String>,
     #[props(optional)]
+    with_mentions: Option<Vec<(String, String)>>,
+    #[props(optional)]
     remote: Option<bool>,
     #[props(optional)]
     remote_message: Option<bool>,
@@ -62,12 +66,14 @@ pub fn MessageReply<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                             "{prefix}"
                         },
                     )),
-                    p {
+                    div {
                         class: {
                             format_args!("text {}", if remote_message { "remote-text" } else { "" })
                         },
-
-                        "{text}"
+                        Markdown {
+                            text: text.clone(),
+                            mentions: cx.props.with_mentions.clone().unwrap_or_default(),
+                        }
                     }
                 }
             )),
@@ -4,6 +4,9 @@ use dioxus::prelude::*;
 pub struct Props<'a> {
     // Represents the image of the user who is typing
     user_image: Element<'a>,
+    // e.g. "Alice is typing"
+    #[props(optional)]
+    with_text: Option<String>,
 }
 
 #[allow(non_snake_case)]
@@ -18,7 +21,13 @@ pub fn MessageTyping<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                 div { class: "dot dot-1" },
                 div { class: "dot dot-2" },
                 div { class: "dot dot-3" }
-            }
+            },
+            cx.props.with_text.as_ref().map(|text| rsx!(
+                p {
+                    class: "message-typing-text",
+                    "{text}"
+                }
+            ))
         }
     ))
 }
@@ -14,6 +14,10 @@ pub mod message_group;
 pub mod message_reply;
 pub mod message_typing;
 
+pub mod markdown;
+
 pub mod file_embed;
 
 pub mod context_menu;
+
+pub mod emoji_picker;
@@ -27,6 +27,12 @@ pub struct Props<'a> {
     with_replying_to: Option<Element<'a>>,
     #[props(optional)]
     with_file_upload: Option<Element<'a>>,
+    // files which are being sent, rendered above the chatbar
+    #[props(optional)]
+    with_attachments: Option<Element<'a>>,
+    // e.g. the users which can be mentioned, rendered above the chatbar
+    #[props(optional)]
+    with_suggestions: Option<Element<'a>>,
     #[props(optional)]
     extensions: Option<Element<'a>>,
     #[props(optional)]
@@ -87,6 +93,18 @@ pub fn Chatbar<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
         div {
             class: "chatbar",
             cx.props.with_replying_to.as_ref(),
+            cx.props.with_attachments.as_ref().map(|attachments| rsx!(
+                div {
+                    class: "chatbar-attachments",
+                    attachments
+                }
+            )),
+            cx.props.with_suggestions.as_ref().map(|suggestions| rsx!(
+                div {
+                    class: "chatbar-suggestions",
+                    suggestions
+                }
+            )),
             cx.props.with_file_upload.as_ref(),
             // apologies for the crappy code.
             match &cx.props.reset {
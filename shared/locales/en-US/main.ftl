@@ -1,5 +1,6 @@
 uplink = Uplink
     .home = Home
+    .lock = Lock Uplink
     .add = Add
     .call = Call
     .video-call = Video Call
@@ -12,6 +13,7 @@ uplink = Uplink
     .clear-unreads = Clear Unreads
     .hide-chat = Hide Chat
     .search-placeholder = Search...
+    .search-no-results = No results
     .end = End
     .pre-release = Pre-release
     .username = Username
@@ -30,8 +32,69 @@ messages = Messages
     .replying = Replying to:
     .react = React
     .reply = Reply
+    .edit = Edit
+    .edited = (edited)
+    .new-messages = New messages
+    .delete = Delete
     .you = You
     .say-something-placeholder = Say Something...
+    .typing = { $others ->
+        [0] { $user } is typing
+        [1] { $user } and 1 other are typing
+       *[other] { $user } and { $others } others are typing
+    }
+    .failed = Not sent
+    .retry = Retry
+    .discard = Discard
+    .open-link = Open this link in your browser?
+    .notification-group-title = { $user } in { $group }
+    .notification-mention-title = { $user } mentioned you
+    .notification-group-mention-title = { $user } mentioned you in { $group }
+    .notification-hidden = Sent you a message
+    .notification-group-hidden = Sent a message
+    .notification-attachments = { $count ->
+        [one] Sent a file
+       *[other] Sent { $count } files
+    }
+    .open = Open
+    .cancel = Cancel
+
+group = Group
+    .create = New Group
+    .name-placeholder = Group name
+    .start = Start Group
+    .cancel = Cancel
+    .manage = Manage Group
+    .members = Members
+    .add-members = Add Friends
+
+chat-notifications = Notifications
+    .settings = Notification Settings
+    .notify-about = Notify me about
+    .all = All messages
+    .mentions-only = Mentions only
+    .mute = Mute
+    .unmute = Unmute
+    .mute-hour = 1 hour
+    .mute-8-hours = 8 hours
+    .mute-day = 24 hours
+    .mute-week = 1 week
+    .mute-forever = Until I unmute it
+    .muted = Muted
+    .muted-until = Muted until { $time }
+    .sounds = Play sounds for this chat
+
+emoji-picker = Emoji Picker
+    .search-placeholder = Search emoji
+    .recent = Recently Used
+    .smileys = Smileys & Emotion
+    .people = People & Body
+    .nature = Animals & Nature
+    .food = Food & Drink
+    .activities = Activities
+    .travel = Travel & Places
+    .objects = Objects
+    .symbols = Symbols
 
 favorites = Favorites
     .favorites = Favorites
@@ -56,8 +119,10 @@ friends = Friends
     .blocked = Blocked
     .accept = Accept
     .deny = Deny Request
+    .decline = Decline
     .request-sent = Friend Request Sent!
     .new_request = New friend request.
+    .request-received = { $user } sent a request.
     .copied-did = Copied ID to clipboard!
     .unblock = Unblock
 
@@ -65,6 +130,9 @@ files = Files
     .files = Files
     .new-folder = New Folder
     .upload = Upload
+    .upload-failed = Couldn't send { $file }.
+    .download-finished = Saved { $file }.
+    .download-failed = Couldn't save { $file }.
     .free-space = Free Space:
     .total-space = Total Space:
 
@@ -84,6 +152,8 @@ settings-profile = Profile Settings
     .change-avatar = Change avatar
     .edit-button = Edit
     .save-button = Save
+    .invalid-image = Couldn't use this image
+    .update-failed = Couldn't update your profile
 
 settings-general = General Settings
     .overlay = Uplink Overlay
@@ -97,6 +167,10 @@ settings-general = General Settings
     .theme-reset = Reset Theme
     .theme-reset-description = Reset the theme to the default.
     .theme-reset-cta = Clear Theme
+    .auto-away = Auto Away
+    .auto-away-description = Show others that you're away when you haven't used Uplink for a while.
+    .auto-away-never = Never
+    .auto-away-minutes = { $minutes } minutes
 
 settings-extensions = Extension Settings
     .placeholder = Placeholder
@@ -106,6 +180,35 @@ settings-privacy = Settings Privacy
     .backup-recovery-phrase = Backup Recovery Phrase
     .backup-phrase = Backup Phrase
     .backup-phrase-description = Back this phrase up! Along with your password this represents your account. If you lose it, we can't help you get it back.
+    .hide-phrase = Hide Phrase
+    .enter-pin = Enter your PIN to view your recovery phrase
+    .wrong-pin = Incorrect PIN.
+    .no-phrase = This account was created before recovery phrases were saved, so there is no phrase to show.
+    .change-pin = Change PIN
+    .change-pin-description = Choose a new PIN to encrypt your account on this device. You'll use it the next time you unlock Uplink.
+    .current-pin = Current PIN
+    .new-pin = New PIN
+    .confirm-pin = Confirm New PIN
+    .pin-mismatch = The new PINs don't match.
+    .save-pin = Save PIN
+    .pin-changed = Your PIN has been changed.
+    .change-pin-failed = Couldn't change your PIN. Your old PIN still works.
+    .cancel = Cancel
+    .auto-lock = Auto Lock
+    .auto-lock-description = Lock Uplink when you haven't used it for a while. Your PIN is needed to unlock it.
+    .auto-lock-never = Never
+    .auto-lock-minutes = { $minutes } minutes
+    .wipe-after = Erase After Failed Unlocks
+    .wipe-after-description = Delete your account from this device when this many incorrect PINs in a row are entered to unlock Uplink. PINs entered on this page count towards it, but only unlocking deletes the account. Without your recovery phrase, it can't be restored.
+    .wipe-after-never = Never
+    .wipe-after-attempts = { $attempts } attempts
+    .try-later = Too many incorrect PINs. Try again later.
+
+passphrase-strength = Strength
+    .weak = Weak
+    .fair = Fair
+    .good = Good
+    .strong = Strong
 
 settings-audio = Audio Settings 
     .call-timer = Call Timer
@@ -123,6 +226,19 @@ settings-files = Files Settings
     .open-sync-folder = Open Sync Folder
     .open-sync-folder-description = Open the folder where your files are synced to.
 
+presence = Presence
+    .online = Online
+    .away = Away
+    .busy = Busy
+    .invisible = Invisible
+    .lock = Lock Uplink
+
+notification-center = Notification Center
+    .notifications = Notifications
+    .mark-all-read = Mark all read
+    .clear = Clear
+    .empty = You're all caught up.
+
 settings-notifications = Notifications
     .notifications-description = Enable notifications for incoming calls, messages, and more.
     .enabled = Enabled
@@ -131,7 +247,26 @@ settings-notifications = Notifications
     .grant-permissions-description = Grant permissions to receive notifications.
     .friends-description = Enable notifications for friend requests.
     .messages-description = Enable notifications for new messages.
+    .message-previews = Message Previews
+    .message-previews-description = Show the text of new messages in notifications. Turn this off to keep your messages private.
     .settings-description = Enable notifications for updates and important alerts.
+    .do-not-disturb = Do Not Disturb
+    .do-not-disturb-description = Silence notifications and sounds, and appear busy to others. Unread counts still update.
+    .dnd-schedule = Scheduled Do Not Disturb
+    .dnd-schedule-description = Turn on do not disturb automatically at the same times every week.
+    .dnd-from = From
+    .dnd-from-description = When do not disturb starts.
+    .dnd-until = Until
+    .dnd-until-description = When do not disturb ends. If this is earlier than the start, it ends the next day.
+    .dnd-days = Days
+    .dnd-days-description = The days on which do not disturb starts.
+    .monday = Mon
+    .tuesday = Tue
+    .wednesday = Wed
+    .thursday = Thu
+    .friday = Fri
+    .saturday = Sat
+    .sunday = Sun
 
 settings-developer = Developer Settings 
     .developer-mode = Developer Mode
@@ -172,6 +307,29 @@ unlock = Unlock
     .warning2 = If you forget this password we cannot help you retrieve it.
     .enter-pin = Enter Pin
     .create-account = Create Account
+    .locked = Uplink is locked. Enter your PIN to continue.
+    .wrong-pin = Incorrect PIN.
+    .retry-in = Too many incorrect PINs. Try again in { $seconds ->
+        [one] 1 second
+       *[other] { $seconds } seconds
+    }.
 
 auth = Create Account 
     .enter-username = Enter Username
+    .overwrite-warning = An account already exists on this device. Creating a new one permanently deletes it, along with its messages and files. Without its recovery phrase, it can't be restored.
+    .overwrite-confirm = Delete and Create
+    .overwrite-cancel = Keep My Account
+    .account-wiped = Too many incorrect PINs were entered, so the account on this device was deleted, as set in Privacy settings. Create a new account or restore one from its recovery phrase.
+
+recovery = Recovery Phrase
+    .save-phrase = This is your recovery phrase. Write these words down in order and keep them somewhere safe. With your PIN, they can restore your account on any device.
+    .save-phrase-warning = You won't be shown this phrase again here. It can be viewed later in Privacy settings.
+    .saved-phrase = I've Saved It
+    .restore-account = Restore Account
+    .restore-description = Enter the 12 words of your recovery phrase and choose a PIN to protect the restored account.
+    .restore-warning = Restoring replaces any account currently on this device.
+    .overwrite-warning = An account already exists on this device. Restoring permanently deletes it, along with its messages and files, unless it's the account this phrase belongs to.
+    .overwrite-confirm = Delete and Restore
+    .enter-phrase = Enter Recovery Phrase
+    .restore-failed = Couldn't restore an account from that phrase. Check the words and try again.
+    .back = Back
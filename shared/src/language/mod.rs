@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+pub use fluent_templates::fluent_bundle::FluentValue;
 use fluent_templates::{once_cell::sync::Lazy, LanguageIdentifier, Loader};
 use unic_langid::langid;
 use warp::sync::RwLock;
@@ -45,3 +46,11 @@ pub fn get_available_languages() -> Vec<String> {
 pub fn get_local_text(text: &str) -> String {
     LOCALES.lookup(&APP_LANG.read().0, text).unwrap_or_default()
 }
+
+// for text with placeables, e.g. `{ $user } is typing`
+pub fn get_local_text_with_args(text: &str, args: Vec<(&str, FluentValue)>) -> String {
+    let args: HashMap<&str, FluentValue> = args.into_iter().collect();
+    LOCALES
+        .lookup_with_args(&APP_LANG.read().0, text, &args)
+        .unwrap_or_default()
+}
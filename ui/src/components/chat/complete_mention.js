@@ -0,0 +1,7 @@
+// replaces the text of the chatbar. the input event lets the chatbar pick up the new value
+const input = document.querySelector(".chatbar input")
+if (input) {
+  input.value = $TEXT
+  input.dispatchEvent(new Event("input", { bubbles: true }))
+  input.focus()
+}
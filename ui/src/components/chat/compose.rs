@@ -1,22 +1,30 @@
 use std::{
+    path::PathBuf,
     rc::Rc,
     time::{Duration, Instant},
 };
 
+use chrono::Utc;
 use dioxus::prelude::*;
 
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     components::{
         context_menu::{ContextItem, ContextMenu},
+        emoji_picker::{EmojiPicker, Reactions},
+        file_embed::FileEmbed,
         indicator::{Platform, Status},
         message::{Message, Order},
+        message_divider::MessageDivider,
         message_group::{MessageGroup, MessageGroupSkeletal},
+        message_reply::MessageReply,
+        message_typing::MessageTyping,
         user_image::UserImage,
         user_image_group::UserImageGroup,
     },
     elements::{
         button::Button,
+        input::Input,
         tooltip::{ArrowPosition, Tooltip},
         Appearance,
     },
@@ -28,6 +36,7 @@ use kit::{
 };
 
 use dioxus_desktop::{use_eval, use_window};
+use rfd::AsyncFileDialog;
 use shared::language::get_local_text;
 use uuid::Uuid;
 use warp::{
@@ -37,16 +46,28 @@ use warp::{
 
 use crate::{
     components::media::player::MediaPlayer,
-    state::{self, Action, Chat, Identity, State},
+    state::{
+        self,
+        chats::{self, PendingMessage, PendingStatus},
+        Action, Chat, Identity, State,
+    },
     utils::{
         build_participants, build_user_from_identity, convert_status,
-        format_timestamp::format_timestamp_timeago,
+        format_timestamp::format_timestamp_timeago, get_file_kind, mentions,
     },
-    warp_runner::{RayGunCmd, WarpCmd},
-    STATIC_ARGS, WARP_CMD_CH,
+    warp_runner::{self, ui_adapter::TransferEvent, RayGunCmd, WarpCmd, WarpEvent},
+    STATIC_ARGS,
+};
+
+use super::{
+    build_typing_text,
+    group::EditGroup,
+    notification_settings::{get_muted_text, ChatNotificationSettings},
+    sidebar::build_participants_names,
 };
 
-use super::sidebar::build_participants_names;
+// the most participants suggested when typing a mention
+const MAX_MENTION_SUGGESTIONS: usize = 5;
 
 struct ComposeData {
     active_chat: Chat,
@@ -79,6 +100,11 @@ pub fn Compose(cx: Scope) -> Element {
     let state = use_shared_state::<State>(cx)?;
     let data = get_compose_data(cx);
     let data2 = data.clone();
+    let show_edit_group = use_state(cx, || false);
+    let active_chat = data.as_ref().map(|d| d.active_chat.clone());
+    let group_chat = active_chat.clone().filter(|c| c.is_group());
+    let chat_notifications = active_chat.as_ref().map(|c| c.notifications);
+    let show_notifications = state.read().ui.show_chat_notifications;
 
     cx.render(rsx!(
         div {
@@ -90,7 +116,37 @@ pub fn Compose(cx: Scope) -> Element {
                     let current = state.read().ui.sidebar_hidden;
                     state.write().mutate(Action::SidebarHidden(!current));
                 },
-                controls: cx.render(rsx!(get_controls{data: data2})),
+                controls: cx.render(rsx!(
+                    chat_notifications.map(|notifications| rsx!(
+                        Button {
+                            icon: if notifications.is_muted() { Icon::BellSlash } else { Icon::Bell },
+                            aria_label: "chat-notifications-button".into(),
+                            appearance: if show_notifications { Appearance::Primary } else { Appearance::Secondary },
+                            tooltip: cx.render(rsx!(Tooltip {
+                                arrow_position: ArrowPosition::Top,
+                                text: get_muted_text(&notifications).unwrap_or_else(|| get_local_text("chat-notifications.settings")),
+                            })),
+                            onpress: move |_| {
+                                state.write().mutate(Action::ShowChatNotifications(!show_notifications));
+                            }
+                        }
+                    )),
+                    group_chat.is_some().then(|| rsx!(
+                        Button {
+                            icon: Icon::UserGroup,
+                            aria_label: "manage-group-button".into(),
+                            appearance: if *show_edit_group.get() { Appearance::Primary } else { Appearance::Secondary },
+                            tooltip: cx.render(rsx!(Tooltip {
+                                arrow_position: ArrowPosition::Top,
+                                text: get_local_text("group.manage"),
+                            })),
+                            onpress: move |_| {
+                                show_edit_group.set(!show_edit_group.get());
+                            }
+                        }
+                    )),
+                    get_controls{data: data2}
+                )),
                 get_topbar_children{data: data.clone()}
             },
             data.as_ref().and_then(|data| data.active_media.then(|| rsx!(
@@ -103,6 +159,18 @@ pub fn Compose(cx: Scope) -> Element {
                     end_text: get_local_text("uplink.end"),
                 },
             ))),
+            active_chat.filter(|_| show_notifications).map(|chat| rsx!(
+                ChatNotificationSettings {
+                    key: "{chat.id}-notifications",
+                    chat: chat,
+                }
+            )),
+            group_chat.filter(|_| *show_edit_group.get()).map(|chat| rsx!(
+                EditGroup {
+                    key: "{chat.id}",
+                    chat: chat,
+                }
+            )),
             get_messages{data: data.clone()},
             get_chatbar{data: data}
         }
@@ -124,21 +192,20 @@ fn get_compose_data(cx: Scope) -> Option<Rc<ComposeData>> {
     };
     let message_groups = s.get_sort_messages(&active_chat);
     let other_participants = s.get_without_me(&active_chat.participants);
+    // everyone else may have left a group
     let active_participant = other_participants
         .first()
         .cloned()
-        .expect("chat should have at least 2 participants");
+        .unwrap_or_else(|| s.account.identity.clone());
     let subtext = active_participant.status_message().unwrap_or_default();
     let is_favorite = s.is_favorite(&active_chat);
     let first_image = active_participant.graphics().profile_picture();
-    let other_participants_names = build_participants_names(&other_participants);
+    let other_participants_names = active_chat
+        .name
+        .clone()
+        .unwrap_or_else(|| build_participants_names(&other_participants));
     let active_media = Some(active_chat.id) == s.chats.active_media;
 
-    // TODO: Pending new message divider implementation
-    // let _new_message_text = LOCALES
-    //     .lookup(&*APP_LANG.read(), "messages.new")
-    //     .unwrap_or_default();
-
     let platform = match active_participant.platform() {
         warp::multipass::identity::Platform::Desktop => Platform::Desktop,
         warp::multipass::identity::Platform::Mobile => Platform::Mobile,
@@ -232,7 +299,7 @@ fn get_topbar_children(cx: Scope<ComposeProps>) -> Element {
     let subtext = data.as_ref().map(|x| x.subtext.clone()).unwrap_or_default();
     cx.render(rsx!(
         if let Some(data) = data {
-            if data.other_participants.len() < 2 {rsx! (
+            if !data.active_chat.is_group() {rsx! (
                 UserImage {
                     loading: false,
                     platform: data.platform,
@@ -284,6 +351,36 @@ fn get_topbar_children(cx: Scope<ComposeProps>) -> Element {
 enum MessagesCommand {
     // contains the emoji reaction
     React((raygun::Message, String)),
+    Edit {
+        conv_id: Uuid,
+        message_id: Uuid,
+        msg: Vec<String>,
+    },
+    Delete {
+        conv_id: Uuid,
+        message_id: Uuid,
+    },
+    // asks where to save an attachment, then saves it there
+    DownloadAttachment {
+        conv_id: Uuid,
+        message_id: Uuid,
+        file: String,
+    },
+    // fetch a page of messages which come before the window. None fetches the newest page
+    FetchOlder {
+        conv_id: Uuid,
+        before: Option<Uuid>,
+    },
+    // fetch a page of messages which come after the window
+    FetchNewer {
+        conv_id: Uuid,
+        after: Uuid,
+    },
+    // load the page around a message if it isn't in the window, then stop jumping to it
+    JumpTo {
+        conv_id: Uuid,
+        message_id: Uuid,
+    },
 }
 
 fn get_messages(cx: Scope<ComposeProps>) -> Element {
@@ -291,18 +388,163 @@ fn get_messages(cx: Scope<ComposeProps>) -> Element {
     let state = use_shared_state::<State>(cx)?;
     let user = state.read().account.identity.did_key();
 
-    let script = include_str!("./script.js");
-    use_eval(cx)(script.to_string());
+    let eval = use_eval(cx);
+    // (chat id, id of the newest message) as of the last render
+    let last_seen: &UseRef<Option<(Uuid, Option<Uuid>)>> = use_ref(cx, || None);
+
+    // set when a page of newer messages was added, so that the chat doesn't scroll to the bottom
+    let keep_scroll = use_ref(cx, || false);
 
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<MessagesCommand>| {
-        //to_owned![];
+        to_owned![state, keep_scroll];
         async move {
+            // the last page requested. scrolling to either end can request the same page more than once
+            // (conversation id, id of the message the page was fetched from)
+            let mut last_fetch: Option<(Uuid, Option<Uuid>)> = None;
+            // the message whose page is being loaded. every render sends JumpTo until it's in the window
+            let mut loading_jump: Option<Uuid> = None;
             while let Some(cmd) = rx.next().await {
                 match cmd {
+                    MessagesCommand::JumpTo {
+                        conv_id,
+                        message_id,
+                    } => {
+                        let in_window = match state.read().chats.all.get(&conv_id) {
+                            Some(chat) if chat.jump_to == Some(message_id) => {
+                                chat.messages.iter().any(|m| m.id() == message_id)
+                            }
+                            // already handled
+                            _ => continue,
+                        };
+                        // the render which sent this scrolled to it
+                        if in_window {
+                            loading_jump = None;
+                            if let Some(chat) = state.write_silent().chats.all.get_mut(&conv_id) {
+                                chat.jump_to = None;
+                            }
+                            continue;
+                        }
+                        if loading_jump == Some(message_id) {
+                            continue;
+                        }
+                        loading_jump = Some(message_id);
+                        let limit = chats::MESSAGE_PAGE_SIZE;
+                        match warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::FetchMessagesAround {
+                                conv_id,
+                                message_id,
+                                limit,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            Ok((messages, more_before, more_after)) => {
+                                state.write().mutate(Action::ReplaceMessages(
+                                    conv_id,
+                                    messages,
+                                    more_before,
+                                    more_after,
+                                ));
+                            }
+                            // the message was deleted
+                            Err(e) => {
+                                log::error!("failed to fetch messages: {}", e);
+                                loading_jump = None;
+                                if let Some(chat) = state.write_silent().chats.all.get_mut(&conv_id)
+                                {
+                                    chat.jump_to = None;
+                                }
+                            }
+                        }
+                    }
+                    MessagesCommand::FetchOlder { conv_id, before } => {
+                        if last_fetch == Some((conv_id, before)) {
+                            continue;
+                        }
+                        last_fetch = Some((conv_id, before));
+                        let limit = chats::MESSAGE_PAGE_SIZE;
+                        match warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::FetchMessages {
+                                conv_id,
+                                before,
+                                limit,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            Ok(messages) => {
+                                let more = messages.len() == limit;
+                                state.write().mutate(Action::PrependMessages(
+                                    conv_id, before, messages, more,
+                                ));
+                            }
+                            Err(e) => {
+                                log::error!("failed to fetch messages: {}", e);
+                                // allow the page to be requested again
+                                last_fetch = None;
+                            }
+                        }
+                    }
+                    MessagesCommand::FetchNewer { conv_id, after } => {
+                        if last_fetch == Some((conv_id, Some(after))) {
+                            continue;
+                        }
+                        last_fetch = Some((conv_id, Some(after)));
+                        let limit = chats::MESSAGE_PAGE_SIZE;
+                        match warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::FetchNewerMessages {
+                                conv_id,
+                                after,
+                                limit,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            Ok(messages) => {
+                                let more = messages.len() == limit;
+                                *keep_scroll.write_silent() = true;
+                                state
+                                    .write()
+                                    .mutate(Action::AppendMessages(conv_id, after, messages, more));
+                            }
+                            Err(e) => {
+                                log::error!("failed to fetch messages: {}", e);
+                                last_fetch = None;
+                            }
+                        }
+                    }
+                    MessagesCommand::DownloadAttachment {
+                        conv_id,
+                        message_id,
+                        file,
+                    } => {
+                        let path = match AsyncFileDialog::new()
+                            .set_file_name(&file)
+                            .save_file()
+                            .await
+                        {
+                            Some(handle) => handle.path().to_path_buf(),
+                            None => continue,
+                        };
+                        // warp_runner responds once the download has started. a TransferEvent reports when it's done
+                        if let Err(e) = warp_runner::send_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::DownloadAttachment {
+                                conv_id,
+                                message_id,
+                                file,
+                                path,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            log::error!("failed to download attachment: {}", e);
+                        }
+                    }
                     MessagesCommand::React((message, emoji)) => {
-                        let warp_cmd_tx = WARP_CMD_CH.tx.clone();
-                        let (tx, rx) = futures::channel::oneshot::channel();
-
                         let mut reactions = message.reactions();
                         reactions.retain(|x| x.users().contains(&user));
                         reactions.retain(|x| x.emoji().eq(&emoji));
@@ -311,20 +553,52 @@ fn get_messages(cx: Scope<ComposeProps>) -> Element {
                         } else {
                             ReactionState::Remove
                         };
-                        if let Err(e) = warp_cmd_tx.send(WarpCmd::RayGun(RayGunCmd::React {
-                            conversation_id: message.conversation_id(),
-                            message_id: message.id(),
-                            reaction_state,
-                            emoji,
-                            rsp: tx,
-                        })) {
-                            log::error!("failed to send warp command: {}", e);
-                            continue;
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::React {
+                                conversation_id: message.conversation_id(),
+                                message_id: message.id(),
+                                reaction_state,
+                                emoji,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            log::error!("failed to add/remove reaction: {}", e);
                         }
-
-                        let res = rx.await.expect("command canceled");
-                        if res.is_err() {
-                            // failed to add/remove reaction
+                    }
+                    MessagesCommand::Edit {
+                        conv_id,
+                        message_id,
+                        msg,
+                    } => {
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::EditMessage {
+                                conv_id,
+                                message_id,
+                                msg,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            log::error!("failed to edit message: {}", e);
+                        }
+                    }
+                    MessagesCommand::Delete {
+                        conv_id,
+                        message_id,
+                    } => {
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::DeleteMessage {
+                                conv_id,
+                                message_id,
+                                rsp,
+                            })
+                        })
+                        .await
+                        {
+                            log::error!("failed to delete message: {}", e);
                         }
                     }
                 }
@@ -332,6 +606,12 @@ fn get_messages(cx: Scope<ComposeProps>) -> Element {
         }
     });
 
+    let my_did = state.read().account.identity.did_key();
+    // the id of the message being edited, if any
+    let editing: &UseState<Option<Uuid>> = use_state(cx, || None);
+    // the id of the message the emoji picker is open for, if any
+    let reacting_to: &UseState<Option<Uuid>> = use_state(cx, || None);
+    let recent_emojis = state.read().ui.recent_emojis.clone();
     let data = match &cx.props.data {
         Some(d) => d.clone(),
         None => {
@@ -345,13 +625,70 @@ fn get_messages(cx: Scope<ComposeProps>) -> Element {
         }
     };
 
+    let conv_id = data.active_chat.id;
+    let older_cursor = data.active_chat.older_cursor(&state.read().chats.outbox);
+    let newer_cursor = data.active_chat.newer_cursor();
+    // only jump to the bottom when new messages arrive or the chat changes. loading older messages keeps the scroll position
+    // opening a chat with unread messages scrolls to the "new messages" divider instead
+    let newest = data.active_chat.messages.back().map(|m| m.id());
+    let first_unread = data.active_chat.first_unread;
+    let last = *last_seen.read();
+    // a message picked from the search results or a notification. the coroutine loads the page around it if needed
+    if let Some(message_id) = data.active_chat.jump_to {
+        if data
+            .active_chat
+            .messages
+            .iter()
+            .any(|m| m.id() == message_id)
+        {
+            eval(
+                include_str!("./scroll_to_message.js")
+                    .replace("$MESSAGE_ID", &message_id.to_string()),
+            );
+            *last_seen.write_silent() = Some((conv_id, newest));
+        }
+        ch.send(MessagesCommand::JumpTo {
+            conv_id,
+            message_id,
+        });
+    } else if std::mem::take(&mut *keep_scroll.write_silent()) {
+        *last_seen.write_silent() = Some((conv_id, newest));
+    } else if last != Some((conv_id, newest)) {
+        let chat_changed = last.map(|(id, _)| id != conv_id).unwrap_or(true);
+        if chat_changed && first_unread.is_some() {
+            eval(include_str!("./scroll_to_unread.js").to_string());
+        } else {
+            eval(include_str!("./script.js").to_string());
+        }
+        *last_seen.write_silent() = Some((conv_id, newest));
+    }
+    // sending a message while reading older ones starts the window over from the new message. refill it
+    if data.active_chat.has_older_messages()
+        && data.active_chat.messages.len() < chats::MESSAGE_PAGE_SIZE
+    {
+        ch.send(MessagesCommand::FetchOlder {
+            conv_id,
+            before: older_cursor,
+        });
+    }
+    eval(include_str!("./load_older_messages.js").to_string());
+
     cx.render(rsx!(
         div {
             id: "messages",
+            // renders below the messages
+            newer_cursor.map(|after| rsx!(
+                div {
+                    id: "load-newer-messages",
+                    onclick: move |_| ch.send(MessagesCommand::FetchNewer { conv_id, after }),
+                    MessageGroupSkeletal { alt: true },
+                }
+            )),
             div {
                 data.message_groups.iter().map(|group| {
                     let messages = &group.messages;
                     let active_chat = data.active_chat.clone();
+                    let participants = &data.active_chat.participants;
                     let last_message = messages.last().unwrap().message.clone();
                     let sender = state.read().get_friend_identity(&group.sender);
                     let active_language = state.read().settings.language.clone();
@@ -361,8 +698,22 @@ fn get_messages(cx: Scope<ComposeProps>) -> Element {
                         _ => Platform::Headless //TODO: Unknown
                     };
                     let status = convert_status(&sender.identity_status());
+                    // groups are split at the first unread message, so the divider renders right above it
+                    let first_unread_date = messages
+                        .first()
+                        .filter(|m| Some(m.message.id()) == first_unread)
+                        .map(|m| m.message.date());
 
                     rsx!(
+                        first_unread_date.map(|date| rsx!(
+                            div {
+                                id: "unread-divider",
+                                MessageDivider {
+                                    text: get_local_text("messages.new-messages"),
+                                    timestamp: (Utc::now() - date).to_std().unwrap_or_default(),
+                                }
+                            }
+                        )),
                         MessageGroup {
                             user_image: cx.render(rsx!(
                                 UserImage {
@@ -376,41 +727,211 @@ fn get_messages(cx: Scope<ComposeProps>) -> Element {
                             messages.iter().map(|grouped_message| {
                                 let message = grouped_message.message.clone();
                                 let message2 = message.clone();
+                                let message3 = message.clone();
                                 let reply_message = grouped_message.message.clone();
                                 let active_chat = active_chat.clone();
+                                let chat_participants = active_chat.participants.clone();
+                                let is_mine = message.sender() == my_did;
+                                let is_editing = *editing.get() == Some(message.id());
+                                let is_reacting = *reacting_to.get() == Some(message.id());
+                                // (emoji, count, whether the user reacted)
+                                let reactions = message
+                                    .reactions()
+                                    .iter()
+                                    .map(|r| (r.emoji(), r.users().len(), r.users().contains(&my_did)))
+                                    .collect::<Vec<_>>();
+                                let is_edited = message.modified().is_some();
+                                let (conv_id, message_id) = (message.conversation_id(), message.id());
+                                let pending_status = state.read().chats.pending_status(message_id);
+                                let is_failed = pending_status == Some(PendingStatus::Failed);
+                                // the message being replied to is only shown if it has been loaded
+                                let replied_to = message
+                                    .replied()
+                                    .and_then(|id| active_chat.messages.iter().find(|m| m.id() == id).cloned());
                                 rsx! (
                                     ContextMenu {
                                         id: format!("message-{}", message.id()),
                                         items: cx.render(rsx!(
-                                            ContextItem {
-                                                icon: Icon::ArrowLongLeft,
-                                                text: get_local_text("messages.reply"),
-                                                onpress: move |_| {
-                                                    state.write().mutate(Action::StartReplying(active_chat.clone(), reply_message.clone()));
+                                            is_failed.then(|| rsx!(
+                                                ContextItem {
+                                                    icon: Icon::ArrowPath,
+                                                    text: get_local_text("messages.retry"),
+                                                    onpress: move |_| {
+                                                        state.write().mutate(Action::SetPendingStatus(message_id, PendingStatus::Queued));
+                                                    }
+                                                },
+                                                ContextItem {
+                                                    icon: Icon::Trash,
+                                                    text: get_local_text("messages.discard"),
+                                                    danger: true,
+                                                    onpress: move |_| {
+                                                        state.write().mutate(Action::DiscardMessage(message_id));
+                                                    }
+                                                },
+                                            )),
+                                            // unconfirmed messages can't be replied to or changed yet
+                                            pending_status.is_none().then(|| rsx!(
+                                                ContextItem {
+                                                    icon: Icon::ArrowLongLeft,
+                                                    text: get_local_text("messages.reply"),
+                                                    onpress: move |_| {
+                                                        state.write().mutate(Action::StartReplying(active_chat.clone(), reply_message.clone()));
+                                                    }
+                                                },
+                                                ContextItem {
+                                                    icon: Icon::FaceSmile,
+                                                    text: get_local_text("messages.react"),
+                                                    onpress: move |_| {
+                                                        reacting_to.set(Some(message_id));
+                                                    }
+                                                },
+                                                is_mine.then(|| rsx!(
+                                                    ContextItem {
+                                                        icon: Icon::PencilSquare,
+                                                        text: get_local_text("messages.edit"),
+                                                        onpress: move |_| {
+                                                            editing.set(Some(message_id));
+                                                        }
+                                                    },
+                                                    ContextItem {
+                                                        icon: Icon::Trash,
+                                                        text: get_local_text("messages.delete"),
+                                                        danger: true,
+                                                        onpress: move |_| {
+                                                            ch.send(MessagesCommand::Delete { conv_id, message_id });
+                                                        }
+                                                    },
+                                                )),
+                                            )),
+                                        )),
+                                        replied_to.map(|parent| {
+                                            let parent_sender = state.read().get_friend_identity(&parent.sender());
+                                            let is_remote_parent = parent.sender() != my_did;
+                                            let (platform, status) = get_platform_and_status(Some(&parent_sender));
+                                            rsx!(
+                                                MessageReply {
+                                                    remote: group.remote,
+                                                    remote_message: is_remote_parent,
+                                                    with_prefix: if is_remote_parent { parent_sender.username() } else { get_local_text("messages.you") },
+                                                    with_text: parent.value().join("\n"),
+                                                    with_mentions: mentions::mentioned_names(&parent.value().join("\n"), participants),
+                                                    user_image: cx.render(rsx!(
+                                                        UserImage {
+                                                            platform: platform,
+                                                            status: status,
+                                                        }
+                                                    )),
                                                 }
-                                            },
-                                            ContextItem {
-                                                icon: Icon::FaceSmile,
-                                                text: get_local_text("messages.react"),
-                                                //TODO: let the user pick a reaction
-                                                onpress: move |_| {
-                                                      // using "like" for now
-                                                    ch.send(MessagesCommand::React((message2.clone(), "👍".into())));
+                                            )
+                                        }),
+                                        if is_editing {rsx!(
+                                            Message {
+                                                remote: group.remote,
+                                                order: if grouped_message.is_first { Order::First } else if grouped_message.is_last { Order::Last } else { Order::Middle },
+                                                with_content: cx.render(rsx!(
+                                                    Input {
+                                                        focus: true,
+                                                        placeholder: get_local_text("messages.say-something-placeholder"),
+                                                        default_text: mentions::decode_mentions(&message.value().join("\n"), participants, |m| m.to_string()),
+                                                        onreturn: move |(val, _): (String, bool)| {
+                                                            let msg = val.lines().map(|x| x.to_string()).collect::<Vec<String>>();
+                                                            let msg = mentions::encode_mentions(msg, &chat_participants);
+                                                            editing.set(None);
+                                                            if msg.iter().any(|line| !line.trim().is_empty()) {
+                                                                ch.send(MessagesCommand::Edit { conv_id, message_id, msg });
+                                                            }
+                                                        }
+                                                    }
+                                                )),
+                                            }
+                                        )} else {rsx!(
+                                            Message {
+                                                remote: group.remote,
+                                                loading: pending_status.is_some() && !is_failed,
+                                                with_text: message.value().join("\n"),
+                                                with_mentions: mentions::mentioned_names(&message.value().join("\n"), participants),
+                                                with_content: cx.render(rsx!(
+                                                    message.attachments().into_iter().map(|file| {
+                                                        let name = file.name();
+                                                        rsx!(
+                                                            FileEmbed {
+                                                                key: "{name}",
+                                                                filename: name.clone(),
+                                                                filesize: file.size() as u64,
+                                                                kind: get_file_kind(&name),
+                                                                icon: Icon::Document,
+                                                                remote: group.remote,
+                                                                on_download: move |_| {
+                                                                    ch.send(MessagesCommand::DownloadAttachment { conv_id, message_id, file: name.clone() });
+                                                                }
+                                                            }
+                                                        )
+                                                    })
+                                                )),
+                                                edited: is_edited,
+                                                order: if grouped_message.is_first { Order::First } else if grouped_message.is_last { Order::Last } else { Order::Middle },
+                                            }
+                                        )},
+                                        is_failed.then(|| rsx!(
+                                            div {
+                                                class: "message-failed",
+                                                aria_label: "message-failed",
+                                                p {
+                                                    get_local_text("messages.failed")
+                                                },
+                                                Button {
+                                                    small: true,
+                                                    text: get_local_text("messages.retry"),
+                                                    appearance: Appearance::Secondary,
+                                                    onpress: move |_| {
+                                                        state.write().mutate(Action::SetPendingStatus(message_id, PendingStatus::Queued));
+                                                    }
+                                                },
+                                                Button {
+                                                    small: true,
+                                                    text: get_local_text("messages.discard"),
+                                                    appearance: Appearance::Danger,
+                                                    onpress: move |_| {
+                                                        state.write().mutate(Action::DiscardMessage(message_id));
+                                                    }
+                                                },
+                                            }
+                                        )),
+                                        (!reactions.is_empty()).then(|| rsx!(
+                                            Reactions {
+                                                reactions: reactions,
+                                                remote: group.remote,
+                                                onpress: move |emoji: String| {
+                                                    ch.send(MessagesCommand::React((message2.clone(), emoji)));
                                                 }
-                                            },
+                                            }
                                         )),
-                                        Message {
-                                            remote: group.remote,
-                                            with_text: message.value().join("\n"),
-                                            order: if grouped_message.is_first { Order::First } else if grouped_message.is_last { Order::Last } else { Order::Middle },
+                                    },
+                                    is_reacting.then(|| rsx!(
+                                        EmojiPicker {
+                                            recents: recent_emojis.clone(),
+                                            onselect: move |emoji: String| {
+                                                reacting_to.set(None);
+                                                state.write().mutate(Action::EmojiUsed(emoji.clone()));
+                                                ch.send(MessagesCommand::React((message3.clone(), emoji)));
+                                            },
+                                            onclose: move |_| reacting_to.set(None),
                                         }
-                                    }
+                                    )),
                                 )
                             })
                         }
                     )
                 })
-            }
+            },
+            // #messages is laid out in reverse, so this renders above the messages
+            data.active_chat.has_older_messages().then(|| rsx!(
+                div {
+                    id: "load-older-messages",
+                    onclick: move |_| ch.send(MessagesCommand::FetchOlder { conv_id, before: older_cursor }),
+                    MessageGroupSkeletal {},
+                }
+            )),
         },
     ))
 }
@@ -440,42 +961,66 @@ fn get_chatbar(cx: Scope<ComposeProps>) -> Element {
     let should_clear_input = use_state(cx, || false);
     let active_chat_id = data.as_ref().map(|d| d.active_chat.id);
 
-    // todo: use this to render the typing indicator
-    let _users_typing = active_chat_id
-        .and_then(|id| state.read().chats.all.get(&id).cloned())
-        .map(|chat| {
-            chat.participants
-                .iter()
-                .filter(|x| chat.typing_indicator.contains_key(&x.did_key()))
-                .map(|x| x.username())
-                .collect::<Vec<_>>()
-        });
-
-    //println!("active chat: {:?}", &active_chat_id);
-    //println!("users typing: {:?}", &users_typing);
+    let users_typing = active_chat_id
+        .and_then(|id| {
+            state.read().chats.all.get(&id).map(|chat| {
+                chat.participants_typing()
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+        })
+        .unwrap_or_default();
+    let typing_text = build_typing_text(
+        &users_typing
+            .iter()
+            .map(|x| x.username())
+            .collect::<Vec<_>>(),
+    );
 
-    let msg_ch = use_coroutine(cx, |mut rx: UnboundedReceiver<(Vec<String>, Uuid)>| {
-        //to_owned![];
+    // asks which files to attach, then sends them to the given chat
+    let upload_ch = use_coroutine(cx, |mut rx: UnboundedReceiver<Uuid>| {
+        to_owned![state];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
-            while let Some((msg, conv_id)) = rx.next().await {
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                if let Err(e) = warp_cmd_tx.send(WarpCmd::RayGun(RayGunCmd::SendMessage {
-                    conv_id,
-                    msg,
-                    rsp: tx,
-                })) {
-                    log::error!("failed to send warp command: {}", e);
+            while let Some(conv_id) = rx.next().await {
+                let files: Vec<PathBuf> =
+                    match AsyncFileDialog::new().set_directory(".").pick_files().await {
+                        Some(handles) => handles.iter().map(|h| h.path().to_path_buf()).collect(),
+                        None => continue,
+                    };
+                if STATIC_ARGS.use_mock {
+                    log::warn!("attachments can't be sent when using mock data");
                     continue;
                 }
-
-                let rsp = rx.await.expect("command canceled");
+                // each file is shown in the chatbar until warp_runner reports that the upload is done
+                state
+                    .write()
+                    .mutate(Action::AddUploads(conv_id, files.clone()));
+                let rsp = warp_runner::send_cmd(|rsp| {
+                    WarpCmd::RayGun(RayGunCmd::SendAttachments {
+                        conv_id,
+                        files: files.clone(),
+                        msg: vec![],
+                        rsp,
+                    })
+                })
+                .await;
                 if let Err(e) = rsp {
-                    log::error!("failed to send message: {}", e);
+                    log::error!("failed to send attachments: {}", e);
+                    state.write().process_warp_event(WarpEvent::Transfer(
+                        TransferEvent::Uploaded {
+                            conversation_id: conv_id,
+                            files,
+                            result: Err(warp::error::Error::Other),
+                        },
+                    ));
                 }
             }
         }
     });
+    let pending_uploads = active_chat_id
+        .and_then(|id| state.read().chats.all.get(&id).map(|c| c.uploads.clone()))
+        .unwrap_or_default();
 
     // typing indicator notes
     // consider side A, the local side, and side B, the remote side
@@ -489,22 +1034,18 @@ fn get_chatbar(cx: Scope<ComposeProps>) -> Element {
         // to_owned![];
         async move {
             let mut typing_info: Option<TypingInfo> = None;
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
 
             let send_typing_indicator = |conv_id| async move {
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
                 let event = raygun::MessageEvent::Typing;
-                if let Err(e) = warp_cmd_tx.send(WarpCmd::RayGun(RayGunCmd::SendEvent {
-                    conv_id,
-                    event,
-                    rsp: tx,
-                })) {
-                    log::error!("failed to send warp command: {}", e);
-                    // return from the closure
-                    return;
-                }
-                let rsp = rx.await.expect("command canceled");
-                if let Err(e) = rsp {
+                if let Err(e) = warp_runner::run_cmd(|rsp| {
+                    WarpCmd::RayGun(RayGunCmd::SendEvent {
+                        conv_id,
+                        event,
+                        rsp,
+                    })
+                })
+                .await
+                {
                     log::error!("failed to send typing indicator: {}", e);
                 }
             };
@@ -565,44 +1106,64 @@ fn get_chatbar(cx: Scope<ComposeProps>) -> Element {
     let msg_valid =
         |msg: &[String]| !msg.is_empty() && msg.iter().any(|line| !line.trim().is_empty());
 
-    cx.render(rsx!(Chatbar {
-        loading: is_loading,
-        placeholder: get_local_text("messages.say-something-placeholder"),
-        reset: should_clear_input.clone(),
-        onchange: move |v: String| {
-            *input.write_silent() = v.lines().map(|x| x.to_string()).collect::<Vec<String>>();
-            if let Some(id) = &active_chat_id {
-                local_typing_ch.send(TypingIndicator::Typing(*id));
-            }
-        },
-        onreturn: move |_| {
-            local_typing_ch.send(TypingIndicator::NotTyping);
-
-            let msg = input.read().clone();
-            // clearing input here should prevent the possibility to double send a message if enter is pressed twice
-            input.write().clear();
-            should_clear_input.set(true);
-
-            if !msg_valid(&msg) {
-                return;
-            }
-            let id = match active_chat_id {
-                Some(i) => i,
-                None => return,
-            };
+    // the partial username after a trailing "@" in the chatbar
+    let mention_query: &UseState<Option<String>> = use_state(cx, || None);
+    let eval = use_eval(cx);
+    let mention_suggestions: Vec<Identity> = match (mention_query.get(), &data) {
+        (Some(query), Some(data)) => {
+            let query = query.to_lowercase();
+            data.other_participants
+                .iter()
+                .filter(|p| p.username().to_lowercase().starts_with(&query))
+                .take(MAX_MENTION_SUGGESTIONS)
+                .cloned()
+                .collect()
+        }
+        _ => vec![],
+    };
 
-            if STATIC_ARGS.use_mock {
-                state.write().mutate(Action::MockSend(id, msg));
-            } else {
-                msg_ch.send((msg, id));
+    cx.render(rsx!(
+        typing_text.map(|text| rsx!(
+            div {
+                class: "typing-indicator",
+                aria_label: "typing-indicator",
+                MessageTyping {
+                    with_text: text,
+                    user_image: cx.render(rsx!(
+                        if users_typing.len() == 1 {
+                            let typing = &users_typing[0];
+                            let (platform, status) = get_platform_and_status(Some(typing));
+                            rsx!(UserImage {
+                                platform: platform,
+                                status: status,
+                                image: typing.graphics().profile_picture(),
+                            })
+                        } else {rsx!(
+                            UserImageGroup {
+                                participants: build_participants(&users_typing),
+                            }
+                        )}
+                    )),
+                }
             }
-        },
-        controls: cx.render(rsx!(Button {
-            icon: Icon::ChevronDoubleRight,
-            disabled: is_loading,
-            appearance: Appearance::Secondary,
-            onpress: move |_| {
+        )),
+        Chatbar {
+            loading: is_loading,
+            placeholder: get_local_text("messages.say-something-placeholder"),
+            reset: should_clear_input.clone(),
+            onchange: move |v: String| {
+                let query = mentions::mention_query(&v).map(|q| q.to_string());
+                if *mention_query.get() != query {
+                    mention_query.set(query);
+                }
+                *input.write_silent() = v.lines().map(|x| x.to_string()).collect::<Vec<String>>();
+                if let Some(id) = &active_chat_id {
+                    local_typing_ch.send(TypingIndicator::Typing(*id));
+                }
+            },
+            onreturn: move |_| {
                 local_typing_ch.send(TypingIndicator::NotTyping);
+                mention_query.set(None);
 
                 let msg = input.read().clone();
                 // clearing input here should prevent the possibility to double send a message if enter is pressed twice
@@ -612,60 +1173,153 @@ fn get_chatbar(cx: Scope<ComposeProps>) -> Element {
                 if !msg_valid(&msg) {
                     return;
                 }
+                let chat =
+                    match active_chat_id.and_then(|id| state.read().chats.all.get(&id).cloned()) {
+                        Some(c) => c,
+                        None => return,
+                    };
 
-                let id = match active_chat_id {
-                    Some(i) => i,
-                    None => return,
-                };
-
+                let msg = mentions::encode_mentions(msg, &chat.participants);
                 if STATIC_ARGS.use_mock {
-                    state.write().mutate(Action::MockSend(id, msg));
+                    state.write().mutate(Action::MockSend(chat.id, msg));
                 } else {
-                    msg_ch.send((msg, id));
+                    // the message is shown right away and sent from the outbox. if the chat is replying to a message, it's sent as a reply
+                    let own_did = state.read().account.identity.did_key();
+                    let reply_to = chat.replying_to.as_ref().map(|m| m.id());
+                    state
+                        .write()
+                        .mutate(Action::QueueMessage(PendingMessage::new(
+                            chat.id, own_did, msg, reply_to,
+                        )));
                 }
             },
-            tooltip: cx.render(rsx!(Tooltip {
-                arrow_position: ArrowPosition::Bottom,
-                text: get_local_text("uplink.send"),
-            })),
-        },)),
-        with_replying_to: data
-            .map(|data| {
-                let active_chat = data.active_chat.clone();
-                cx.render(rsx!(active_chat.clone().replying_to.map(|msg| {
-                    let our_did = state.read().account.identity.did_key();
-                    let mut participants = data.active_chat.participants.clone();
-                    participants.retain(|p| p.did_key() == msg.sender());
-                    let msg_owner = participants.first();
-                    let (platform, status) = get_platform_and_status(msg_owner);
+            controls: cx.render(rsx!(Button {
+                icon: Icon::ChevronDoubleRight,
+                disabled: is_loading,
+                appearance: Appearance::Secondary,
+                onpress: move |_| {
+                    local_typing_ch.send(TypingIndicator::NotTyping);
+                    mention_query.set(None);
 
-                    rsx!(
-                        Reply {
-                            label: get_local_text("messages.replying"),
-                            remote: our_did != msg.sender(),
-                            onclose: move |_| {
-                                state.write().mutate(Action::CancelReply(active_chat.clone()))
-                            },
-                            message: msg.value().join("\n"),
-                            UserImage {
-                                platform: platform,
-                                status: status,
-                            },
+                    let msg = input.read().clone();
+                    // clearing input here should prevent the possibility to double send a message if enter is pressed twice
+                    input.write().clear();
+                    should_clear_input.set(true);
+
+                    if !msg_valid(&msg) {
+                        return;
+                    }
+
+                    let chat = match active_chat_id
+                        .and_then(|id| state.read().chats.all.get(&id).cloned())
+                    {
+                        Some(c) => c,
+                        None => return,
+                    };
+
+                    let msg = mentions::encode_mentions(msg, &chat.participants);
+                    if STATIC_ARGS.use_mock {
+                        state.write().mutate(Action::MockSend(chat.id, msg));
+                    } else {
+                        let own_did = state.read().account.identity.did_key();
+                        let reply_to = chat.replying_to.as_ref().map(|m| m.id());
+                        state
+                            .write()
+                            .mutate(Action::QueueMessage(PendingMessage::new(
+                                chat.id, own_did, msg, reply_to,
+                            )));
+                    }
+                },
+                tooltip: cx.render(rsx!(Tooltip {
+                    arrow_position: ArrowPosition::Bottom,
+                    text: get_local_text("uplink.send"),
+                })),
+            },)),
+            with_replying_to: data
+                .map(|data| {
+                    let active_chat = data.active_chat.clone();
+                    cx.render(rsx!(active_chat.clone().replying_to.map(|msg| {
+                        let our_did = state.read().account.identity.did_key();
+                        let mut participants = data.active_chat.participants.clone();
+                        participants.retain(|p| p.did_key() == msg.sender());
+                        let msg_owner = participants.first();
+                        let (platform, status) = get_platform_and_status(msg_owner);
+
+                        rsx!(
+                            Reply {
+                                label: get_local_text("messages.replying"),
+                                remote: our_did != msg.sender(),
+                                onclose: move |_| {
+                                    state.write().mutate(Action::CancelReply(active_chat.clone()))
+                                },
+                                message: mentions::decode_mentions(&msg.value().join("\n"), &data.active_chat.participants, |m| m.to_string()),
+                                UserImage {
+                                    platform: platform,
+                                    status: status,
+                                },
+                            }
+                        )
+                    })))
+                })
+                .unwrap_or(None),
+            with_suggestions: cx.render(rsx!(mention_suggestions.iter().map(|identity| {
+                let username = identity.username();
+                let (platform, status) = get_platform_and_status(Some(identity));
+                rsx!(
+                    div {
+                        key: "{identity.did_key()}",
+                        class: "mention-suggestion",
+                        aria_label: "mention-suggestion",
+                        onclick: move |_| {
+                            let text = mentions::complete_mention(&input.read().join("\n"), &username);
+                            mention_query.set(None);
+                            let text = serde_json::to_string(&text).unwrap_or_default();
+                            eval(include_str!("./complete_mention.js").replace("$TEXT", &text));
+                        },
+                        UserImage {
+                            platform: platform,
+                            status: status,
+                            image: identity.graphics().profile_picture(),
+                        },
+                        p {
+                            "{identity.username()}"
                         }
-                    )
-                })))
-            })
-            .unwrap_or(None),
-        with_file_upload: cx.render(rsx!(Button {
-            icon: Icon::Plus,
-            disabled: is_loading,
-            appearance: Appearance::Primary,
-            tooltip: cx.render(rsx!(Tooltip {
-                arrow_position: ArrowPosition::Bottom,
-                text: get_local_text("files.upload"),
+                    }
+                )
+            }))),
+            with_attachments: cx.render(rsx!(pending_uploads.iter().map(|(path, size)| {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // two files can have the same name
+                let key = path.display().to_string();
+                rsx!(FileEmbed {
+                    key: "{key}",
+                    filename: name.clone(),
+                    filesize: *size,
+                    kind: get_file_kind(&name),
+                    icon: Icon::Document,
+                    uploading: true,
+                })
+            }))),
+            with_file_upload: cx.render(rsx!(Button {
+                icon: Icon::Plus,
+                disabled: is_loading,
+                appearance: Appearance::Primary,
+                aria_label: "upload-file".into(),
+                tooltip: cx.render(rsx!(Tooltip {
+                    arrow_position: ArrowPosition::Bottom,
+                    text: get_local_text("files.upload"),
+                })),
+                onpress: move |_| {
+                    if let Some(id) = active_chat_id {
+                        upload_ch.send(id);
+                    }
+                }
             }))
-        }))
-    }))
+        }
+    ))
 }
 
 fn get_platform_and_status(msg_sender: Option<&Identity>) -> (Platform, Status) {
@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+
+use dioxus::prelude::*;
+use dioxus_router::use_router;
+use futures::StreamExt;
+use kit::{
+    components::{indicator::Platform, user_image::UserImage},
+    elements::{button::Button, input::Input, label::Label, Appearance},
+    icons::{Icon, IconElement},
+};
+use shared::language::get_local_text;
+use uuid::Uuid;
+use warp::{crypto::DID, logging::tracing::log};
+
+use crate::{
+    state::{Action, Chat, Identity, State},
+    utils::convert_status,
+    warp_runner::{self, RayGunCmd, WarpCmd},
+    UPLINK_ROUTES,
+};
+
+enum GroupCmd {
+    Rename { conv_id: Uuid, name: String },
+    Add { conv_id: Uuid, recipient: DID },
+    Remove { conv_id: Uuid, recipient: DID },
+}
+
+fn get_platform(identity: &Identity) -> Platform {
+    match identity.platform() {
+        warp::multipass::identity::Platform::Desktop => Platform::Desktop,
+        warp::multipass::identity::Platform::Mobile => Platform::Mobile,
+        _ => Platform::Headless, //TODO: Unknown
+    }
+}
+
+fn sorted_friends(state: &State) -> Vec<Identity> {
+    let mut friends: Vec<Identity> = state.friends.all.values().cloned().collect();
+    friends.sort_by_key(|f| f.username().to_lowercase());
+    friends
+}
+
+// lets the user pick a set of friends and start a group conversation with them
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn CreateGroup(cx: Scope, show: UseState<bool>) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let router = use_router(cx);
+    let selected: &UseRef<HashSet<DID>> = use_ref(cx, HashSet::new);
+    let group_name: &UseRef<String> = use_ref(cx, String::new);
+    let chat_with: &UseState<Option<Chat>> = use_state(cx, || None);
+
+    if let Some(chat) = chat_with.get().clone() {
+        chat_with.set(None);
+        show.set(false);
+        state.write().mutate(Action::ChatWith(chat));
+        if state.read().ui.is_minimal_view() {
+            state.write().mutate(Action::SidebarHidden(true));
+        }
+        router.replace_route(UPLINK_ROUTES.chat, None, None);
+    }
+
+    let ch = use_coroutine(
+        cx,
+        |mut rx: UnboundedReceiver<(Vec<DID>, Option<String>)>| {
+            to_owned![chat_with];
+            async move {
+                while let Some((recipients, name)) = rx.next().await {
+                    match warp_runner::run_cmd(|rsp| {
+                        WarpCmd::RayGun(RayGunCmd::CreateGroupConversation {
+                            recipients,
+                            name,
+                            rsp,
+                        })
+                    })
+                    .await
+                    {
+                        Ok(chat) => chat_with.set(Some(chat)),
+                        Err(e) => log::error!("failed to create group conversation: {}", e),
+                    }
+                }
+            }
+        },
+    );
+
+    let friends = sorted_friends(&state.read());
+    // a group needs at least two other participants
+    let can_create = selected.read().len() >= 2;
+
+    cx.render(rsx!(
+        div {
+            class: "group-panel",
+            aria_label: "create-group",
+            Label {
+                text: get_local_text("group.create"),
+            },
+            Input {
+                placeholder: get_local_text("group.name-placeholder"),
+                aria_label: "group-name-input".into(),
+                onchange: move |(val, _): (String, bool)| {
+                    *group_name.write_silent() = val;
+                }
+            },
+            div {
+                class: "group-friends",
+                friends.into_iter().map(|friend| {
+                    let did = friend.did_key();
+                    let is_selected = selected.read().contains(&did);
+                    rsx!(
+                        div {
+                            key: "{did}",
+                            class: if is_selected { "group-friend selected" } else { "group-friend" },
+                            onclick: move |_| {
+                                let mut selected = selected.write();
+                                if !selected.remove(&did) {
+                                    selected.insert(did.clone());
+                                }
+                            },
+                            UserImage {
+                                platform: get_platform(&friend),
+                                status: convert_status(&friend.identity_status()),
+                                image: friend.graphics().profile_picture(),
+                                with_username: friend.username(),
+                            },
+                            is_selected.then(|| rsx!(
+                                IconElement {
+                                    icon: Icon::Check
+                                }
+                            ))
+                        }
+                    )
+                })
+            },
+            div {
+                class: "group-actions",
+                Button {
+                    text: get_local_text("group.cancel"),
+                    appearance: Appearance::Secondary,
+                    onpress: move |_| {
+                        show.set(false);
+                    }
+                },
+                Button {
+                    icon: Icon::UserGroup,
+                    text: get_local_text("group.start"),
+                    aria_label: "start-group-button".into(),
+                    disabled: !can_create,
+                    onpress: move |_| {
+                        let recipients: Vec<DID> = selected.read().iter().cloned().collect();
+                        let name = group_name.read().trim().to_string();
+                        ch.send((recipients, (!name.is_empty()).then_some(name)));
+                    }
+                },
+            }
+        }
+    ))
+}
+
+// rename a group and add or remove its participants
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn EditGroup(cx: Scope, chat: Chat) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<GroupCmd>| async move {
+        while let Some(cmd) = rx.next().await {
+            let res = match cmd {
+                GroupCmd::Rename { conv_id, name } => {
+                    warp_runner::run_cmd(|rsp| {
+                        WarpCmd::RayGun(RayGunCmd::SetGroupName { conv_id, name, rsp })
+                    })
+                    .await
+                }
+                GroupCmd::Add { conv_id, recipient } => {
+                    warp_runner::run_cmd(|rsp| {
+                        WarpCmd::RayGun(RayGunCmd::AddGroupParticipants {
+                            conv_id,
+                            recipients: vec![recipient],
+                            rsp,
+                        })
+                    })
+                    .await
+                }
+                GroupCmd::Remove { conv_id, recipient } => {
+                    warp_runner::run_cmd(|rsp| {
+                        WarpCmd::RayGun(RayGunCmd::RemoveGroupParticipant {
+                            conv_id,
+                            recipient,
+                            rsp,
+                        })
+                    })
+                    .await
+                }
+            };
+            if let Err(e) = res {
+                log::error!("failed to update group: {}", e);
+            }
+        }
+    });
+
+    let conv_id = chat.id;
+    let members = state.read().get_without_me(&chat.participants);
+    // a group keeps at least one other member
+    let can_remove = members.len() > 1;
+    let member_dids: HashSet<DID> = chat.participants.iter().map(|p| p.did_key()).collect();
+    let others: Vec<Identity> = sorted_friends(&state.read())
+        .into_iter()
+        .filter(|f| !member_dids.contains(&f.did_key()))
+        .collect();
+
+    cx.render(rsx!(
+        div {
+            class: "group-panel",
+            aria_label: "edit-group",
+            Input {
+                placeholder: get_local_text("group.name-placeholder"),
+                aria_label: "group-name-input".into(),
+                default_text: chat.name.clone().unwrap_or_default(),
+                onreturn: move |(val, _): (String, bool)| {
+                    let name = val.trim().to_string();
+                    if !name.is_empty() {
+                        ch.send(GroupCmd::Rename { conv_id, name });
+                    }
+                }
+            },
+            Label {
+                text: get_local_text("group.members"),
+            },
+            div {
+                class: "group-friends",
+                members.into_iter().map(|member| {
+                    let did = member.did_key();
+                    rsx!(
+                        div {
+                            key: "{did}",
+                            class: "group-friend",
+                            UserImage {
+                                platform: get_platform(&member),
+                                status: convert_status(&member.identity_status()),
+                                image: member.graphics().profile_picture(),
+                                with_username: member.username(),
+                            },
+                            Button {
+                                icon: Icon::UserMinus,
+                                aria_label: "remove-member-button".into(),
+                                appearance: Appearance::Secondary,
+                                small: true,
+                                disabled: !can_remove,
+                                onpress: move |_| {
+                                    ch.send(GroupCmd::Remove { conv_id, recipient: did.clone() });
+                                }
+                            }
+                        }
+                    )
+                })
+            },
+            (!others.is_empty()).then(|| rsx!(
+                Label {
+                    text: get_local_text("group.add-members"),
+                },
+                div {
+                    class: "group-friends",
+                    others.into_iter().map(|friend| {
+                        let did = friend.did_key();
+                        rsx!(
+                            div {
+                                key: "{did}",
+                                class: "group-friend",
+                                UserImage {
+                                    platform: get_platform(&friend),
+                                    status: convert_status(&friend.identity_status()),
+                                    image: friend.graphics().profile_picture(),
+                                    with_username: friend.username(),
+                                },
+                                Button {
+                                    icon: Icon::UserPlus,
+                                    aria_label: "add-member-button".into(),
+                                    appearance: Appearance::Secondary,
+                                    small: true,
+                                    onpress: move |_| {
+                                        ch.send(GroupCmd::Add { conv_id, recipient: did.clone() });
+                                    }
+                                }
+                            }
+                        )
+                    })
+                }
+            )),
+        }
+    ))
+}
@@ -0,0 +1,14 @@
+const chat = document.getElementById("messages")
+// #messages is laid out in reverse, so scrollTop is zero at the bottom and negative while scrolled up.
+// reaching the top of the chat loads the next page of older messages, and reaching the bottom loads newer ones
+// if the newest messages were evicted while reading older ones
+chat.onscroll = () => {
+  const older = document.getElementById("load-older-messages")
+  if (older && Math.abs(chat.scrollTop) + chat.clientHeight >= chat.scrollHeight - 1) {
+    older.click()
+  }
+  const newer = document.getElementById("load-newer-messages")
+  if (newer && Math.abs(chat.scrollTop) <= 1) {
+    newer.click()
+  }
+}
@@ -1,6 +1,9 @@
 use kit::components::nav::Route;
+use shared::language::{get_local_text_with_args, FluentValue};
 
 pub mod compose;
+pub mod group;
+pub mod notification_settings;
 pub mod sidebar;
 pub mod welcome;
 
@@ -9,3 +12,15 @@ pub struct RouteInfo {
     pub routes: Vec<Route>,
     pub active: Route,
 }
+
+// e.g. "Alice is typing" or "Alice and 2 others are typing"
+pub fn build_typing_text(names: &[String]) -> Option<String> {
+    let first = names.first()?;
+    Some(get_local_text_with_args(
+        "messages.typing",
+        vec![
+            ("user", FluentValue::from(first.as_str())),
+            ("others", FluentValue::from(names.len() - 1)),
+        ],
+    ))
+}
@@ -0,0 +1,161 @@
+use chrono::{Duration, Local, Utc};
+use dioxus::prelude::*;
+use kit::elements::{button::Button, label::Label, switch::Switch, Appearance};
+use shared::language::{get_local_text, get_local_text_with_args, FluentValue};
+
+use crate::state::{
+    chats::{ChatNotifications, Mute, NotificationLevel},
+    Action, Chat, State,
+};
+
+// (label, how long the chat is muted for). None mutes the chat until it is unmuted
+fn mute_options() -> Vec<(String, Option<Duration>)> {
+    vec![
+        (
+            get_local_text("chat-notifications.mute-hour"),
+            Some(Duration::hours(1)),
+        ),
+        (
+            get_local_text("chat-notifications.mute-8-hours"),
+            Some(Duration::hours(8)),
+        ),
+        (
+            get_local_text("chat-notifications.mute-day"),
+            Some(Duration::days(1)),
+        ),
+        (
+            get_local_text("chat-notifications.mute-week"),
+            Some(Duration::weeks(1)),
+        ),
+        (get_local_text("chat-notifications.mute-forever"), None),
+    ]
+}
+
+// e.g. "Muted until Mar 3, 14:30"
+pub fn get_muted_text(notifications: &ChatNotifications) -> Option<String> {
+    if !notifications.is_muted() {
+        return None;
+    }
+    match notifications.muted? {
+        Mute::Forever => Some(get_local_text("chat-notifications.muted")),
+        Mute::Until(date) => Some(get_local_text_with_args(
+            "chat-notifications.muted-until",
+            vec![(
+                "time",
+                FluentValue::from(
+                    date.with_timezone(&Local)
+                        .format("%b %-d, %H:%M")
+                        .to_string(),
+                ),
+            )],
+        )),
+    }
+}
+
+// lets the user pick which messages of a chat notify them, mute the chat, and turn off its sounds
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn ChatNotificationSettings(cx: Scope, chat: Chat) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let chat_id = chat.id;
+    let settings = chat.notifications;
+    let update = move |notifications: ChatNotifications| {
+        state
+            .write()
+            .mutate(Action::SetChatNotifications(chat_id, notifications));
+    };
+    let muted_text = get_muted_text(&settings);
+    let is_muted = muted_text.is_some();
+    let level_appearance = |level: NotificationLevel| {
+        if settings.level == level {
+            Appearance::Primary
+        } else {
+            Appearance::Secondary
+        }
+    };
+
+    cx.render(rsx!(
+        div {
+            class: "chat-notifications-panel",
+            aria_label: "chat-notifications",
+            Label {
+                text: get_local_text("chat-notifications.notify-about"),
+            },
+            div {
+                class: "chat-notifications-options",
+                Button {
+                    text: get_local_text("chat-notifications.all"),
+                    aria_label: "notify-all-button".into(),
+                    appearance: level_appearance(NotificationLevel::All),
+                    onpress: move |_| update(ChatNotifications {
+                        level: NotificationLevel::All,
+                        ..settings
+                    }),
+                },
+                Button {
+                    text: get_local_text("chat-notifications.mentions-only"),
+                    aria_label: "notify-mentions-button".into(),
+                    appearance: level_appearance(NotificationLevel::MentionsOnly),
+                    onpress: move |_| update(ChatNotifications {
+                        level: NotificationLevel::MentionsOnly,
+                        ..settings
+                    }),
+                },
+            },
+            Label {
+                text: get_local_text("chat-notifications.mute"),
+            },
+            muted_text.map(|text| rsx!(
+                div {
+                    class: "chat-notifications-options",
+                    p {
+                        class: "chat-notifications-muted",
+                        "{text}"
+                    },
+                    Button {
+                        text: get_local_text("chat-notifications.unmute"),
+                        aria_label: "unmute-button".into(),
+                        appearance: Appearance::Secondary,
+                        onpress: move |_| update(ChatNotifications {
+                            muted: None,
+                            ..settings
+                        }),
+                    },
+                }
+            )),
+            (!is_muted).then(|| rsx!(
+                div {
+                    class: "chat-notifications-options",
+                    mute_options().into_iter().map(|(text, duration)| rsx!(
+                        Button {
+                            key: "{text}",
+                            text: text.clone(),
+                            small: true,
+                            appearance: Appearance::Secondary,
+                            onpress: move |_| update(ChatNotifications {
+                                muted: Some(match duration {
+                                    Some(d) => Mute::Until(Utc::now() + d),
+                                    None => Mute::Forever,
+                                }),
+                                ..settings
+                            }),
+                        }
+                    ))
+                }
+            )),
+            div {
+                class: "chat-notifications-sounds",
+                p {
+                    get_local_text("chat-notifications.sounds")
+                },
+                Switch {
+                    active: settings.sounds,
+                    onflipped: move |sounds| update(ChatNotifications {
+                        sounds,
+                        ..settings
+                    }),
+                }
+            }
+        }
+    ))
+}
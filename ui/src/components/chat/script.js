@@ -1,4 +1,5 @@
 const chat = document.getElementById("messages")
-const lastChild = chat.lastElementChild
+// the first child holds the messages. #messages is laid out in reverse, so it is at the bottom
+const lastChild = chat.firstElementChild
 chat.scrollTop = chat.scrollHeight
 lastChild.scrollIntoView({ behavior: 'smooth', block: 'end' })
\ No newline at end of file
@@ -0,0 +1,6 @@
+const message = document.getElementById("message-$MESSAGE_ID")
+if (message) {
+  message.scrollIntoView({ block: 'center' })
+  message.classList.add("search-highlight")
+  setTimeout(() => message.classList.remove("search-highlight"), 2000)
+}
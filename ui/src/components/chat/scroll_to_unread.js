@@ -0,0 +1,4 @@
+const divider = document.getElementById("unread-divider")
+if (divider) {
+  divider.scrollIntoView({ block: 'center' })
+}
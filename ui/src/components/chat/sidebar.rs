@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 use dioxus_router::*;
+use futures::StreamExt;
 use kit::{
     components::{
         context_menu::{ContextItem, ContextMenu},
@@ -10,20 +11,34 @@ use kit::{
         user_image_group::UserImageGroup,
     },
     elements::{
+        button::Button,
         input::{Input, Options},
         label::Label,
+        tooltip::{ArrowPosition, Tooltip},
+        Appearance,
     },
     icons::Icon,
     layout::sidebar::Sidebar as ReusableSidebar,
 };
 use shared::language::get_local_text;
+use std::collections::HashSet;
+use uuid::Uuid;
 use warp::{logging::tracing::log, raygun::Message};
 
 use crate::{
-    components::{chat::RouteInfo, media::remote_control::RemoteControls},
-    state::{Action, Identity, State},
-    utils::{build_participants, convert_status},
-    UPLINK_ROUTES,
+    components::{
+        chat::{build_typing_text, group::CreateGroup, RouteInfo},
+        media::remote_control::RemoteControls,
+        notification_center::NotificationCenter,
+        presence::PresencePicker,
+    },
+    state::{
+        chats::{ChatNotifications, Mute},
+        Action, Identity, State,
+    },
+    utils::{build_participants, convert_status, mentions},
+    warp_runner::{self, RayGunCmd, WarpCmd},
+    STATIC_ARGS, UPLINK_ROUTES,
 };
 
 #[derive(PartialEq, Props)]
@@ -66,6 +81,57 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
         (vec![], vec![], None)
     };
 
+    let show_create_group = use_state(cx, || false);
+    let show_notification_center = state.read().ui.show_notification_center;
+    let unread_notifications = state.read().ui.notifications.unread();
+    let search_query = use_state(cx, String::new);
+    let is_searching = !search_query.trim().is_empty();
+    let search_results = if is_searching {
+        state.read().search(search_query.get())
+    } else {
+        vec![]
+    };
+
+    // the history of a chat is only indexed the first time the user searches. mock chats are indexed when they're loaded
+    let index_ch = use_coroutine(cx, |mut rx: UnboundedReceiver<Vec<Uuid>>| {
+        to_owned![state];
+        async move {
+            let mut requested: HashSet<Uuid> = HashSet::new();
+            while let Some(conv_ids) = rx.next().await {
+                for conv_id in conv_ids {
+                    if !requested.insert(conv_id) {
+                        continue;
+                    }
+                    match warp_runner::run_cmd(|rsp| {
+                        WarpCmd::RayGun(RayGunCmd::FetchAllMessages { conv_id, rsp })
+                    })
+                    .await
+                    {
+                        Ok(messages) => state.write().search_index.insert_all(conv_id, &messages),
+                        Err(e) => {
+                            log::error!("failed to fetch messages to index: {}", e);
+                            // try again with the next search
+                            requested.remove(&conv_id);
+                        }
+                    }
+                }
+            }
+        }
+    });
+    if is_searching && !STATIC_ARGS.use_mock {
+        let unindexed: Vec<Uuid> = state
+            .read()
+            .chats
+            .all
+            .keys()
+            .filter(|id| !state.read().search_index.is_indexed(**id))
+            .copied()
+            .collect();
+        if !unindexed.is_empty() {
+            index_ch.send(unindexed);
+        }
+    }
+
     cx.render(rsx!(
         ReusableSidebar {
             hidden: state.read().ui.sidebar_hidden,
@@ -74,18 +140,45 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                     class: "search-input",
                     Input {
                         placeholder: get_local_text("uplink.search-placeholder"),
-                        // TODO: Pending implementation
-                        disabled: true,
                         aria_label: "chat-search-input".into(),
                         icon: Icon::MagnifyingGlass,
                         options: Options {
                             with_clear_btn: true,
                             ..Options::default()
+                        },
+                        onchange: move |(v, _): (String, bool)| {
+                            search_query.set(v);
+                        },
+                    },
+                    Button {
+                        icon: Icon::UserGroup,
+                        aria_label: "create-group-button".into(),
+                        appearance: if *show_create_group.get() { Appearance::Primary } else { Appearance::Secondary },
+                        tooltip: cx.render(rsx!(Tooltip {
+                            arrow_position: ArrowPosition::Top,
+                            text: get_local_text("group.create"),
+                        })),
+                        onpress: move |_| {
+                            show_create_group.set(!show_create_group.get());
+                        }
+                    },
+                    Button {
+                        icon: Icon::Bell,
+                        aria_label: "notification-center-button".into(),
+                        appearance: if show_notification_center { Appearance::Primary } else { Appearance::Secondary },
+                        with_badge: if unread_notifications > 0 { unread_notifications.to_string() } else { "".into() },
+                        tooltip: cx.render(rsx!(Tooltip {
+                            arrow_position: ArrowPosition::Top,
+                            text: get_local_text("notification-center.notifications"),
+                        })),
+                        onpress: move |_| {
+                            state.write().mutate(Action::ShowNotificationCenter(!show_notification_center));
                         }
                     }
                 }
             ))
             with_nav: cx.render(rsx!(
+                PresencePicker {},
                 Nav {
                     routes: cx.props.route_info.routes.clone(),
                     active: cx.props.route_info.active.clone(),
@@ -97,8 +190,84 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                     }
                 },
             )),
+            is_searching.then(|| rsx!(
+                div {
+                    id: "search-results",
+                    aria_label: "search-results",
+                    search_results.is_empty().then(|| rsx!(
+                        p {
+                            class: "search-empty",
+                            get_local_text("uplink.search-no-results")
+                        }
+                    )),
+                    search_results.iter().cloned().map(|result| {
+                        let chat = match state.read().chats.all.get(&result.chat_id) {
+                            Some(c) => c.clone(),
+                            None => return rsx!("")
+                        };
+                        let without_me = state.read().get_without_me(&chat.participants);
+                        let chat_name = chat.name.clone().unwrap_or_else(|| build_participants_names(&without_me));
+                        let chat_with = chat.clone();
+                        rsx!(
+                            div {
+                                key: "{chat.id}-search",
+                                class: "search-result-group",
+                                Label {
+                                    text: chat_name.clone(),
+                                },
+                                // the chat matched by name but none of its messages did
+                                result.hits.is_empty().then(|| rsx!(
+                                    UserImageGroup {
+                                        participants: build_participants(&chat.participants),
+                                        with_username: chat_name.clone(),
+                                        onpress: move |_| {
+                                            state.write().mutate(Action::ChatWith(chat_with.clone()));
+                                            if cx.props.route_info.active.to != UPLINK_ROUTES.chat {
+                                                use_router(cx).replace_route(UPLINK_ROUTES.chat, None, None);
+                                            }
+                                        }
+                                    }
+                                )),
+                                result.hits.iter().cloned().map(|hit| {
+                                    let sender = chat.participants.iter().find(|p| p.did_key() == hit.sender).cloned().unwrap_or_default();
+                                    let conv_id = hit.conversation_id;
+                                    let message_id = hit.message_id;
+                                    rsx!(
+                                        User {
+                                            key: "{message_id}-search",
+                                            username: sender.username(),
+                                            subtext: mentions::decode_mentions(&hit.text, &chat.participants, |m| m.to_string()),
+                                            timestamp: hit.date.timestamp_millis() as u64,
+                                            user_image: cx.render(rsx!(
+                                                UserImage {
+                                                    platform: match sender.platform() {
+                                                        warp::multipass::identity::Platform::Desktop => Platform::Desktop,
+                                                        warp::multipass::identity::Platform::Mobile => Platform::Mobile,
+                                                        _ => Platform::Headless
+                                                    },
+                                                    status: convert_status(&sender.identity_status()),
+                                                    image: sender.graphics().profile_picture(),
+                                                }
+                                            )),
+                                            onpress: move |_| {
+                                                state.write().mutate(Action::JumpToMessage(conv_id, message_id));
+                                                if cx.props.route_info.active.to != UPLINK_ROUTES.chat {
+                                                    use_router(cx).replace_route(UPLINK_ROUTES.chat, None, None);
+                                                }
+                                                if state.read().ui.is_minimal_view() {
+                                                    state.write().mutate(Action::SidebarHidden(true));
+                                                }
+                                            }
+                                        }
+                                    )
+                                })
+                            }
+                        )
+                    })
+                }
+            )),
             // Only display favorites if we have some.
-            (!favorites.is_empty()).then(|| rsx!(
+            (!is_searching && !favorites.is_empty()).then(|| rsx!(
                 div {
                     id: "favorites",
                     aria_label: "Favorites",
@@ -115,7 +284,7 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                             let favorites_chat = chat.clone();
                             let remove_favorite = chat.clone();
                             let without_me = state.read().get_without_me(&chat.participants);
-                            let participants_name = build_participants_names(&without_me);
+                            let participants_name = chat.name.clone().unwrap_or_else(|| build_participants_names(&without_me));
                             rsx! (
                                 ContextMenu {
                                     key: "{chat_id}-favorite",
@@ -161,158 +330,202 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                     }
                 }
             )),
-            div {
-                id: "chats",
-                aria_label: "Chats",
-                (!sidebar_chats.is_empty()).then(|| rsx!(
-                    Label {
-                        text: get_local_text("uplink.chats"),
-                    }
-                )),
-                sidebar_chats.iter().cloned().map(|chat_id| {
-                    let chat = match state.read().chats.all.get(&chat_id) {
-                        Some(c) => c.clone(),
-                        None => return rsx!("")
-                    };
-                    let without_me = state.read().get_without_me(&chat.participants);
-                    let user = without_me.first();
-                    let default_message = Message::default();
-                    let parsed_user = user.cloned().unwrap_or_default();
+            show_notification_center.then(|| rsx!(NotificationCenter {})),
+            show_create_group.get().then(|| rsx!(
+                CreateGroup {
+                    show: show_create_group.clone(),
+                }
+            )),
+            (!is_searching).then(|| rsx!(
+                div {
+                    id: "chats",
+                    aria_label: "Chats",
+                    (!sidebar_chats.is_empty()).then(|| rsx!(
+                        Label {
+                            text: get_local_text("uplink.chats"),
+                        }
+                    )),
+                    sidebar_chats.iter().cloned().map(|chat_id| {
+                        let chat = match state.read().chats.all.get(&chat_id) {
+                            Some(c) => c.clone(),
+                            None => return rsx!("")
+                        };
+                        let without_me = state.read().get_without_me(&chat.participants);
+                        let user = without_me.first();
+                        let default_message = Message::default();
+                        let parsed_user = user.cloned().unwrap_or_default();
 
-                    let platform = match parsed_user.platform() {
-                        warp::multipass::identity::Platform::Desktop => Platform::Desktop,
-                        warp::multipass::identity::Platform::Mobile => Platform::Mobile,
-                        _ => Platform::Headless //TODO: Unknown (Matt: This represents bots and other platforms which are not using known UIs)
-                    };
+                        let platform = match parsed_user.platform() {
+                            warp::multipass::identity::Platform::Desktop => Platform::Desktop,
+                            warp::multipass::identity::Platform::Mobile => Platform::Mobile,
+                            _ => Platform::Headless //TODO: Unknown (Matt: This represents bots and other platforms which are not using known UIs)
+                        };
 
-                    let last_message = chat.messages.iter().last();
-                    let unwrapped_message = match last_message {
-                        Some(m) => m,
-                        None => &default_message,
-                    };
+                        let last_message = chat.messages.iter().last();
+                        let unwrapped_message = match last_message {
+                            Some(m) => m,
+                            None => &default_message,
+                        };
 
-                    let val = unwrapped_message.value();
-                    let timestamp = unwrapped_message.date().timestamp_millis() as u64;
+                        let typing_text = build_typing_text(
+                            &chat.participants_typing().iter().map(|x| x.username()).collect::<Vec<_>>()
+                        );
+                        // shows who is typing in place of the last message
+                        let subtext = typing_text.unwrap_or_else(|| {
+                            mentions::decode_mentions(&unwrapped_message.value().join("\n"), &chat.participants, |m| m.to_string())
+                        });
+                        let timestamp = unwrapped_message.date().timestamp_millis() as u64;
 
-                    let badge = if chat.unreads > 0 {
-                        chat.unreads.to_string()
-                    } else { "".into() };
-                    let key = chat.id;
+                        // unread mentions take priority over the unread count
+                        let badge = if chat.has_unread_mentions(&state.read().account.identity.did_key()) {
+                            "@".into()
+                        } else if chat.unreads > 0 {
+                            chat.unreads.to_string()
+                        } else { "".into() };
+                        let key = chat.id;
 
-                    let is_active = state.read().get_active_chat().map(|c| c.id) == Some(chat.id);
-                    let chat_with = chat.clone();
-                    let clear_unreads = chat.clone();
+                        let is_active = state.read().get_active_chat().map(|c| c.id) == Some(chat.id);
+                        let chat_with = chat.clone();
+                        let clear_unreads = chat.clone();
+                        let notification_settings = chat.clone();
+                        let notifications = chat.notifications;
+                        let is_muted = notifications.is_muted();
 
-                    let participants = without_me.clone();
-                    let participants_name = if participants.len() > 2 { build_participants_names(&participants) } else { parsed_user.username() };
+                        let participants = without_me.clone();
+                        let is_group = chat.is_group();
+                        let participants_name = match &chat.name {
+                            Some(name) => name.clone(),
+                            None if is_group => build_participants_names(&participants),
+                            None => parsed_user.username(),
+                        };
 
-                    // TODO:
-                    // let _block_user_text = LOCALES
-                    //     .lookup(&*APP_LANG.read(), "friends.block")
-                    //     .unwrap_or_default();
+                        // TODO:
+                        // let _block_user_text = LOCALES
+                        //     .lookup(&*APP_LANG.read(), "friends.block")
+                        //     .unwrap_or_default();
 
-                    rsx!(
-                        ContextMenu {
-                            key: "{key}-chat",
-                            id: format!("{key}-chat"),
-                            items: cx.render(rsx!(
-                                ContextItem {
-                                    icon: Icon::BellSlash,
-                                    text: get_local_text("uplink.clear-unreads"),
-                                    onpress: move |_| {
-                                        state.write().mutate(Action::ClearUnreads(clear_unreads.clone()));
-                                    }
-                                },
-                                hr{ },
-                                ContextItem {
-                                    icon: Icon::PhoneArrowUpRight,
-                                    text: get_local_text("uplink.call"),
-                                    //TODO: Wire to state
+                        rsx!(
+                            ContextMenu {
+                                key: "{key}-chat",
+                                id: format!("{key}-chat"),
+                                items: cx.render(rsx!(
+                                    ContextItem {
+                                        icon: Icon::BellSlash,
+                                        text: get_local_text("uplink.clear-unreads"),
+                                        onpress: move |_| {
+                                            state.write().mutate(Action::ClearUnreads(clear_unreads.clone()));
+                                        }
+                                    },
+                                    ContextItem {
+                                        icon: if is_muted { Icon::Bell } else { Icon::BellSlash },
+                                        text: if is_muted { get_local_text("chat-notifications.unmute") } else { get_local_text("chat-notifications.mute") },
+                                        onpress: move |_| {
+                                            let muted = if is_muted { None } else { Some(Mute::Forever) };
+                                            state.write().mutate(Action::SetChatNotifications(key, ChatNotifications { muted, ..notifications }));
+                                        }
+                                    },
+                                    ContextItem {
+                                        icon: Icon::Cog6Tooth,
+                                        text: get_local_text("chat-notifications.settings"),
+                                        onpress: move |_| {
+                                            state.write().mutate(Action::ChatWith(notification_settings.clone()));
+                                            state.write().mutate(Action::ShowChatNotifications(true));
+                                            if cx.props.route_info.active.to != UPLINK_ROUTES.chat {
+                                                use_router(cx).replace_route(UPLINK_ROUTES.chat, None, None);
+                                            }
+                                        }
+                                    },
+                                    hr{ },
+                                    ContextItem {
+                                        icon: Icon::PhoneArrowUpRight,
+                                        text: get_local_text("uplink.call"),
+                                        //TODO: Wire to state
 
-                                },
-                                hr{ }
-                                ContextItem {
-                                    icon: Icon::EyeSlash,
-                                    text: get_local_text("uplink.hide-chat"),
+                                    },
+                                    hr{ }
+                                    ContextItem {
+                                        icon: Icon::EyeSlash,
+                                        text: get_local_text("uplink.hide-chat"),
+                                        onpress: move |_| {
+                                            state.write().mutate(Action::RemoveFromSidebar(chat.id));
+                                        }
+                                    },
+                                )),
+                                User {
+                                    username: participants_name,
+                                    subtext: subtext,
+                                    timestamp: timestamp,
+                                    active: is_active,
+                                    user_image: cx.render(rsx!(
+                                        if !is_group {rsx! (
+                                            UserImage {
+                                                platform: platform,
+                                                status:  convert_status(&parsed_user.identity_status()),
+                                                image: parsed_user.graphics().profile_picture(),
+                                            }
+                                        )} else {rsx! (
+                                            UserImageGroup {
+                                                participants: build_participants(&participants)
+                                            }
+                                        )}
+                                    )),
+                                    with_badge: badge,
                                     onpress: move |_| {
-                                        state.write().mutate(Action::RemoveFromSidebar(chat.id));
-                                    }
-                                },
-                            )),
-                            User {
-                                username: participants_name,
-                                subtext: val.join("\n"),
-                                timestamp: timestamp,
-                                active: is_active,
-                                user_image: cx.render(rsx!(
-                                    if participants.len() <= 2 {rsx! (
-                                        UserImage {
-                                            platform: platform,
-                                            status:  convert_status(&parsed_user.identity_status()),
-                                            image: parsed_user.graphics().profile_picture(),
+                                        state.write().mutate(Action::ChatWith(chat_with.clone()));
+                                        if cx.props.route_info.active.to != UPLINK_ROUTES.chat {
+                                            use_router(cx).replace_route(UPLINK_ROUTES.chat, None, None);
                                         }
-                                    )} else {rsx! (
-                                        UserImageGroup {
-                                            participants: build_participants(&participants)
+                                        if state.read().ui.is_minimal_view() {
+                                            state.write().mutate(Action::SidebarHidden(true));
                                         }
-                                    )}
-                                )),
-                                with_badge: badge,
-                                onpress: move |_| {
-                                    state.write().mutate(Action::ChatWith(chat_with.clone()));
-                                    if cx.props.route_info.active.to != UPLINK_ROUTES.chat {
-                                        use_router(cx).replace_route(UPLINK_ROUTES.chat, None, None);
-                                    }
-                                    if state.read().ui.is_minimal_view() {
-                                        state.write().mutate(Action::SidebarHidden(true));
                                     }
                                 }
                             }
+                        )}
+                    ),
+                    sidebar_chats.is_empty().then(|| rsx!(
+                        div {
+                            class: "skeletal-steady",
+                            User {
+                                loading: true,
+                                username: "Loading".into(),
+                                subtext: "loading".into(),
+                                user_image: cx.render(rsx!(
+                                    UserImage {
+                                        platform: Platform::Mobile,
+                                        status: Status::Online,
+                                        loading: true
+                                    }
+                                ))
+                            },
+                            User {
+                                loading: true,
+                                username: "Loading".into(),
+                                subtext: "loading".into(),
+                                user_image: cx.render(rsx!(
+                                    UserImage {
+                                        platform: Platform::Mobile,
+                                        status: Status::Online,
+                                        loading: true
+                                    }
+                                ))
+                            },
+                            User {
+                                loading: true,
+                                username: "Loading".into(),
+                                subtext: "loading".into(),
+                                user_image: cx.render(rsx!(
+                                    UserImage {
+                                        platform: Platform::Mobile,
+                                        status: Status::Online,
+                                        loading: true
+                                    }
+                                ))
+                            },
                         }
-                    )}
-                ),
-                sidebar_chats.is_empty().then(|| rsx!(
-                    div {
-                        class: "skeletal-steady",
-                        User {
-                            loading: true,
-                            username: "Loading".into(),
-                            subtext: "loading".into(),
-                            user_image: cx.render(rsx!(
-                                UserImage {
-                                    platform: Platform::Mobile,
-                                    status: Status::Online,
-                                    loading: true
-                                }
-                            ))
-                        },
-                        User {
-                            loading: true,
-                            username: "Loading".into(),
-                            subtext: "loading".into(),
-                            user_image: cx.render(rsx!(
-                                UserImage {
-                                    platform: Platform::Mobile,
-                                    status: Status::Online,
-                                    loading: true
-                                }
-                            ))
-                        },
-                        User {
-                            loading: true,
-                            username: "Loading".into(),
-                            subtext: "loading".into(),
-                            user_image: cx.render(rsx!(
-                                UserImage {
-                                    platform: Platform::Mobile,
-                                    status: Status::Online,
-                                    loading: true
-                                }
-                            ))
-                        },
-                    }
-                ))
-            },
+                    ))
+                }
+            )),
             active_media_chat.is_some().then(|| rsx!(
                 RemoteControls {
                     in_call_text: get_local_text("remote-controls.in-call"),
@@ -2,7 +2,7 @@ use arboard::Clipboard;
 use std::str::FromStr;
 
 use dioxus::prelude::*;
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     elements::{
         button::Button,
@@ -17,8 +17,8 @@ use warp::{crypto::DID, logging::tracing::log};
 
 use crate::{
     state::{Action, Identity, State, ToastNotification},
-    warp_runner::{MultiPassCmd, WarpCmd},
-    STATIC_ARGS, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, WarpCmd, WarpCmdError},
+    STATIC_ARGS,
 };
 #[allow(non_snake_case)]
 pub fn AddFriend(cx: Scope) -> Element {
@@ -67,23 +67,16 @@ pub fn AddFriend(cx: Scope) -> Element {
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<DID>| {
         to_owned![request_sent];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(did) = rx.next().await {
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                if let Err(e) = warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::RequestFriend {
-                    did,
-                    rsp: tx,
-                })) {
-                    log::error!("failed to send warp command: {}", e);
-                    continue;
-                }
-
-                let res = rx.await.expect("failed to get response from warp_runner");
+                let res = warp_runner::run_cmd(|rsp| {
+                    WarpCmd::MultiPass(MultiPassCmd::RequestFriend { did, rsp })
+                })
+                .await;
                 match res {
-                    Ok(_) | Err(Error::FriendRequestExist) => {
+                    Ok(_) | Err(WarpCmdError::Warp(Error::FriendRequestExist)) => {
                         request_sent.set(true);
                     }
-                    Err(e) => match e {
+                    Err(WarpCmdError::Warp(e)) => match e {
                         Error::CannotSendSelfFriendRequest
                         | Error::CannotSendFriendRequest
                         | Error::IdentityDoesntExist
@@ -96,6 +89,9 @@ pub fn AddFriend(cx: Scope) -> Element {
                             log::error!("add friend failed: {}", e);
                         }
                     },
+                    Err(e) => {
+                        log::error!("add friend failed: {}", e);
+                    }
                 }
             }
         }
@@ -104,17 +100,10 @@ pub fn AddFriend(cx: Scope) -> Element {
     let id_ch = use_coroutine(cx, |mut rx: UnboundedReceiver<()>| {
         to_owned![my_id];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while rx.next().await.is_some() {
-                let (tx, rx) = oneshot::channel::<Result<DID, warp::error::Error>>();
-                if let Err(e) =
-                    warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::GetOwnDid { rsp: tx }))
-                {
-                    log::error!("failed to send warp command: {}", e);
-                    continue;
-                }
-
-                let res = rx.await.expect("failed to get response from warp_runner");
+                let res =
+                    warp_runner::run_cmd(|rsp| WarpCmd::MultiPass(MultiPassCmd::GetOwnDid { rsp }))
+                        .await;
                 match res {
                     Ok(did) => my_id.set(Some(did.to_string())),
                     Err(e) => log::error!("get own did failed: {}", e),
@@ -2,11 +2,11 @@ use crate::{
     components::friends::friend::Friend,
     state::{Action, State},
     utils::convert_status,
-    warp_runner::{MultiPassCmd, WarpCmd},
-    STATIC_ARGS, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, WarpCmd, WarpCmdError},
+    STATIC_ARGS,
 };
 use dioxus::prelude::*;
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     components::{
         context_menu::{ContextItem, ContextMenu},
@@ -27,20 +27,14 @@ pub fn BlockedUsers(cx: Scope) -> Element {
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<DID>| {
         //to_owned![];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(did) = rx.next().await {
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                if let Err(e) =
-                    warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::Unblock { did, rsp: tx }))
-                {
-                    log::error!("failed to send warp command: {}", e);
-                    continue;
-                }
-
-                let rsp = rx.await.expect("command canceled");
+                let rsp = warp_runner::run_cmd(|rsp| {
+                    WarpCmd::MultiPass(MultiPassCmd::Unblock { did, rsp })
+                })
+                .await;
                 if let Err(e) = rsp {
                     match e {
-                        Error::PublicKeyIsntBlocked => {}
+                        WarpCmdError::Warp(Error::PublicKeyIsntBlocked) => {}
                         _ => {
                             log::error!("failed to unblock user: {}", e);
                         }
@@ -1,6 +1,6 @@
 use dioxus::prelude::*;
 use dioxus_router::use_router;
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     components::{
         context_menu::{ContextItem, ContextMenu},
@@ -18,8 +18,8 @@ use crate::{
     components::friends::friend::{Friend, SkeletalFriend},
     state::{Action, Chat, State},
     utils::convert_status,
-    warp_runner::{MultiPassCmd, RayGunCmd, WarpCmd},
-    STATIC_ARGS, UPLINK_ROUTES, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, RayGunCmd, WarpCmd},
+    STATIC_ARGS, UPLINK_ROUTES,
 };
 
 #[allow(clippy::large_enum_variant)]
@@ -52,7 +52,6 @@ pub fn Friends(cx: Scope) -> Element {
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<ChanCmd>| {
         to_owned![chat_with];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(cmd) = rx.next().await {
                 match cmd {
                     ChanCmd::CreateConversation { chat, recipient } => {
@@ -61,18 +60,14 @@ pub fn Friends(cx: Scope) -> Element {
                             Some(c) => c,
                             None => {
                                 // if not, create the chat
-                                let (tx, rx) =
-                                    oneshot::channel::<Result<Chat, warp::error::Error>>();
-                                if let Err(e) = warp_cmd_tx.send(WarpCmd::RayGun(
-                                    RayGunCmd::CreateConversation { recipient, rsp: tx },
-                                )) {
-                                    log::error!("failed to send warp command: {}", e);
-                                    continue;
-                                }
-
-                                let rsp = rx.await.expect("command canceled");
-
-                                match rsp {
+                                match warp_runner::run_cmd(|rsp| {
+                                    WarpCmd::RayGun(RayGunCmd::CreateConversation {
+                                        recipient,
+                                        rsp,
+                                    })
+                                })
+                                .await
+                                {
                                     Ok(c) => c,
                                     Err(e) => {
                                         log::error!("failed to create conversation: {}", e);
@@ -84,51 +79,33 @@ pub fn Friends(cx: Scope) -> Element {
                         chat_with.set(Some(chat));
                     }
                     ChanCmd::RemoveFriend(did) => {
-                        let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                        if let Err(e) =
-                            warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::RemoveFriend {
-                                did,
-                                rsp: tx,
-                            }))
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::MultiPass(MultiPassCmd::RemoveFriend { did, rsp })
+                        })
+                        .await
                         {
-                            log::error!("failed to send warp command: {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
-                        if let Err(e) = rsp {
                             log::error!("failed to remove friend: {}", e);
                         }
                     }
                     ChanCmd::BlockFriend(did) => {
-                        let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                        if let Err(e) = warp_cmd_tx
-                            .send(WarpCmd::MultiPass(MultiPassCmd::Block { did, rsp: tx }))
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::MultiPass(MultiPassCmd::Block { did, rsp })
+                        })
+                        .await
                         {
-                            log::error!("failed to send warp command: {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
-                        if let Err(e) = rsp {
                             // todo: display message to user
                             log::error!("failed to block friend: {}", e);
                         }
                     }
                     ChanCmd::RemoveDirectConvs(recipient) => {
-                        let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                        if let Err(e) =
-                            warp_cmd_tx.send(WarpCmd::RayGun(RayGunCmd::RemoveDirectConvs {
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::RayGun(RayGunCmd::RemoveDirectConvs {
                                 recipient: recipient.clone(),
-                                rsp: tx,
-                            }))
+                                rsp,
+                            })
+                        })
+                        .await
                         {
-                            log::error!("failed to send warp command: {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
-                        if let Err(e) = rsp {
                             log::error!(
                                 "failed to remove conversation with friend {}: {}",
                                 recipient,
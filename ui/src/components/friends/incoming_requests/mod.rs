@@ -2,12 +2,12 @@ use crate::{
     components::friends::friend::Friend,
     state::{Action, Identity, State},
     utils::convert_status,
-    warp_runner::{MultiPassCmd, WarpCmd},
-    STATIC_ARGS, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, WarpCmd},
+    STATIC_ARGS,
 };
 use chrono::{Duration, Utc};
 use dioxus::prelude::*;
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     components::{
         context_menu::{ContextItem, ContextMenu},
@@ -34,40 +34,29 @@ pub fn PendingFriends(cx: Scope) -> Element {
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<ChanCmd>| {
         //to_owned![];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(cmd) = rx.next().await {
                 match cmd {
                     ChanCmd::AcceptRequest(identity) => {
-                        let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                        if let Err(e) =
-                            warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::AcceptRequest {
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::MultiPass(MultiPassCmd::AcceptRequest {
                                 did: identity.did_key(),
-                                rsp: tx,
-                            }))
+                                rsp,
+                            })
+                        })
+                        .await
                         {
-                            log::error!("failed to send warp command: {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
-                        if let Err(e) = rsp {
                             log::error!("failed to accept request: {}", e);
                         }
                     }
                     ChanCmd::DenyRequest(identity) => {
-                        let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                        if let Err(e) =
-                            warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::DenyRequest {
+                        if let Err(e) = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::MultiPass(MultiPassCmd::DenyRequest {
                                 did: identity.did_key(),
-                                rsp: tx,
-                            }))
+                                rsp,
+                            })
+                        })
+                        .await
                         {
-                            log::error!("failed to send warp command: {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
-                        if let Err(e) = rsp {
                             log::error!("failed to deny request: {}", e);
                         }
                     }
@@ -2,12 +2,12 @@ use crate::{
     components::friends::friend::Friend,
     state::{Action, State},
     utils::convert_status,
-    warp_runner::{MultiPassCmd, WarpCmd},
-    STATIC_ARGS, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, WarpCmd},
+    STATIC_ARGS,
 };
 use chrono::{Duration, Utc};
 use dioxus::prelude::*;
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     components::{
         context_menu::{ContextItem, ContextMenu},
@@ -29,19 +29,12 @@ pub fn OutgoingRequests(cx: Scope) -> Element {
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<DID>| {
         //to_owned![];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(did) = rx.next().await {
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-                if let Err(e) = warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::CancelRequest {
-                    did,
-                    rsp: tx,
-                })) {
-                    log::error!("failed to send warp command: {}", e);
-                    continue;
-                }
-
-                let rsp = rx.await.expect("command canceled");
-                if let Err(e) = rsp {
+                if let Err(e) = warp_runner::run_cmd(|rsp| {
+                    WarpCmd::MultiPass(MultiPassCmd::CancelRequest { did, rsp })
+                })
+                .await
+                {
                     log::error!("failed to cancel request: {}", e);
                 }
             }
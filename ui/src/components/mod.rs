@@ -3,5 +3,8 @@ pub mod chat;
 pub mod debug_logger;
 pub mod friends;
 pub mod media;
+pub mod notification_center;
+pub mod presence;
+pub mod recovery_phrase;
 pub mod settings;
 pub mod toast;
@@ -0,0 +1,139 @@
+use dioxus::prelude::*;
+use dioxus_router::use_router;
+use kit::{
+    elements::{button::Button, label::Label, Appearance},
+    icons::{Icon, IconElement},
+};
+use shared::language::get_local_text;
+
+use crate::{
+    state::{
+        notifications::{Notification, NotificationKind, NotificationLink},
+        Action, State,
+    },
+    utils::format_timestamp::format_timestamp_timeago,
+    UPLINK_ROUTES,
+};
+
+fn get_icon(kind: NotificationKind) -> Icon {
+    match kind {
+        NotificationKind::FriendRequest => Icon::UserPlus,
+        NotificationKind::Message => Icon::ChatBubbleBottomCenterText,
+        NotificationKind::Mention => Icon::AtSymbol,
+        NotificationKind::Settings => Icon::Cog6Tooth,
+    }
+}
+
+// lists the recent friend requests, messages, mentions and system events. clicking an entry goes to what it's about
+#[allow(non_snake_case)]
+pub fn NotificationCenter(cx: Scope) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let router = use_router(cx);
+    let history = state.read().ui.notifications.history.clone();
+    let has_unread = history.iter().any(|n| !n.read);
+    let active_language = state.read().settings.language.clone();
+
+    let open = move |notification: &Notification| {
+        state
+            .write()
+            .mutate(Action::MarkNotificationRead(notification.id));
+        let route = match notification.link {
+            NotificationLink::Friends => UPLINK_ROUTES.friends,
+            NotificationLink::Settings => UPLINK_ROUTES.settings,
+            NotificationLink::Chat {
+                conversation_id,
+                message_id,
+            } => {
+                let chat = state.read().chats.all.get(&conversation_id).cloned();
+                match (chat, message_id) {
+                    (Some(_), Some(message_id)) => state
+                        .write()
+                        .mutate(Action::JumpToMessage(conversation_id, message_id)),
+                    (Some(chat), None) => state.write().mutate(Action::ChatWith(chat)),
+                    // the chat was deleted or left
+                    (None, _) => return,
+                }
+                UPLINK_ROUTES.chat
+            }
+        };
+        state.write().mutate(Action::ShowNotificationCenter(false));
+        router.replace_route(route, None, None);
+    };
+
+    cx.render(rsx!(
+        div {
+            id: "notification-center",
+            aria_label: "notification-center",
+            div {
+                class: "notification-center-header",
+                Label {
+                    text: get_local_text("notification-center.notifications"),
+                },
+                div {
+                    class: "notification-center-actions",
+                    Button {
+                        icon: Icon::Check,
+                        aria_label: "mark-all-read-button".into(),
+                        text: get_local_text("notification-center.mark-all-read"),
+                        appearance: Appearance::Secondary,
+                        disabled: !has_unread,
+                        onpress: move |_| {
+                            state.write().mutate(Action::MarkAllNotificationsRead);
+                        }
+                    },
+                    Button {
+                        icon: Icon::Trash,
+                        aria_label: "clear-notifications-button".into(),
+                        text: get_local_text("notification-center.clear"),
+                        appearance: Appearance::Secondary,
+                        disabled: history.is_empty(),
+                        onpress: move |_| {
+                            state.write().mutate(Action::ClearAllNotifications);
+                        }
+                    },
+                }
+            },
+            history.is_empty().then(|| rsx!(
+                p {
+                    class: "notification-center-empty",
+                    get_local_text("notification-center.empty")
+                }
+            )),
+            div {
+                class: "notification-center-list",
+                history.iter().cloned().map(|notification| {
+                    let key = notification.id;
+                    let icon = get_icon(notification.kind);
+                    let timeago = format_timestamp_timeago(notification.date, active_language.clone());
+                    let (title, text, read) = (notification.title.clone(), notification.text.clone(), notification.read);
+                    rsx!(
+                        div {
+                            key: "{key}",
+                            class: format_args!("notification {}", if read { "" } else { "unread" }),
+                            aria_label: "notification",
+                            onclick: move |_| open(&notification),
+                            IconElement {
+                                icon: icon,
+                            },
+                            div {
+                                class: "notification-content",
+                                p {
+                                    class: "notification-title",
+                                    "{title}"
+                                },
+                                p {
+                                    class: "notification-text",
+                                    "{text}"
+                                },
+                            },
+                            p {
+                                class: "notification-time",
+                                "{timeago}"
+                            }
+                        }
+                    )
+                })
+            }
+        }
+    ))
+}
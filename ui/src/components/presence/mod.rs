@@ -0,0 +1,100 @@
+use dioxus::prelude::*;
+use kit::{
+    components::{
+        indicator::{Indicator, Platform, Status},
+        user_image::UserImage,
+    },
+    icons::{Icon, IconElement},
+};
+use shared::language::get_local_text;
+
+use crate::{
+    state::{Action, Presence, State},
+    utils::convert_status,
+};
+
+fn presence_options() -> Vec<(Presence, Status, String)> {
+    vec![
+        (
+            Presence::Online,
+            Status::Online,
+            get_local_text("presence.online"),
+        ),
+        (
+            Presence::Away,
+            Status::Idle,
+            get_local_text("presence.away"),
+        ),
+        (
+            Presence::Busy,
+            Status::DoNotDisturb,
+            get_local_text("presence.busy"),
+        ),
+        (
+            Presence::Invisible,
+            Status::Offline,
+            get_local_text("presence.invisible"),
+        ),
+    ]
+}
+
+// the user's own avatar and name. clicking it opens a menu to pick the presence friends see
+#[allow(non_snake_case)]
+pub fn PresencePicker(cx: Scope) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let show_menu = use_state(cx, || false);
+    let identity = state.read().account.identity.clone();
+    let presence = state.read().account.presence;
+    let username = identity.username();
+
+    cx.render(rsx!(
+        div {
+            id: "presence-picker",
+            aria_label: "presence-picker",
+            show_menu.get().then(|| rsx!(
+                div {
+                    class: "presence-menu",
+                    aria_label: "presence-menu",
+                    presence_options().into_iter().map(|(option, status, label)| rsx!(
+                        div {
+                            key: "{label}",
+                            class: format_args!("presence-option {}", if option == presence { "selected" } else { "" }),
+                            aria_label: "presence-option",
+                            onclick: move |_| {
+                                state.write().mutate(Action::SetPresence(option));
+                                show_menu.set(false);
+                            },
+                            Indicator {
+                                platform: Platform::Desktop,
+                                status: status,
+                            },
+                            p { "{label}" }
+                        }
+                    )),
+                    div {
+                        class: "presence-option",
+                        aria_label: "lock-option",
+                        onclick: move |_| {
+                            state.write().ui.lock_requested = true;
+                            show_menu.set(false);
+                        },
+                        IconElement {
+                            icon: Icon::LockClosed,
+                        },
+                        p { get_local_text("presence.lock") }
+                    }
+                }
+            )),
+            div {
+                class: "presence-current",
+                onclick: move |_| show_menu.set(!show_menu.get()),
+                UserImage {
+                    platform: Platform::Desktop,
+                    status: convert_status(&identity.identity_status()),
+                    image: identity.graphics().profile_picture(),
+                },
+                p { "{username}" }
+            }
+        }
+    ))
+}
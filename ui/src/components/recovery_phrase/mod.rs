@@ -0,0 +1,26 @@
+use dioxus::prelude::*;
+
+use crate::warp_runner::Passphrase;
+
+// the words of a recovery phrase, numbered so they can be written down in order
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn RecoveryPhrase(cx: Scope, phrase: Passphrase) -> Element {
+    cx.render(rsx!(
+        div {
+            class: "recovery-phrase",
+            aria_label: "recovery-phrase",
+            phrase.split_whitespace().enumerate().map(|(idx, word)| {
+                let num = idx + 1;
+                rsx!(
+                    div {
+                        key: "{num}",
+                        class: "recovery-word",
+                        span { class: "recovery-word-number", "{num}" },
+                        span { "{word}" }
+                    }
+                )
+            })
+        }
+    ))
+}
@@ -10,7 +10,10 @@ use warp::logging::tracing::log;
 use crate::{
     components::settings::SettingSection,
     logger,
-    state::{notifications::NotificationKind, Action, State},
+    state::{
+        notifications::{Notification, NotificationKind, NotificationLink},
+        Action, State,
+    },
     utils::{notifications::push_notification, sounds::Sounds},
     window_manager::{WindowManagerCmd, WindowManagerCmdTx},
     STATIC_ARGS,
@@ -67,9 +70,12 @@ pub fn DeveloperSettings(cx: Scope) -> Element {
                             Some(Sounds::General),
                             notify_rust::Timeout::Milliseconds(4),
                         );
-                        state
-                            .write()
-                            .mutate(Action::AddNotification(NotificationKind::Settings, 1));
+                        state.write().mutate(Action::AddNotification(Notification::new(
+                            NotificationKind::Settings,
+                            "Test".to_string(),
+                            "Test".to_string(),
+                            NotificationLink::Settings,
+                        )));
                         }
                     }
             },
@@ -3,7 +3,9 @@ use kit::{
     elements::{button::Button, select::Select, switch::Switch},
     icons::Icon,
 };
-use shared::language::{change_language, get_available_languages, get_local_text};
+use shared::language::{
+    change_language, get_available_languages, get_local_text, get_local_text_with_args, FluentValue,
+};
 use warp::logging::tracing::log;
 
 use crate::{
@@ -12,6 +14,20 @@ use crate::{
     utils::get_available_themes,
 };
 
+// the idle periods offered for auto-away, in minutes. 0 turns it off
+const AUTO_AWAY_MINUTES: [u32; 6] = [0, 5, 10, 15, 30, 60];
+
+fn auto_away_label(minutes: u32) -> String {
+    if minutes == 0 {
+        get_local_text("settings-general.auto-away-never")
+    } else {
+        get_local_text_with_args(
+            "settings-general.auto-away-minutes",
+            vec![("minutes", FluentValue::from(minutes))],
+        )
+    }
+}
+
 #[allow(non_snake_case)]
 pub fn GeneralSettings(cx: Scope) -> Element {
     let state = use_shared_state::<State>(cx)?;
@@ -73,6 +89,19 @@ pub fn GeneralSettings(cx: Scope) -> Element {
                     }
                 }
             },
+            SettingSection {
+                section_label: get_local_text("settings-general.auto-away"),
+                section_description: get_local_text("settings-general.auto-away-description"),
+                Select {
+                    initial_value: auto_away_label(state.read().configuration.config.general.auto_away_minutes),
+                    options: AUTO_AWAY_MINUTES.iter().map(|m| auto_away_label(*m)).collect(),
+                    onselect: move |value| {
+                        if let Some(minutes) = AUTO_AWAY_MINUTES.iter().find(|m| auto_away_label(**m) == value) {
+                            state.write().configuration.set_auto_away_minutes(*minutes);
+                        }
+                    }
+                }
+            },
             SettingSection {
                 section_label: get_local_text("settings-general.app-language"),
                 section_description: get_local_text("settings-general.change-language"),
@@ -1,15 +1,44 @@
 use dioxus::prelude::*;
 use kit::{
-    elements::{button::Button, switch::Switch},
+    elements::{button::Button, select::Select, switch::Switch, Appearance},
     icons::Icon,
 };
 use shared::language::get_local_text;
 
-use crate::{components::settings::SettingSection, state::State};
+use crate::{components::settings::SettingSection, config::DndSchedule, state::State};
+
+// the times offered for the do not disturb schedule, in minutes after midnight
+const DND_SCHEDULE_STEP: u16 = 30;
+
+fn format_minutes(minutes: u16) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+fn dnd_schedule_times() -> Vec<String> {
+    (0..24 * 60)
+        .step_by(DND_SCHEDULE_STEP as usize)
+        .map(format_minutes)
+        .collect()
+}
+
+fn parse_minutes(time: &str) -> Option<u16> {
+    let (hours, minutes) = time.split_once(':')?;
+    Some(hours.parse::<u16>().ok()? * 60 + minutes.parse::<u16>().ok()?)
+}
 
 #[allow(non_snake_case)]
 pub fn NotificationSettings(cx: Scope) -> Element {
     let state = use_shared_state::<State>(cx)?;
+    let dnd_schedule = state.read().configuration.config.notifications.dnd_schedule;
+    let days = [
+        "settings-notifications.monday",
+        "settings-notifications.tuesday",
+        "settings-notifications.wednesday",
+        "settings-notifications.thursday",
+        "settings-notifications.friday",
+        "settings-notifications.saturday",
+        "settings-notifications.sunday",
+    ];
 
     cx.render(rsx!(
         div {
@@ -39,6 +68,93 @@ pub fn NotificationSettings(cx: Scope) -> Element {
                     }
                 }
             },
+            SettingSection {
+                section_label: get_local_text("settings-notifications.do-not-disturb"),
+                section_description: get_local_text("settings-notifications.do-not-disturb-description"),
+                Switch {
+                    active: state.read().configuration.config.notifications.do_not_disturb,
+                    onflipped: move |e| {
+                        if state.read().configuration.config.audiovideo.interface_sounds {
+                            crate::utils::sounds::Play(crate::utils::sounds::Sounds::Flip);
+                        }
+                        state.write().configuration.set_do_not_disturb(e);
+                    }
+                }
+            },
+            SettingSection {
+                section_label: get_local_text("settings-notifications.dnd-schedule"),
+                section_description: get_local_text("settings-notifications.dnd-schedule-description"),
+                Switch {
+                    active: dnd_schedule.enabled,
+                    onflipped: move |e| {
+                        if state.read().configuration.config.audiovideo.interface_sounds {
+                            crate::utils::sounds::Play(crate::utils::sounds::Sounds::Flip);
+                        }
+                        state.write().configuration.set_dnd_schedule(DndSchedule {
+                            enabled: e,
+                            ..dnd_schedule
+                        });
+                    }
+                }
+            },
+            dnd_schedule.enabled.then(|| rsx!(
+                SettingSection {
+                    section_label: get_local_text("settings-notifications.dnd-from"),
+                    section_description: get_local_text("settings-notifications.dnd-from-description"),
+                    Select {
+                        initial_value: format_minutes(dnd_schedule.start),
+                        options: dnd_schedule_times(),
+                        onselect: move |value: String| {
+                            if let Some(start) = parse_minutes(&value) {
+                                state.write().configuration.set_dnd_schedule(DndSchedule {
+                                    start,
+                                    ..dnd_schedule
+                                });
+                            }
+                        }
+                    }
+                },
+                SettingSection {
+                    section_label: get_local_text("settings-notifications.dnd-until"),
+                    section_description: get_local_text("settings-notifications.dnd-until-description"),
+                    Select {
+                        initial_value: format_minutes(dnd_schedule.end),
+                        options: dnd_schedule_times(),
+                        onselect: move |value: String| {
+                            if let Some(end) = parse_minutes(&value) {
+                                state.write().configuration.set_dnd_schedule(DndSchedule {
+                                    end,
+                                    ..dnd_schedule
+                                });
+                            }
+                        }
+                    }
+                },
+                SettingSection {
+                    section_label: get_local_text("settings-notifications.dnd-days"),
+                    section_description: get_local_text("settings-notifications.dnd-days-description"),
+                    div {
+                        class: "dnd-days",
+                        days.iter().enumerate().map(|(idx, day)| {
+                            let selected = dnd_schedule.days[idx];
+                            rsx!(Button {
+                                key: "{day}",
+                                text: get_local_text(day),
+                                aria_label: day.to_string(),
+                                appearance: if selected { Appearance::Primary } else { Appearance::Secondary },
+                                onpress: move |_| {
+                                    let mut days = dnd_schedule.days;
+                                    days[idx] = !selected;
+                                    state.write().configuration.set_dnd_schedule(DndSchedule {
+                                        days,
+                                        ..dnd_schedule
+                                    });
+                                }
+                            })
+                        })
+                    }
+                },
+            )),
             div {
                 class: format_args!("{}", if state.read().configuration.config.notifications.enabled { "enabled" } else { "disabled" }),
                 SettingSection {
@@ -69,6 +185,23 @@ pub fn NotificationSettings(cx: Scope) -> Element {
                         }
                     }
                 },
+                SettingSection {
+                    section_label: get_local_text("settings-notifications.message-previews"),
+                    section_description: get_local_text("settings-notifications.message-previews-description"),
+                    Switch {
+                        active: state.read().configuration.config.notifications.enabled
+                            && state.read().configuration.config.notifications.messages_notifications
+                            && state.read().configuration.config.notifications.message_previews,
+                        disabled: !state.read().configuration.config.notifications.enabled
+                            || !state.read().configuration.config.notifications.messages_notifications,
+                        onflipped: move |e| {
+                            if state.read().configuration.config.audiovideo.interface_sounds {
+                                crate::utils::sounds::Play(crate::utils::sounds::Sounds::Flip);
+                            }
+                            state.write().configuration.set_message_previews(e);
+                        }
+                    }
+                },
                 SettingSection {
                     section_label: get_local_text("settings"),
                     section_description: get_local_text("settings-notifications.settings-description"),
@@ -1,16 +1,96 @@
 use dioxus::prelude::*;
+use futures::StreamExt;
 use kit::{
-    elements::{button::Button, Appearance},
+    elements::{
+        button::Button,
+        input::{Input, Options, Validation},
+        select::Select,
+        Appearance,
+    },
     icons::Icon,
 };
-use shared::language::get_local_text;
+use shared::language::{get_local_text, get_local_text_with_args, FluentValue};
 use warp::logging::tracing::log;
 
-use crate::components::settings::SettingSection;
+use crate::{
+    components::{recovery_phrase::RecoveryPhrase, settings::SettingSection},
+    state::{Action, State, ToastNotification},
+    utils::passphrase::{self, Strength},
+    warp_runner::{self, Passphrase, TesseractCmd, UnlockError, WarpCmd},
+};
+
+// the idle periods offered for auto-lock, in minutes. 0 turns it off
+const AUTO_LOCK_MINUTES: [u32; 7] = [0, 1, 5, 10, 15, 30, 60];
+
+fn auto_lock_label(minutes: u32) -> String {
+    if minutes == 0 {
+        get_local_text("settings-privacy.auto-lock-never")
+    } else {
+        get_local_text_with_args(
+            "settings-privacy.auto-lock-minutes",
+            vec![("minutes", FluentValue::from(minutes))],
+        )
+    }
+}
+
+// wrong PINs in a row before the account is erased. 0 turns it off
+const WIPE_AFTER_ATTEMPTS: [u32; 4] = [0, 10, 20, 50];
+
+fn wipe_after_label(attempts: u32) -> String {
+    if attempts == 0 {
+        get_local_text("settings-privacy.wipe-after-never")
+    } else {
+        get_local_text_with_args(
+            "settings-privacy.wipe-after-attempts",
+            vec![("attempts", FluentValue::from(attempts))],
+        )
+    }
+}
 
 #[allow(non_snake_case)]
 pub fn PrivacySettings(cx: Scope) -> Element {
     log::debug!("Privacy settings page rendered.");
+    let state = use_shared_state::<State>(cx)?;
+    // zeroed once it's hidden. the words rendered into the page are out of our hands
+    let phrase: &UseState<Option<Passphrase>> = use_state(cx, || None);
+    let asking_pin = use_state(cx, || false);
+    let error: &UseState<Option<String>> = use_state(cx, || None);
+
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<Passphrase>| {
+        to_owned![phrase, asking_pin, error];
+        async move {
+            while let Some(passphrase) = rx.next().await {
+                let res = warp_runner::send_cmd(|rsp| {
+                    WarpCmd::Tesseract(TesseractCmd::GetRecoveryPhrase { passphrase, rsp })
+                })
+                .await;
+                match res {
+                    Ok(Ok(p)) => {
+                        asking_pin.set(false);
+                        error.set(None);
+                        phrase.set(Some(p));
+                    }
+                    Ok(Err(UnlockError::WrongPin)) => {
+                        error.set(Some(get_local_text("settings-privacy.wrong-pin")));
+                    }
+                    Ok(Err(UnlockError::RetryIn(_))) => {
+                        error.set(Some(get_local_text("settings-privacy.try-later")));
+                    }
+                    Ok(Err(e)) => {
+                        log::error!("failed to get recovery phrase: {}", e);
+                        asking_pin.set(false);
+                        error.set(Some(get_local_text("settings-privacy.no-phrase")));
+                    }
+                    Err(e) => {
+                        log::error!("failed to get recovery phrase: {}", e);
+                        asking_pin.set(false);
+                        error.set(Some(get_local_text("settings-privacy.no-phrase")));
+                    }
+                }
+            }
+        }
+    });
+
     cx.render(rsx!(
         div {
             id: "settings-privacy",
@@ -18,13 +98,280 @@ pub fn PrivacySettings(cx: Scope) -> Element {
             SettingSection {
                 section_label: get_local_text("settings-privacy.backup-recovery-phrase"),
                 section_description: get_local_text("settings-privacy.backup-phrase-description"),
-                Button {
-                    text: get_local_text("settings-privacy.backup-phrase"),
-                    aria_label: "backup-phrase-button".into(),
-                    appearance: Appearance::Secondary,
-                    icon: Icon::DocumentText,
+                phrase.get().is_some().then(|| rsx!(
+                    Button {
+                        text: get_local_text("settings-privacy.hide-phrase"),
+                        aria_label: "hide-phrase-button".into(),
+                        appearance: Appearance::Secondary,
+                        icon: Icon::EyeSlash,
+                        onpress: move |_| phrase.set(None),
+                    }
+                )),
+                phrase.get().is_none().then(|| rsx!(
+                    Button {
+                        text: get_local_text("settings-privacy.backup-phrase"),
+                        aria_label: "backup-phrase-button".into(),
+                        appearance: Appearance::Secondary,
+                        icon: Icon::DocumentText,
+                        onpress: move |_| {
+                            error.set(None);
+                            asking_pin.set(true);
+                        }
+                    }
+                ))
+            },
+            asking_pin.get().then(|| rsx!(
+                div {
+                    class: "recovery-phrase-auth",
+                    Input {
+                        id: "recovery-phrase-pin".to_owned(),
+                        focus: true,
+                        is_password: true,
+                        icon: Icon::Key,
+                        aria_label: "recovery-phrase-pin".into(),
+                        placeholder: get_local_text("settings-privacy.enter-pin"),
+                        options: Options {
+                            with_clear_btn: true,
+                            ..Default::default()
+                        }
+                        onreturn: move |(val, _): (String, bool)| ch.send(Passphrase::new(val)),
+                    }
+                }
+            )),
+            error.get().as_ref().map(|error| rsx!(
+                p {
+                    class: "error",
+                    aria_label: "recovery-phrase-error",
+                    "{error}"
+                }
+            )),
+            phrase.get().as_ref().map(|phrase| rsx!(
+                RecoveryPhrase {
+                    phrase: phrase.clone(),
+                }
+            )),
+            ChangePassphrase {},
+            SettingSection {
+                section_label: get_local_text("settings-privacy.wipe-after"),
+                section_description: get_local_text("settings-privacy.wipe-after-description"),
+                Select {
+                    initial_value: wipe_after_label(state.read().configuration.config.privacy.wipe_after_failed_unlocks),
+                    options: WIPE_AFTER_ATTEMPTS.iter().map(|n| wipe_after_label(*n)).collect(),
+                    onselect: move |value| {
+                        if let Some(attempts) = WIPE_AFTER_ATTEMPTS.iter().find(|n| wipe_after_label(**n) == value) {
+                            state.write().configuration.set_wipe_after_failed_unlocks(*attempts);
+                        }
+                    }
                 }
             },
+            SettingSection {
+                section_label: get_local_text("settings-privacy.auto-lock"),
+                section_description: get_local_text("settings-privacy.auto-lock-description"),
+                Select {
+                    initial_value: auto_lock_label(state.read().configuration.config.privacy.auto_lock_minutes),
+                    options: AUTO_LOCK_MINUTES.iter().map(|m| auto_lock_label(*m)).collect(),
+                    onselect: move |value| {
+                        if let Some(minutes) = AUTO_LOCK_MINUTES.iter().find(|m| auto_lock_label(**m) == value) {
+                            state.write().configuration.set_auto_lock_minutes(*minutes);
+                        }
+                    }
+                }
+            },
+        }
+    ))
+}
+
+// same rules as the PIN entered on the unlock screen
+fn pin_validation() -> Validation {
+    Validation {
+        max_length: Some(32),
+        min_length: Some(4),
+        alpha_numeric_only: false,
+        no_whitespace: true,
+        ignore_colons: false,
+        special_chars_allowed: None,
+    }
+}
+
+#[inline_props]
+#[allow(non_snake_case)]
+fn StrengthMeter(cx: Scope, strength: Strength) -> Element {
+    let filled = strength.level();
+    let label = get_local_text(strength.label_key());
+    cx.render(rsx!(
+        div {
+            class: "strength-meter",
+            aria_label: "strength-meter",
+            div {
+                class: format_args!("strength-bars strength-{filled}"),
+                (1..=Strength::Strong.level()).map(|idx| rsx!(
+                    div {
+                        key: "{idx}",
+                        class: format_args!("strength-bar {}", if idx <= filled { "filled" } else { "" }),
+                    }
+                ))
+            },
+            p { "{label}" }
+        }
+    ))
+}
+
+#[allow(non_snake_case)]
+fn ChangePassphrase(cx: Scope) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let changing = use_state(cx, || false);
+    let in_progress = use_state(cx, || false);
+    let error: &UseState<Option<String>> = use_state(cx, || None);
+    let old = use_ref(cx, || Passphrase::new(String::new()));
+    let new = use_state(cx, || Passphrase::new(String::new()));
+    let new_valid = use_state(cx, || false);
+    let confirm = use_state(cx, || Passphrase::new(String::new()));
+
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<(Passphrase, Passphrase)>| {
+        to_owned![state, changing, in_progress, error];
+        async move {
+            while let Some((old, new)) = rx.next().await {
+                in_progress.set(true);
+                let res = warp_runner::send_cmd(|rsp| {
+                    WarpCmd::Tesseract(TesseractCmd::ChangePassphrase { old, new, rsp })
+                })
+                .await;
+                in_progress.set(false);
+                match res {
+                    Ok(Ok(_)) => {
+                        changing.set(false);
+                        error.set(None);
+                        state.write().mutate(Action::AddToastNotification(
+                            ToastNotification::init(
+                                get_local_text("settings-privacy.change-pin"),
+                                get_local_text("settings-privacy.pin-changed"),
+                                None,
+                                2,
+                            ),
+                        ));
+                    }
+                    Ok(Err(UnlockError::WrongPin)) => {
+                        error.set(Some(get_local_text("settings-privacy.wrong-pin")));
+                    }
+                    Ok(Err(UnlockError::RetryIn(_))) => {
+                        error.set(Some(get_local_text("settings-privacy.try-later")));
+                    }
+                    Ok(Err(e)) => {
+                        log::error!("failed to change passphrase: {}", e);
+                        error.set(Some(get_local_text("settings-privacy.change-pin-failed")));
+                    }
+                    Err(e) => {
+                        log::error!("failed to change passphrase: {}", e);
+                        error.set(Some(get_local_text("settings-privacy.change-pin-failed")));
+                    }
+                }
+            }
+        }
+    });
+
+    let mismatch = !confirm.get().is_empty() && confirm.get() != new.get();
+    let can_submit = !*in_progress.get()
+        && !old.read().is_empty()
+        && *new_valid.get()
+        && confirm.get() == new.get();
+    let submit = move || {
+        if can_submit {
+            ch.send((old.read().clone(), new.get().clone()));
         }
+    };
+
+    cx.render(rsx!(
+        SettingSection {
+            section_label: get_local_text("settings-privacy.change-pin"),
+            section_description: get_local_text("settings-privacy.change-pin-description"),
+            Button {
+                text: get_local_text(if *changing.get() { "settings-privacy.cancel" } else { "settings-privacy.change-pin" }),
+                aria_label: "change-pin-button".into(),
+                appearance: Appearance::Secondary,
+                icon: Icon::Key,
+                disabled: *in_progress.get(),
+                onpress: move |_| {
+                    if !*changing.get() {
+                        *old.write_silent() = Passphrase::new(String::new());
+                        new.set(Passphrase::new(String::new()));
+                        new_valid.set(false);
+                        confirm.set(Passphrase::new(String::new()));
+                        error.set(None);
+                    }
+                    changing.set(!changing.get());
+                }
+            }
+        },
+        changing.get().then(|| rsx!(
+            div {
+                class: "change-pin-form",
+                aria_label: "change-pin-form",
+                Input {
+                    id: "current-pin".to_owned(),
+                    focus: true,
+                    is_password: true,
+                    icon: Icon::Key,
+                    aria_label: "current-pin-input".into(),
+                    disabled: *in_progress.get(),
+                    placeholder: get_local_text("settings-privacy.current-pin"),
+                    onchange: move |(val, _): (String, bool)| {
+                        error.set(None);
+                        *old.write() = Passphrase::new(val);
+                    }
+                },
+                Input {
+                    id: "new-pin".to_owned(),
+                    is_password: true,
+                    icon: Icon::Key,
+                    aria_label: "new-pin-input".into(),
+                    disabled: *in_progress.get(),
+                    placeholder: get_local_text("settings-privacy.new-pin"),
+                    options: Options {
+                        with_validation: Some(pin_validation()),
+                        ..Default::default()
+                    }
+                    onchange: move |(val, is_valid): (String, bool)| {
+                        new_valid.set(is_valid);
+                        new.set(Passphrase::new(val));
+                    }
+                },
+                (!new.get().is_empty()).then(|| rsx!(
+                    StrengthMeter {
+                        strength: passphrase::strength(new.get()),
+                    }
+                )),
+                Input {
+                    id: "confirm-pin".to_owned(),
+                    is_password: true,
+                    icon: Icon::Key,
+                    aria_label: "confirm-pin-input".into(),
+                    disabled: *in_progress.get(),
+                    placeholder: get_local_text("settings-privacy.confirm-pin"),
+                    onchange: move |(val, _): (String, bool)| confirm.set(Passphrase::new(val)),
+                    onreturn: move |_| submit(),
+                },
+                mismatch.then(|| rsx!(
+                    p {
+                        class: "error",
+                        get_local_text("settings-privacy.pin-mismatch")
+                    }
+                )),
+                error.get().as_ref().map(|error| rsx!(
+                    p {
+                        class: "error",
+                        aria_label: "change-pin-error",
+                        "{error}"
+                    }
+                )),
+                Button {
+                    text: get_local_text("settings-privacy.save-pin"),
+                    aria_label: "save-pin-button".into(),
+                    appearance: Appearance::Primary,
+                    icon: Icon::Check,
+                    disabled: !can_submit,
+                    onpress: move |_| submit(),
+                }
+            }
+        )),
     ))
 }
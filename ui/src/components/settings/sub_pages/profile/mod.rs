@@ -1,16 +1,141 @@
+use std::path::{Path, PathBuf};
+
 use dioxus::prelude::*;
+use futures::StreamExt;
 use kit::elements::{
     button::Button,
     input::{Input, Options, Validation},
     label::Label,
 };
-use mime::*;
 use rfd::FileDialog;
 use shared::language::get_local_text;
-use warp::{error::Error, logging::tracing::log};
+use warp::logging::tracing::log;
+
+use crate::{
+    state::{Action, Identity, State, ToastNotification},
+    utils::images::{prepare_profile_image, ProfileImage},
+    warp_runner::{self, MultiPassCmd, WarpCmd},
+    STATIC_ARGS,
+};
+
+enum ChanCmd {
+    Username(String),
+    StatusMessage(Option<String>),
+    Picture(String),
+    Banner(String),
+}
+
+impl ChanCmd {
+    // applies the update to the identity directly, for when warp isn't used
+    fn apply_mock(self, identity: &mut Identity) {
+        let mut graphics = identity.graphics();
+        match self {
+            ChanCmd::Username(username) => identity.set_username(&username),
+            ChanCmd::StatusMessage(status_message) => identity.set_status_message(status_message),
+            ChanCmd::Picture(picture) => graphics.set_profile_picture(&picture),
+            ChanCmd::Banner(banner) => graphics.set_profile_banner(&banner),
+        }
+        identity.set_graphics(graphics);
+    }
+}
 
 #[allow(non_snake_case)]
 pub fn ProfileSettings(cx: Scope) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let identity = state.read().account.identity.clone();
+    let username = identity.username();
+    let status_message = identity.status_message().unwrap_or_default();
+    let image = identity.graphics().profile_picture();
+    let banner = identity.graphics().profile_banner();
+
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<ChanCmd>| {
+        to_owned![state];
+        async move {
+            while let Some(cmd) = rx.next().await {
+                let res = warp_runner::run_cmd(|rsp| {
+                    WarpCmd::MultiPass(match cmd {
+                        ChanCmd::Username(username) => {
+                            MultiPassCmd::UpdateUsername { username, rsp }
+                        }
+                        ChanCmd::StatusMessage(status_message) => {
+                            MultiPassCmd::UpdateStatusMessage {
+                                status_message,
+                                rsp,
+                            }
+                        }
+                        ChanCmd::Picture(picture) => {
+                            MultiPassCmd::UpdateProfilePicture { picture, rsp }
+                        }
+                        ChanCmd::Banner(banner) => MultiPassCmd::UpdateBanner { banner, rsp },
+                    })
+                })
+                .await;
+                match res {
+                    Ok(mut identity) => {
+                        // the refreshed identity doesn't know our own presence
+                        let own = state.read().account.identity.clone();
+                        identity.set_identity_status(own.identity_status());
+                        identity.set_platform(own.platform());
+                        state.write().mutate(Action::SetId(identity));
+                    }
+                    Err(e) => {
+                        log::error!("failed to update identity: {e}");
+                        state.write().mutate(Action::AddToastNotification(
+                            ToastNotification::init(
+                                get_local_text("settings-profile.update-failed"),
+                                e.to_string(),
+                                None,
+                                2,
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    });
+
+    let update = move |cmd: ChanCmd| send_update(state, ch, cmd);
+
+    // large images take a while to decode and scale down, so it's done off the UI thread
+    let image_ch = use_coroutine(cx, |mut rx: UnboundedReceiver<(ProfileImage, PathBuf)>| {
+        to_owned![state, ch];
+        async move {
+            while let Some((kind, path)) = rx.next().await {
+                let res = tokio::task::spawn_blocking(move || read_profile_image(&path, kind))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|res| res.map_err(|e| e.to_string()));
+                match res {
+                    Ok(image) => send_update(
+                        &state,
+                        &ch,
+                        match kind {
+                            ProfileImage::Picture => ChanCmd::Picture(image),
+                            ProfileImage::Banner => ChanCmd::Banner(image),
+                        },
+                    ),
+                    Err(error) => {
+                        log::error!("Error to change profile image {error}");
+                        state.write().mutate(Action::AddToastNotification(
+                            ToastNotification::init(
+                                get_local_text("settings-profile.invalid-image"),
+                                error,
+                                None,
+                                2,
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    });
+
+    let pick_image = move |kind: ProfileImage| {
+        if let Some(path) = pick_profile_image() {
+            image_ch.send((kind, path));
+        }
+    };
+
     // Set up validation options for the input field
     let username_validation_options = Validation {
         // The input should have a maximum length of 32
@@ -44,9 +169,6 @@ pub fn ProfileSettings(cx: Scope) -> Element {
         special_chars_allowed: None,
     };
 
-    let image_state = use_state(cx, String::new);
-    let banner_state = use_state(cx, String::new);
-
     let change_banner_text = get_local_text("settings-profile.change-banner");
     log::debug!("Profile settings page rendered.");
     cx.render(rsx!(
@@ -59,31 +181,19 @@ pub fn ProfileSettings(cx: Scope) -> Element {
                 div {
                     class: "profile-banner",
                     aria_label: "profile-banner",
-                    style: "background-image: url({banner_state});",
-                    onclick: move |_| {
-                        if let Err(error) = change_profile_image(banner_state) {
-                            log::error!("Error to change profile avatar image {error}");
-                        };
-                    },
+                    style: "background-image: url({banner});",
+                    onclick: move |_| pick_image(ProfileImage::Banner),
                     p {class: "change-banner-text", "{change_banner_text}" },
                 },
                 div {
                     class: "profile-picture",
                     aria_label: "profile-picture",
-                    style: "background-image: url({image_state});",
-                    onclick: move |_| {
-                        if let Err(error) = change_profile_image(image_state) {
-                            log::error!("Error to change profile avatar image {error}");
-                        };
-                    },
+                    style: "background-image: url({image});",
+                    onclick: move |_| pick_image(ProfileImage::Picture),
                     Button {
                         icon: kit::icons::Icon::Plus,
                         aria_label: "add-picture-button".into(),
-                        onpress: move |_| {
-                            if let Err(error) = change_profile_image(image_state) {
-                                log::error!("Error to change profile avatar image {error}");
-                            };
-                        }
+                        onpress: move |_| pick_image(ProfileImage::Picture),
                     },
                 }
             },
@@ -94,11 +204,7 @@ pub fn ProfileSettings(cx: Scope) -> Element {
                     class: "plus-button",
                     Button {
                         icon: kit::icons::Icon::Plus,
-                        onpress: move |_| {
-                            if let Err(error) = change_profile_image(image_state) {
-                                log::error!("Error to change profile avatar image {error}");
-                            };
-                        }
+                        onpress: move |_| pick_image(ProfileImage::Picture),
                     }
                 },
                 div {
@@ -109,8 +215,13 @@ pub fn ProfileSettings(cx: Scope) -> Element {
                     Input {
                         placeholder: get_local_text("uplink.username"),
                         aria_label: "username-input".into(),
-                        default_text: "Mock Username".into(),
+                        default_text: username.clone(),
                         options: get_input_options(username_validation_options),
+                        onreturn: move |(value, is_valid): (String, bool)| {
+                            if is_valid && value != username {
+                                update(ChanCmd::Username(value));
+                            }
+                        },
                     },
                 },
                 div {
@@ -121,11 +232,17 @@ pub fn ProfileSettings(cx: Scope) -> Element {
                     Input {
                         placeholder: get_local_text("uplink.status"),
                         aria_label: "status-input".into(),
-                        default_text: "Mock status messages are so 2008.".into(),
+                        default_text: status_message.clone(),
                         options: Options {
                             with_clear_btn: true,
                             ..get_input_options(status_validation_options)
-                        }
+                        },
+                        onreturn: move |(value, is_valid): (String, bool)| {
+                            if is_valid && value != status_message {
+                                let value = value.trim().to_string();
+                                update(ChanCmd::StatusMessage((!value.is_empty()).then_some(value)));
+                            }
+                        },
                     }
                 }
             }
@@ -133,51 +250,32 @@ pub fn ProfileSettings(cx: Scope) -> Element {
     ))
 }
 
-fn change_profile_image(image_state: &UseState<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let path = match FileDialog::new()
-        .add_filter("image", &["jpg", "png", "jpeg", "svg"])
+fn send_update(state: &UseSharedState<State>, ch: &Coroutine<ChanCmd>, cmd: ChanCmd) {
+    if STATIC_ARGS.use_mock {
+        let mut identity = state.read().account.identity.clone();
+        cmd.apply_mock(&mut identity);
+        state.write().mutate(Action::SetId(identity));
+    } else {
+        ch.send(cmd);
+    }
+}
+
+// lets the user pick an image. returns None if no file was picked
+fn pick_profile_image() -> Option<PathBuf> {
+    FileDialog::new()
+        .add_filter("image", &["jpg", "png", "jpeg"])
         .set_directory(".")
         .pick_file()
-    {
-        Some(path) => path,
-        None => return Err(Box::from(Error::InvalidItem)),
-    };
-
-    let file = std::fs::read(&path)?;
-
-    let filename = path
-        .file_name()
-        .map(|file| file.to_string_lossy().to_string())
-        .unwrap_or_default();
-
-    let parts_of_filename: Vec<&str> = filename.split('.').collect();
-
-    //Since files selected are filtered to be jpg, jpeg, png or svg the last branch is not reachable
-    let mime = match parts_of_filename.last() {
-        Some(m) => match *m {
-            "png" => IMAGE_PNG.to_string(),
-            "jpg" => IMAGE_JPEG.to_string(),
-            "jpeg" => IMAGE_JPEG.to_string(),
-            "svg" => IMAGE_SVG.to_string(),
-            &_ => "".to_string(),
-        },
-        None => "".to_string(),
-    };
-
-    let image = match &file.len() {
-        0 => "".to_string(),
-        _ => {
-            let prefix = format!("data:{mime};base64,");
-            let base64_image = base64::encode(&file);
-            let img = prefix + base64_image.as_str();
-            img
-        }
-    };
-
-    // TODO: Add upload picture to multipass here
+}
 
-    image_state.set(image);
-    Ok(())
+// validates and downscales the picked image. this blocks, so it's run with spawn_blocking
+fn read_profile_image(
+    path: &Path,
+    kind: ProfileImage,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let file = std::fs::read(path)?;
+    let image = prepare_profile_image(&file, kind)?;
+    Ok(image)
 }
 
 fn get_input_options(validation_options: Validation) -> Options {
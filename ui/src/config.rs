@@ -1,3 +1,4 @@
+use chrono::{Datelike, NaiveDateTime, Timelike};
 use serde::{Deserialize, Serialize};
 
 use std::fs;
@@ -32,7 +33,7 @@ pub struct Configuration {
     pub notifications: Notifications,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct General {
     #[serde(default)]
     pub theme: String,
@@ -40,6 +41,24 @@ pub struct General {
     pub show_splash: bool,
     #[serde(default)]
     pub enable_overlay: bool,
+    // Minutes without activity before the user is shown as away. 0 turns it off.
+    #[serde(default = "default_auto_away_minutes")]
+    pub auto_away_minutes: u32,
+}
+
+fn default_auto_away_minutes() -> u32 {
+    10
+}
+
+impl Default for General {
+    fn default() -> Self {
+        Self {
+            theme: String::new(),
+            show_splash: false,
+            enable_overlay: false,
+            auto_away_minutes: default_auto_away_minutes(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, Copy, Clone)]
@@ -48,6 +67,12 @@ pub struct Privacy {
     pub satellite_sync_nodes: bool,
     #[serde(default)]
     pub safer_file_scanning: bool,
+    // Minutes without activity before Uplink locks itself. 0 turns it off.
+    #[serde(default)]
+    pub auto_lock_minutes: u32,
+    // Wrong PINs in a row before the account is deleted from this device. 0 turns it off.
+    #[serde(default)]
+    pub wipe_after_failed_unlocks: u32,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, Copy, Clone)]
@@ -92,9 +117,80 @@ pub struct Notifications {
     pub friends_notifications: bool,
     #[serde(default = "bool_true")]
     pub messages_notifications: bool,
+    // Shows the text of new messages in their notifications. Turned off for privacy.
+    #[serde(default = "bool_true")]
+    pub message_previews: bool,
     // By default we leave this one off.
     #[serde(default)]
     pub settings_notifications: bool,
+    // Do not disturb, toggled by hand. Silences notifications until it's turned off.
+    #[serde(default)]
+    pub do_not_disturb: bool,
+    #[serde(default)]
+    pub dnd_schedule: DndSchedule,
+}
+
+impl Notifications {
+    // `now` is in local time
+    pub fn is_dnd_active(&self, now: NaiveDateTime) -> bool {
+        self.do_not_disturb || self.dnd_schedule.is_active_at(now)
+    }
+}
+
+fn all_days() -> [bool; 7] {
+    [true; 7]
+}
+
+fn default_dnd_start() -> u16 {
+    22 * 60
+}
+
+fn default_dnd_end() -> u16 {
+    7 * 60
+}
+
+// A weekly do not disturb period, e.g. every night from 22:00 to 07:00
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+pub struct DndSchedule {
+    #[serde(default)]
+    pub enabled: bool,
+    // The days the period starts on, beginning with Monday. A period which ends after midnight
+    // belongs to the day it started on.
+    #[serde(default = "all_days")]
+    pub days: [bool; 7],
+    // Minutes after midnight, in local time
+    #[serde(default = "default_dnd_start")]
+    pub start: u16,
+    #[serde(default = "default_dnd_end")]
+    pub end: u16,
+}
+
+impl Default for DndSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: all_days(),
+            start: default_dnd_start(),
+            end: default_dnd_end(),
+        }
+    }
+}
+
+impl DndSchedule {
+    pub fn is_active_at(&self, now: NaiveDateTime) -> bool {
+        if !self.enabled || self.start == self.end {
+            return false;
+        }
+        let minute = (now.hour() * 60 + now.minute()) as u16;
+        let today = now.weekday().num_days_from_monday() as usize;
+        let yesterday = now.weekday().pred().num_days_from_monday() as usize;
+        if self.start < self.end {
+            self.days[today] && (self.start..self.end).contains(&minute)
+        } else {
+            (self.days[today] && minute >= self.start)
+                || (self.days[yesterday] && minute < self.end)
+        }
+    }
 }
 
 impl Configuration {
@@ -143,3 +239,41 @@ impl Configuration {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_dnd_schedule() {
+        let mut schedule = DndSchedule {
+            enabled: true,
+            ..Default::default()
+        };
+        // 2023-03-06 is a Monday
+        let at = |day: u32, hour: u32| {
+            NaiveDate::from_ymd_opt(2023, 3, day)
+                .and_then(|d| d.and_hms_opt(hour, 0, 0))
+                .unwrap()
+        };
+        assert!(schedule.is_active_at(at(6, 23)));
+        assert!(schedule.is_active_at(at(7, 6)));
+        assert!(!schedule.is_active_at(at(7, 12)));
+
+        // off on Mondays: Monday night isn't quiet, but the early Monday morning which
+        // started on Sunday still is
+        schedule.days[0] = false;
+        assert!(!schedule.is_active_at(at(6, 23)));
+        assert!(!schedule.is_active_at(at(7, 6)));
+        assert!(schedule.is_active_at(at(6, 6)));
+
+        schedule.start = 9 * 60;
+        schedule.end = 17 * 60;
+        assert!(schedule.is_active_at(at(7, 12)));
+        assert!(!schedule.is_active_at(at(6, 12)));
+
+        schedule.enabled = false;
+        assert!(!schedule.is_active_at(at(7, 12)));
+    }
+}
@@ -1,5 +1,4 @@
 use dioxus::prelude::*;
-use futures::channel::oneshot;
 use futures::StreamExt;
 use kit::{
     elements::{
@@ -12,18 +11,33 @@ use shared::language::get_local_text;
 use warp::logging::tracing::log;
 
 use crate::{
+    components::recovery_phrase::RecoveryPhrase,
     config::Configuration,
-    warp_runner::{MultiPassCmd, WarpCmd},
-    AuthPages, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, Passphrase, WarpCmd, LOGIN_TIMEOUT},
+    AuthPages,
 };
 
 #[inline_props]
 #[allow(non_snake_case)]
-pub fn CreateAccountLayout(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -> Element {
+pub fn CreateAccountLayout(
+    cx: Scope,
+    page: UseState<AuthPages>,
+    pin: UseRef<Passphrase>,
+    // too many wrong PINs were entered on the unlock page, so the old account was deleted
+    account_wiped: UseRef<bool>,
+) -> Element {
     log::trace!("rendering create account layout");
     let username = use_state(cx, String::new);
     //let error = use_state(cx, String::new);
     let button_disabled = use_state(cx, || true);
+    // the PIN typed on the unlock page isn't reused. a new account gets its own
+    let pin_valid = use_state(cx, || false);
+    // None until warp_runner answers
+    let account_exists: &UseState<Option<bool>> = use_state(cx, || None);
+    // creating an account deletes the existing one, so the user has to confirm it
+    let confirm_overwrite = use_state(cx, || false);
+    // shown once after the account is created. the user has to confirm before continuing
+    let recovery_phrase: &UseState<Option<Passphrase>> = use_state(cx, || None);
 
     let username_validation = Validation {
         // The input should have a maximum length of 32
@@ -41,44 +55,115 @@ pub fn CreateAccountLayout(cx: Scope, page: UseState<AuthPages>, pin: UseRef<Str
         special_chars_allowed: None,
     };
 
-    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<(String, String)>| {
-        to_owned![page];
+    use_future(cx, (), |_| {
+        to_owned![account_exists];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
-            while let Some((username, passphrase)) = rx.next().await {
-                //println!("auth got input");
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
+            account_exists.set(Some(warp_runner::account_exists().await));
+        }
+    });
 
-                if let Err(e) = warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::CreateIdentity {
-                    username,
-                    passphrase,
-                    rsp: tx,
-                })) {
-                    log::error!("failed to send warp command: {}", e);
-                    continue;
-                }
+    // Set up validation options for the input field
+    let pin_validation = Validation {
+        // The input should have a maximum length of 32
+        max_length: Some(32),
+        // The input should have a minimum length of 4
+        min_length: Some(4),
+        // The input should only contain alphanumeric characters
+        alpha_numeric_only: false,
+        // The input should not contain any whitespace
+        no_whitespace: true,
+        // The input component validation is shared - if you need to allow just colons in, set this to true
+        ignore_colons: false,
+        // The input should allow any special characters
+        // if you need special chars, just pass a vec! with each char necessary, mainly if alpha_numeric_only is true
+        special_chars_allowed: None,
+    };
 
-                let res = rx.await.expect("failed to get response from warp_runner");
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<(String, Passphrase)>| {
+        to_owned![recovery_phrase];
+        async move {
+            while let Some((username, passphrase)) = rx.next().await {
+                //println!("auth got input");
+                let res = warp_runner::run_cmd_with_timeout(
+                    |rsp| {
+                        WarpCmd::MultiPass(MultiPassCmd::CreateIdentity {
+                            username,
+                            passphrase,
+                            rsp,
+                        })
+                    },
+                    LOGIN_TIMEOUT,
+                )
+                .await;
 
                 //println!("got response from warp");
                 match res {
-                    Ok(_) => {
+                    Ok(phrase) => recovery_phrase.set(Some(phrase)),
+                    // todo: notify user
+                    Err(e) => log::error!("create identity failed: {}", e),
+                }
+            }
+        }
+    });
+
+    if let Some(phrase) = recovery_phrase.get() {
+        return cx.render(rsx!(
+            div {
+                id: "unlock-layout",
+                aria_label: "recovery-phrase-layout",
+                p {
+                    class: "info",
+                    get_local_text("recovery.save-phrase")
+                    br {},
+                    span {
+                        class: "warning",
+                        get_local_text("recovery.save-phrase-warning")
+                    }
+                },
+                RecoveryPhrase {
+                    phrase: phrase.clone(),
+                },
+                Button {
+                    text: get_local_text("recovery.saved-phrase"),
+                    aria_label: "saved-phrase-button".into(),
+                    appearance: kit::elements::Appearance::Primary,
+                    icon: Icon::Check,
+                    onpress: move |_| {
                         if Configuration::load_or_default().audiovideo.interface_sounds {
                             crate::utils::sounds::Play(crate::utils::sounds::Sounds::On);
                         }
                         page.set(AuthPages::Success);
                     }
-                    // todo: notify user
-                    Err(e) => log::error!("create identity failed: {}", e),
                 }
             }
+        ));
+    }
+
+    let can_create = !*button_disabled.get() && *pin_valid.get();
+    let create = move |confirmed: bool| {
+        if !can_create {
+            return;
         }
-    });
+        // unknown counts as existing
+        if account_exists.get().unwrap_or(true) && !confirmed {
+            confirm_overwrite.set(true);
+            return;
+        }
+        confirm_overwrite.set(false);
+        ch.send((username.get().to_string(), pin.read().clone()));
+    };
 
     cx.render(rsx!(
         div {
             id: "unlock-layout",
             aria_label: "unlock-layout",
+            account_wiped.read().then(|| rsx!(
+                p {
+                    class: "warning",
+                    aria_label: "account-wiped",
+                    get_local_text("auth.account-wiped")
+                }
+            )),
             Input {
                 id: "username-input".to_owned(),
                 focus: true,
@@ -99,23 +184,78 @@ pub fn CreateAccountLayout(cx: Scope, page: UseState<AuthPages>, pin: UseRef<Str
                     }
                     username.set(val);
                 }
-                onreturn: move |_| {
-                    if !*button_disabled.get() {
-                        ch.send((username.get().to_string(), pin.read().to_string()));
+                onreturn: move |_| create(false),
+            },
+            Input {
+                id: "create-pin-input".to_owned(),
+                is_password: true,
+                icon: Icon::Key,
+                aria_label: "create-pin-input".into(),
+                disabled: false,
+                placeholder: get_local_text("unlock.enter-pin"),
+                options: Options {
+                    with_validation: Some(pin_validation),
+                    with_clear_btn: true,
+                    ..Default::default()
+                }
+                onchange: move |(val, is_valid): (String, bool)| {
+                    *pin.write_silent() = Passphrase::new(val);
+                    if *pin_valid.get() != is_valid {
+                        pin_valid.set(is_valid);
                     }
                 }
+                onreturn: move |_| create(false),
             },
+            confirm_overwrite.get().then(|| rsx!(
+                div {
+                    class: "overwrite-warning",
+                    aria_label: "overwrite-warning",
+                    p {
+                        class: "warning",
+                        get_local_text("auth.overwrite-warning")
+                    },
+                    Button {
+                        text: get_local_text("auth.overwrite-confirm"),
+                        aria_label: "overwrite-confirm-button".into(),
+                        appearance: kit::elements::Appearance::Danger,
+                        icon: Icon::Trash,
+                        onpress: move |_| create(true),
+                    },
+                    Button {
+                        text: get_local_text("auth.overwrite-cancel"),
+                        aria_label: "overwrite-cancel-button".into(),
+                        appearance: kit::elements::Appearance::Secondary,
+                        icon: Icon::XMark,
+                        onpress: move |_| confirm_overwrite.set(false),
+                    }
+                }
+            )),
+            (!*confirm_overwrite.get()).then(|| rsx!(
+                Button {
+                    text:  get_local_text("unlock.create-account"),
+                    aria_label: "create-account-button".into(),
+                    appearance: kit::elements::Appearance::Primary,
+                    icon: Icon::Check,
+                    disabled: !can_create,
+                    onpress: move |_| create(false),
+                }
+            )),
             Button {
-                text:  get_local_text("unlock.create-account"),
-                aria_label: "create-account-button".into(),
-                appearance: kit::elements::Appearance::Primary,
-                icon: Icon::Check,
-                disabled: *button_disabled.get(),
-                onpress: move |_| {
-                    ch.send((username.get().to_string(), pin.read().to_string()));
+                text: get_local_text("recovery.restore-account"),
+                aria_label: "restore-account-button".into(),
+                appearance: kit::elements::Appearance::Secondary,
+                icon: Icon::ArrowPath,
+                onpress: move |_| page.set(AuthPages::RestoreAccount),
+            },
+            account_exists.get().unwrap_or(false).then(|| rsx!(
+                Button {
+                    text: get_local_text("recovery.back"),
+                    aria_label: "back-button".into(),
+                    appearance: kit::elements::Appearance::Secondary,
+                    icon: Icon::ChevronLeft,
+                    onpress: move |_| page.set(AuthPages::Unlock),
                 }
-
-            }
+            ))
         }
     ))
 }
@@ -1,6 +1,7 @@
 pub mod chat;
 pub mod create_account;
 pub mod friends;
+pub mod restore_account;
 pub mod settings;
 pub mod storage;
 pub mod unlock;
@@ -0,0 +1,219 @@
+use dioxus::prelude::*;
+use futures::StreamExt;
+use kit::{
+    elements::{
+        button::Button,
+        input::{Input, Options, Validation},
+    },
+    icons::Icon,
+};
+use shared::language::get_local_text;
+use warp::logging::tracing::log;
+
+use crate::{
+    config::Configuration,
+    warp_runner::{self, MultiPassCmd, Passphrase, WarpCmd, LOGIN_TIMEOUT},
+    AuthPages,
+};
+
+// standard phrases are 12 words
+const PHRASE_WORDS: usize = 12;
+
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn RestoreAccountLayout(
+    cx: Scope,
+    page: UseState<AuthPages>,
+    pin: UseRef<Passphrase>,
+) -> Element {
+    log::trace!("rendering restore account layout");
+    let phrase = use_state(cx, || Passphrase::new(String::new()));
+    let restore_failed = use_state(cx, || false);
+    let in_progress = use_state(cx, || false);
+    // the restored account gets its own PIN, like a new one
+    let pin_valid = use_state(cx, || false);
+    // None until warp_runner answers
+    let account_exists: &UseState<Option<bool>> = use_state(cx, || None);
+    // restoring deletes the existing account, so the user has to confirm it
+    let confirm_overwrite = use_state(cx, || false);
+
+    use_future(cx, (), |_| {
+        to_owned![account_exists];
+        async move {
+            account_exists.set(Some(warp_runner::account_exists().await));
+        }
+    });
+
+    // Set up validation options for the input field
+    let pin_validation = Validation {
+        // The input should have a maximum length of 32
+        max_length: Some(32),
+        // The input should have a minimum length of 4
+        min_length: Some(4),
+        // The input should only contain alphanumeric characters
+        alpha_numeric_only: false,
+        // The input should not contain any whitespace
+        no_whitespace: true,
+        // The input component validation is shared - if you need to allow just colons in, set this to true
+        ignore_colons: false,
+        // The input should allow any special characters
+        // if you need special chars, just pass a vec! with each char necessary, mainly if alpha_numeric_only is true
+        special_chars_allowed: None,
+    };
+
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<(Passphrase, Passphrase)>| {
+        to_owned![page, restore_failed, in_progress];
+        async move {
+            while let Some((phrase, passphrase)) = rx.next().await {
+                in_progress.set(true);
+                let res = warp_runner::run_cmd_with_timeout(
+                    |rsp| {
+                        WarpCmd::MultiPass(MultiPassCmd::RestoreIdentity {
+                            passphrase,
+                            phrase,
+                            rsp,
+                        })
+                    },
+                    LOGIN_TIMEOUT,
+                )
+                .await;
+                in_progress.set(false);
+
+                match res {
+                    Ok(_) => {
+                        if Configuration::load_or_default().audiovideo.interface_sounds {
+                            crate::utils::sounds::Play(crate::utils::sounds::Sounds::On);
+                        }
+                        page.set(AuthPages::Success);
+                    }
+                    Err(e) => {
+                        log::error!("restore account failed: {}", e);
+                        restore_failed.set(true);
+                    }
+                }
+            }
+        }
+    });
+
+    let button_disabled = *in_progress.get()
+        || !*pin_valid.get()
+        || phrase.split_whitespace().count() != PHRASE_WORDS;
+    let restore = move |confirmed: bool| {
+        if button_disabled {
+            return;
+        }
+        // unknown counts as existing
+        if account_exists.get().unwrap_or(true) && !confirmed {
+            confirm_overwrite.set(true);
+            return;
+        }
+        confirm_overwrite.set(false);
+        // normalize the spacing so a phrase pasted with line breaks still works
+        let normalized = Passphrase::new(phrase.split_whitespace().collect::<Vec<_>>().join(" "));
+        ch.send((normalized, pin.read().clone()));
+    };
+
+    cx.render(rsx!(
+        div {
+            id: "unlock-layout",
+            aria_label: "restore-account-layout",
+            p {
+                class: "info",
+                get_local_text("recovery.restore-description")
+                br {},
+                span {
+                    class: "warning",
+                    get_local_text("recovery.restore-warning")
+                }
+            },
+            Input {
+                id: "recovery-phrase-input".to_owned(),
+                focus: true,
+                is_password: false,
+                icon: Icon::DocumentText,
+                aria_label: "recovery-phrase-input".into(),
+                disabled: *in_progress.get(),
+                placeholder: get_local_text("recovery.enter-phrase"),
+                options: Options {
+                    with_clear_btn: true,
+                    ..Default::default()
+                }
+                onchange: move |(val, _): (String, bool)| {
+                    restore_failed.set(false);
+                    phrase.set(Passphrase::new(val));
+                }
+                onreturn: move |_| restore(false),
+            },
+            Input {
+                id: "restore-pin-input".to_owned(),
+                is_password: true,
+                icon: Icon::Key,
+                aria_label: "restore-pin-input".into(),
+                disabled: *in_progress.get(),
+                placeholder: get_local_text("unlock.enter-pin"),
+                options: Options {
+                    with_validation: Some(pin_validation),
+                    with_clear_btn: true,
+                    ..Default::default()
+                }
+                onchange: move |(val, is_valid): (String, bool)| {
+                    *pin.write_silent() = Passphrase::new(val);
+                    if *pin_valid.get() != is_valid {
+                        pin_valid.set(is_valid);
+                    }
+                }
+                onreturn: move |_| restore(false),
+            },
+            restore_failed.get().then(|| rsx!(
+                p {
+                    class: "error",
+                    aria_label: "restore-failed",
+                    get_local_text("recovery.restore-failed")
+                }
+            )),
+            confirm_overwrite.get().then(|| rsx!(
+                div {
+                    class: "overwrite-warning",
+                    aria_label: "overwrite-warning",
+                    p {
+                        class: "warning",
+                        get_local_text("recovery.overwrite-warning")
+                    },
+                    Button {
+                        text: get_local_text("recovery.overwrite-confirm"),
+                        aria_label: "overwrite-confirm-button".into(),
+                        appearance: kit::elements::Appearance::Danger,
+                        icon: Icon::Trash,
+                        disabled: button_disabled,
+                        onpress: move |_| restore(true),
+                    },
+                    Button {
+                        text: get_local_text("auth.overwrite-cancel"),
+                        aria_label: "overwrite-cancel-button".into(),
+                        appearance: kit::elements::Appearance::Secondary,
+                        icon: Icon::XMark,
+                        onpress: move |_| confirm_overwrite.set(false),
+                    }
+                }
+            )),
+            (!*confirm_overwrite.get()).then(|| rsx!(
+                Button {
+                    text: get_local_text("recovery.restore-account"),
+                    aria_label: "restore-account-button".into(),
+                    appearance: kit::elements::Appearance::Primary,
+                    icon: Icon::ArrowPath,
+                    disabled: button_disabled,
+                    onpress: move |_| restore(false),
+                }
+            )),
+            Button {
+                text: get_local_text("recovery.back"),
+                aria_label: "back-button".into(),
+                appearance: kit::elements::Appearance::Secondary,
+                icon: Icon::ChevronLeft,
+                disabled: *in_progress.get(),
+                onpress: move |_| page.set(AuthPages::Unlock),
+            }
+        }
+    ))
+}
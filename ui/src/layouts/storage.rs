@@ -2,7 +2,7 @@ use std::{path::PathBuf, time::Duration};
 
 use dioxus::prelude::*;
 use dioxus_router::*;
-use futures::{channel::oneshot, StreamExt};
+use futures::StreamExt;
 use kit::{
     components::nav::Nav,
     elements::{
@@ -26,8 +26,8 @@ use warp::{
 use crate::{
     components::chat::{sidebar::Sidebar as ChatSidebar, RouteInfo},
     state::{storage::Storage, Action, State},
-    warp_runner::{ConstellationCmd, WarpCmd},
-    STATIC_ARGS, WARP_CMD_CH,
+    warp_runner::{self, ConstellationCmd, WarpCmd},
+    STATIC_ARGS,
 };
 
 pub const ROOT_DIR_NAME: &str = "root";
@@ -72,24 +72,18 @@ pub fn FilesLayout(cx: Scope<Props>) -> Element {
     let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<ChanCmd>| {
         to_owned![storage_state];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(cmd) = rx.next().await {
                 match cmd {
                     ChanCmd::CreateNewDirectory(directory_name) => {
-                        let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
                         let directory_name2 = directory_name.clone();
 
-                        if let Err(e) = warp_cmd_tx.send(WarpCmd::Constellation(
-                            ConstellationCmd::CreateNewDirectory {
+                        let rsp = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::Constellation(ConstellationCmd::CreateNewDirectory {
                                 directory_name,
-                                rsp: tx,
-                            },
-                        )) {
-                            log::error!("failed to add new directory {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
+                                rsp,
+                            })
+                        })
+                        .await;
 
                         match rsp {
                             Ok(_) => {
@@ -102,41 +96,32 @@ pub fn FilesLayout(cx: Scope<Props>) -> Element {
                         }
                     }
                     ChanCmd::GetItemsFromCurrentDirectory => {
-                        let (tx, rx) = oneshot::channel::<Result<Storage, warp::error::Error>>();
-
-                        if let Err(e) = warp_cmd_tx.send(WarpCmd::Constellation(
-                            ConstellationCmd::GetItemsFromCurrentDirectory { rsp: tx },
-                        )) {
-                            log::error!("failed to get items from current directory {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
+                        let rsp = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::Constellation(ConstellationCmd::GetItemsFromCurrentDirectory {
+                                rsp,
+                            })
+                        })
+                        .await;
                         match rsp {
                             Ok(storage) => {
                                 storage_state.set(Some(storage));
                             }
                             Err(e) => {
-                                log::error!("failed to add new directory: {}", e);
+                                log::error!("failed to get items from current directory: {}", e);
                                 continue;
                             }
                         }
                     }
                     ChanCmd::OpenDirectory(directory_name) => {
-                        let (tx, rx) = oneshot::channel::<Result<Storage, warp::error::Error>>();
                         let directory_name2 = directory_name.clone();
 
-                        if let Err(e) = warp_cmd_tx.send(WarpCmd::Constellation(
-                            ConstellationCmd::OpenDirectory {
+                        let rsp = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::Constellation(ConstellationCmd::OpenDirectory {
                                 directory_name,
-                                rsp: tx,
-                            },
-                        )) {
-                            log::error!("failed to open {directory_name2} directory {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
+                                rsp,
+                            })
+                        })
+                        .await;
                         match rsp {
                             Ok(storage) => {
                                 storage_state.set(Some(storage));
@@ -149,17 +134,15 @@ pub fn FilesLayout(cx: Scope<Props>) -> Element {
                         }
                     }
                     ChanCmd::BackToPreviousDirectory(directory) => {
-                        let (tx, rx) = oneshot::channel::<Result<Storage, warp::error::Error>>();
                         let directory_name = directory.name();
 
-                        if let Err(e) = warp_cmd_tx.send(WarpCmd::Constellation(
-                            ConstellationCmd::BackToPreviousDirectory { directory, rsp: tx },
-                        )) {
-                            log::error!("failed to open directory {}: {}", directory_name, e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
+                        let rsp = warp_runner::run_cmd(|rsp| {
+                            WarpCmd::Constellation(ConstellationCmd::BackToPreviousDirectory {
+                                directory,
+                                rsp,
+                            })
+                        })
+                        .await;
                         match rsp {
                             Ok(storage) => {
                                 storage_state.set(Some(storage));
@@ -172,19 +155,17 @@ pub fn FilesLayout(cx: Scope<Props>) -> Element {
                         }
                     }
                     ChanCmd::UploadFiles(files_path) => {
-                        let (tx, rx) = oneshot::channel::<Result<Storage, warp::error::Error>>();
-
-                        if let Err(e) = warp_cmd_tx.send(WarpCmd::Constellation(
-                            ConstellationCmd::UploadFiles {
-                                files_path,
-                                rsp: tx,
+                        // uploads can take much longer than other commands
+                        let rsp = warp_runner::run_cmd_with_timeout(
+                            |rsp| {
+                                WarpCmd::Constellation(ConstellationCmd::UploadFiles {
+                                    files_path,
+                                    rsp,
+                                })
                             },
-                        )) {
-                            log::error!("failed to upload files {}", e);
-                            continue;
-                        }
-
-                        let rsp = rx.await.expect("command canceled");
+                            warp_runner::UPLOAD_TIMEOUT,
+                        )
+                        .await;
                         match rsp {
                             Ok(storage) => {
                                 storage_state.set(Some(storage));
@@ -1,5 +1,4 @@
 use dioxus::prelude::*;
-use futures::channel::oneshot;
 use futures::StreamExt;
 use kit::{
     elements::{
@@ -8,78 +7,120 @@ use kit::{
     },
     icons::Icon,
 };
-use shared::language::get_local_text;
+use shared::language::{get_local_text, get_local_text_with_args, FluentValue};
+use tokio::time::{sleep, Duration};
 use warp::logging::tracing::log;
 
 use crate::{
     config::Configuration,
-    warp_runner::{MultiPassCmd, WarpCmd},
-    AuthPages, WARP_CMD_CH,
+    warp_runner::{self, MultiPassCmd, Passphrase, UnlockError, WarpCmd, LOGIN_TIMEOUT},
+    AuthPages,
 };
 
-// todo: go to the auth page if no account has been created
+// rounded up, so the countdown doesn't show 0 while the user still has to wait
+fn wait_secs(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
 #[inline_props]
 #[allow(non_snake_case)]
-pub fn UnlockLayout(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -> Element {
+pub fn UnlockLayout(
+    cx: Scope,
+    page: UseState<AuthPages>,
+    pin: UseRef<Passphrase>,
+    // Uplink was locked after logging in. creating or restoring an account isn't possible until it restarts
+    locked: bool,
+    // set when too many wrong PINs deleted the account, so the create account page can say why
+    account_wiped: UseRef<bool>,
+) -> Element {
     log::trace!("rendering unlock layout");
+    let locked = *locked;
     let password_failed: &UseRef<Option<bool>> = use_ref(cx, || None);
     let button_disabled = use_state(cx, || true);
     let can_create_new_account = use_state(cx, || false);
+    // seconds until another PIN can be tried, after too many wrong ones
+    let retry_in = use_state(cx, || 0);
 
-    // this will be needed later
-    /*let account_exists = use_future(cx, (), |_| async move {
-        let warp_cmd_tx = WARP_CMD_CH.tx.clone();
-        let (tx, rx) = oneshot::channel::<bool>();
-        if let Err(e) =
-            warp_cmd_tx.send(WarpCmd::Tesseract(TesseractCmd::AccountExists { rsp: tx }))
-        {
-            log::error!("failed to send warp command: {}", e);
-            // returning true will prevent the account from being created
-            return true;
+    // the wait is saved by warp_runner, so it carries over from the last time Uplink ran
+    use_future(cx, (), |_| {
+        to_owned![retry_in];
+        async move {
+            if let Some(wait) = warp_runner::unlock_retry_in().await {
+                retry_in.set(wait_secs(wait));
+            }
+            loop {
+                sleep(Duration::from_secs(1)).await;
+                if *retry_in.current() > 0 {
+                    retry_in.modify(|secs| secs - 1);
+                }
+            }
         }
+    });
 
-        let exists = rx.await.unwrap_or(false);
-        log::debug!("account_exists: {}", exists);
-        exists
-    });*/
+    // the first launch goes straight to creating an account
+    use_future(cx, (), |_| {
+        to_owned![page];
+        async move {
+            if !locked && !warp_runner::account_exists().await {
+                page.set(AuthPages::CreateAccount);
+            }
+        }
+    });
 
-    let ch = use_coroutine(cx, |mut rx| {
-        to_owned![password_failed, page, can_create_new_account];
+    let ch = use_coroutine(cx, |mut rx: UnboundedReceiver<Passphrase>| {
+        to_owned![
+            password_failed,
+            page,
+            can_create_new_account,
+            retry_in,
+            account_wiped
+        ];
         async move {
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             while let Some(password) = rx.next().await {
-                let (tx, rx) = oneshot::channel::<Result<(), warp::error::Error>>();
-
-                if let Err(e) = warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::TryLogIn {
-                    passphrase: password,
-                    rsp: tx,
-                })) {
-                    log::error!("failed to send warp command: {}", e);
-                    continue;
-                }
-
-                let res = rx.await.expect("failed to get response from warp_runner");
+                let res = warp_runner::send_cmd_with_timeout(
+                    |rsp| {
+                        WarpCmd::MultiPass(MultiPassCmd::TryLogIn {
+                            passphrase: password,
+                            rsp,
+                        })
+                    },
+                    LOGIN_TIMEOUT,
+                )
+                .await;
 
                 match res {
-                    Ok(_) => {
+                    Ok(Ok(_)) => {
                         if Configuration::load_or_default().audiovideo.interface_sounds {
                             crate::utils::sounds::Play(crate::utils::sounds::Sounds::On);
                         }
                         page.set(AuthPages::Success)
                     }
-                    Err(err) => {
-                        can_create_new_account.set(true);
-                        match err {
-                            warp::error::Error::DecryptionError => {
-                                // wrong password
-                                password_failed.set(Some(true));
-                                log::warn!("decryption error");
-                            }
-                            _ => {
-                                // unexpected
-                                log::error!("LogIn failed: {}", err);
-                            }
+                    Ok(Err(UnlockError::AccountWiped)) => {
+                        log::warn!("too many wrong PINs. the account was deleted");
+                        *account_wiped.write_silent() = true;
+                        page.set(AuthPages::CreateAccount);
+                    }
+                    Ok(Err(UnlockError::RetryIn(wait))) => {
+                        can_create_new_account.set(!locked);
+                        retry_in.set(wait_secs(wait));
+                    }
+                    Ok(Err(UnlockError::WrongPin)) => {
+                        can_create_new_account.set(!locked);
+                        // the wrong PIN may have started a wait
+                        if let Some(wait) = warp_runner::unlock_retry_in().await {
+                            retry_in.set(wait_secs(wait));
                         }
+                        password_failed.set(Some(true));
+                        log::warn!("decryption error");
+                    }
+                    Ok(Err(err)) => {
+                        can_create_new_account.set(!locked);
+                        // unexpected
+                        log::error!("LogIn failed: {}", err);
+                    }
+                    Err(err) => {
+                        can_create_new_account.set(!locked);
+                        log::error!("LogIn failed: {}", err);
                     }
                 }
             }
@@ -103,11 +144,19 @@ pub fn UnlockLayout(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -
         special_chars_allowed: None,
     };
 
-    // todo: use password_failed to display an error message
+    let can_try = !*button_disabled.get() && *retry_in.get() == 0;
+
     cx.render(rsx!(
         div {
             id: "unlock-layout",
             aria_label: "unlock-layout",
+            locked.then(|| rsx!(
+                p {
+                    class: "info",
+                    aria_label: "unlock-locked",
+                    get_local_text("unlock.locked")
+                }
+            )),
             p {
                 class: "info",
                 aria_label: "unlock-warning-paragraph",
@@ -133,31 +182,64 @@ pub fn UnlockLayout(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -
                     ..Default::default()
                 }
                 onchange: move |(val, is_valid): (String, bool)| {
-                    *pin.write_silent() = val.clone();
+                    *pin.write_silent() = Passphrase::new(val);
+                    password_failed.set(None);
                     let should_disable = !is_valid;
                     if *button_disabled.get() != should_disable {
                         button_disabled.set(should_disable);
                     }
-                    if !should_disable {
-                        ch.send(val)
-                    }
                 }
+                // every attempt counts towards the backoff, so only try when the user submits
                 onreturn: move |_| {
-                    if !*button_disabled.get() {
-                        page.set(AuthPages::CreateAccount);
+                    if can_try {
+                        ch.send(pin.read().clone());
                     }
                 }
             },
+            (*retry_in.get() > 0).then(|| rsx!(
+                p {
+                    class: "error",
+                    aria_label: "unlock-retry-in",
+                    get_local_text_with_args("unlock.retry-in", vec![("seconds", FluentValue::from(*retry_in.get()))])
+                }
+            )),
+            (*retry_in.get() == 0 && *password_failed.read() == Some(true)).then(|| rsx!(
+                p {
+                    class: "error",
+                    aria_label: "unlock-failed",
+                    get_local_text("unlock.wrong-pin")
+                }
+            )),
+            Button {
+                text: get_local_text("unlock"),
+                aria_label: "unlock-button".into(),
+                appearance: kit::elements::Appearance::Primary,
+                icon: Icon::LockOpen,
+                disabled: !can_try,
+                onpress: move |_| {
+                    ch.send(pin.read().clone());
+                }
+            },
             can_create_new_account.get().then(|| rsx!(
                 Button {
                     text: get_local_text("unlock.create-account"),
                     aria_label: "create-account-button".into(),
-                    appearance: kit::elements::Appearance::Primary,
+                    appearance: kit::elements::Appearance::Secondary,
                     icon: Icon::Check,
                     disabled: *button_disabled.get(),
                     onpress: move |_| {
                         page.set(AuthPages::CreateAccount);
                     }
+                },
+                Button {
+                    text: get_local_text("recovery.restore-account"),
+                    aria_label: "restore-account-button".into(),
+                    appearance: kit::elements::Appearance::Secondary,
+                    icon: Icon::ArrowPath,
+                    disabled: *button_disabled.get(),
+                    onpress: move |_| {
+                        page.set(AuthPages::RestoreAccount);
+                    }
                 }
             ))
         }
@@ -8,7 +8,6 @@ use dioxus_desktop::tao::menu::AboutMetadata;
 use dioxus_desktop::Config;
 use dioxus_desktop::{tao, use_window};
 use fs_extra::dir::*;
-use futures::channel::oneshot;
 use kit::elements::button::Button;
 use kit::elements::Appearance;
 use kit::icons::IconElement;
@@ -17,17 +16,16 @@ use once_cell::sync::Lazy;
 use overlay::{make_config, OverlayDom};
 use shared::language::{change_language, get_local_text};
 use state::State;
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
-use uuid::Uuid;
 
 use std::sync::Arc;
-use tao::menu::{MenuBar as Menu, MenuItem};
+use tao::menu::{MenuBar as Menu, MenuId, MenuItem, MenuItemAttributes};
 use tao::window::WindowBuilder;
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 use warp::logging::tracing::log::{self, LevelFilter};
+use warp::multipass::identity::IdentityStatus;
 
 use dioxus_desktop::use_wry_event_handler;
 use dioxus_desktop::wry::application::event::Event as WryEvent;
@@ -36,14 +34,15 @@ use crate::components::debug_logger::DebugLogger;
 use crate::components::toast::Toast;
 use crate::layouts::create_account::CreateAccountLayout;
 use crate::layouts::friends::FriendsLayout;
+use crate::layouts::restore_account::RestoreAccountLayout;
 use crate::layouts::settings::SettingsLayout;
 use crate::layouts::storage::FilesLayout;
 use crate::layouts::unlock::UnlockLayout;
 use crate::state::ui::WindowMeta;
-use crate::state::Action;
-use crate::state::{friends, storage};
+use crate::state::{chats::PendingStatus, Action};
 use crate::warp_runner::{
-    ConstellationCmd, MultiPassCmd, RayGunCmd, WarpCmd, WarpCmdChannels, WarpEventChannels,
+    ConstellationCmd, MultiPassCmd, Passphrase, RayGunCmd, TesseractCmd, WarpCmd, WarpCmdChannels,
+    WarpEventChannels,
 };
 use crate::window_manager::WindowManagerCmdChannels;
 use crate::{components::chat::RouteInfo, layouts::chat::ChatLayout};
@@ -72,6 +71,8 @@ pub struct StaticArgs {
     pub warp_path: PathBuf,
     pub logger_path: PathBuf,
     pub tesseract_path: PathBuf,
+    // failed unlock attempts. lives next to the keystore so that deleting the account resets it
+    pub login_attempts_path: PathBuf,
     // seconds
     pub typing_indicator_refresh: u64,
     // seconds
@@ -97,6 +98,7 @@ pub static STATIC_ARGS: Lazy<StaticArgs> = Lazy::new(|| {
         typing_indicator_refresh: 5,
         typing_indicator_timeout: 6,
         tesseract_path: warp_path.join("tesseract.json"),
+        login_attempts_path: warp_path.join("login_attempts.json"),
         use_mock: args.with_mock,
     }
 });
@@ -136,6 +138,9 @@ pub struct UplinkRoutes<'a> {
     pub settings: &'a str,
 }
 
+// the "Lock Uplink" item in the app menu
+pub static LOCK_MENU_ID: Lazy<MenuId> = Lazy::new(|| MenuId::new("lock-uplink"));
+
 pub static UPLINK_ROUTES: UplinkRoutes = UplinkRoutes {
     chat: "/",
     friends: "/friends",
@@ -148,6 +153,7 @@ pub static UPLINK_ROUTES: UplinkRoutes = UplinkRoutes {
 pub enum AuthPages {
     Unlock,
     CreateAccount,
+    RestoreAccount,
     Success,
 }
 
@@ -244,6 +250,9 @@ fn main() {
         String::from("Uplink"),
         AboutMetadata::default(),
     ));
+    app_menu
+        .add_item(MenuItemAttributes::new(&get_local_text("uplink.lock")).with_id(*LOCK_MENU_ID));
+    app_menu.add_native_item(MenuItem::Separator);
     app_menu.add_native_item(MenuItem::Quit);
     // add native shortcuts to `edit_menu` menu
     // in macOS native item are required to get keyboard shortcut
@@ -334,20 +343,43 @@ fn bootstrap(cx: Scope) -> Element {
 // don't see a way to replace the router
 // so instead use a Prop to determine which page to render
 // after the user logs in, app_bootstrap loads Uplink as normal.
+// when Uplink is locked, the page goes back to Unlock and app_bootstrap runs again once the user unlocks it.
 fn auth_page_manager(cx: Scope) -> Element {
     let page = use_state(cx, || AuthPages::Unlock);
-    let pin = use_ref(cx, String::new);
+    let pin = use_ref(cx, || Passphrase::new(String::new()));
+    // after the first login, the unlock page only unlocks. warp_runner is already running
+    let logged_in = use_ref(cx, || false);
+    // too many wrong PINs deleted the account. the create account page explains why
+    let account_wiped = use_ref(cx, || false);
+    if *page.current() == AuthPages::Success {
+        *logged_in.write_silent() = true;
+        *account_wiped.write_silent() = false;
+        // dropping the old PIN zeroes it. `clear` would leave its bytes in the buffer
+        *pin.write_silent() = Passphrase::new(String::new());
+    }
+    // warp_runner is back to waiting for an account, as on the first launch
+    if *account_wiped.read() {
+        *logged_in.write_silent() = false;
+    }
     cx.render(rsx!(match *page.current() {
-        AuthPages::Success => rsx!(app_bootstrap {}),
+        AuthPages::Success => rsx!(app_bootstrap { page: page.clone() }),
         _ => rsx!(auth_wrapper {
             page: page.clone(),
-            pin: pin.clone()
+            pin: pin.clone(),
+            locked: *logged_in.read(),
+            account_wiped: account_wiped.clone(),
         }),
     }))
 }
 
 #[inline_props]
-fn auth_wrapper(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -> Element {
+fn auth_wrapper(
+    cx: Scope,
+    page: UseState<AuthPages>,
+    pin: UseRef<Passphrase>,
+    locked: bool,
+    account_wiped: UseRef<bool>,
+) -> Element {
     log::trace!("rendering auth wrapper");
     let desktop = use_window(cx);
     let theme = "";
@@ -368,8 +400,9 @@ fn auth_wrapper(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -> El
                 }
             },
             match *page.current() {
-                AuthPages::Unlock => rsx!(UnlockLayout { page: page.clone(), pin: pin.clone() }),
-                AuthPages::CreateAccount => rsx!(CreateAccountLayout { page: page.clone(), pin: pin.clone() }),
+                AuthPages::Unlock => rsx!(UnlockLayout { page: page.clone(), pin: pin.clone(), locked: *locked, account_wiped: account_wiped.clone() }),
+                AuthPages::CreateAccount => rsx!(CreateAccountLayout { page: page.clone(), pin: pin.clone(), account_wiped: account_wiped.clone() }),
+                AuthPages::RestoreAccount => rsx!(RestoreAccountLayout { page: page.clone(), pin: pin.clone() }),
                 _ => panic!("invalid page")
             }
         }
@@ -378,9 +411,11 @@ fn auth_wrapper(cx: Scope, page: UseState<AuthPages>, pin: UseRef<String>) -> El
 
 // called at the end of the auth flow
 #[inline_props]
-pub fn app_bootstrap(cx: Scope) -> Element {
+pub fn app_bootstrap(cx: Scope, page: UseState<AuthPages>) -> Element {
     log::trace!("rendering app_bootstrap");
     let mut state = State::load();
+    // logging in counts as activity, so auto-lock doesn't fire right away
+    state.ui.record_activity();
 
     if STATIC_ARGS.use_mock {
         assert!(state.friends.initialized);
@@ -413,10 +448,11 @@ pub fn app_bootstrap(cx: Scope) -> Element {
 
     use_shared_state_provider(cx, || state);
 
-    cx.render(rsx!(crate::app {}))
+    cx.render(rsx!(crate::app { page: page.clone() }))
 }
 
-fn app(cx: Scope) -> Element {
+#[inline_props]
+fn app(cx: Scope, page: UseState<AuthPages>) -> Element {
     log::trace!("rendering app");
     let desktop = use_window(cx);
     let state = use_shared_state::<State>(cx)?;
@@ -445,6 +481,9 @@ fn app(cx: Scope) -> Element {
             style { "{UIKIT_STYLES} {APP_STYLE} {theme}" },
             div {
                 id: "app-wrap",
+                // any input counts as activity for auto-away. written silently, it doesn't change what's rendered
+                onmousemove: move |_| state.write_silent().ui.record_activity(),
+                onkeydown: move |_| state.write_silent().ui.record_activity(),
                 get_titlebar(cx),
                 get_toasts(cx),
                 get_call_dialog(cx),
@@ -468,6 +507,8 @@ fn app(cx: Scope) -> Element {
     // UI tasks
     // clear toasts
     // update message timestamps
+    // update own status
+    // lock Uplink
     // control child windows
     // clear typing indicator
     //
@@ -498,6 +539,9 @@ fn app(cx: Scope) -> Element {
                 match inner.try_borrow_mut() {
                     Ok(state) => {
                         state.write().ui.metadata.focused = *focused;
+                        if *focused {
+                            state.write().ui.record_activity();
+                        }
                         //crate::utils::sounds::Play(Sounds::Notification);
                         //needs_update.set(true);
                     }
@@ -536,6 +580,12 @@ fn app(cx: Scope) -> Element {
                     }
                 }
             }
+            WryEvent::MenuEvent { menu_id, .. } if *menu_id == *LOCK_MENU_ID => {
+                match inner.try_borrow_mut() {
+                    Ok(state) => state.write().ui.lock_requested = true,
+                    Err(e) => log::error!("{e}"),
+                }
+            }
             _ => {}
         }
     });
@@ -630,6 +680,94 @@ fn app(cx: Scope) -> Element {
         }
     });
 
+    // keep the status friends see in sync with the picked presence, do not disturb and auto-away
+    let inner = state.inner();
+    use_future(cx, (), |_| {
+        to_owned![needs_update];
+        async move {
+            let mut sent_status: Option<IdentityStatus> = None;
+            loop {
+                let status = match inner.try_borrow_mut() {
+                    Ok(state) => {
+                        if state.write().ui.last_activity.is_none() {
+                            state.write().ui.record_activity();
+                        }
+                        Some(state.write().own_status(Instant::now()))
+                    }
+                    Err(e) => {
+                        log::error!("{e}");
+                        None
+                    }
+                };
+                if let Some(status) = status.filter(|s| Some(*s) != sent_status) {
+                    match inner.try_borrow_mut() {
+                        Ok(state) => {
+                            state.write().set_own_status(status);
+                            needs_update.set(true);
+                        }
+                        Err(e) => {
+                            log::error!("{e}");
+                        }
+                    }
+                    if STATIC_ARGS.use_mock {
+                        sent_status = Some(status);
+                    } else {
+                        // retried on the next tick if warp_runner isn't ready yet
+                        match warp_runner::run_cmd(|rsp| {
+                            WarpCmd::MultiPass(MultiPassCmd::SetStatus { status, rsp })
+                        })
+                        .await
+                        {
+                            Ok(_) => sent_status = Some(status),
+                            Err(e) => log::error!("failed to set status: {e}"),
+                        }
+                    }
+                }
+                sleep(Duration::from_secs(1)).await;
+            }
+        }
+    });
+
+    // lock Uplink when asked to, or after the auto-lock period. dropping app clears State from memory.
+    // warp_runner keeps running, so unlocking picks up where it left off
+    let inner = state.inner();
+    use_future(cx, (), |_| {
+        to_owned![page, desktop];
+        async move {
+            loop {
+                sleep(Duration::from_millis(250)).await;
+                let should_lock = match inner.try_borrow_mut() {
+                    Ok(state) => {
+                        let state = state.read();
+                        state.ui.lock_requested || state.should_auto_lock(Instant::now())
+                    }
+                    Err(e) => {
+                        log::error!("{e}");
+                        false
+                    }
+                };
+                if !should_lock {
+                    continue;
+                }
+
+                log::info!("locking Uplink");
+                if let Err(e) =
+                    warp_runner::send_cmd(|rsp| WarpCmd::Tesseract(TesseractCmd::Lock { rsp }))
+                        .await
+                {
+                    log::error!("failed to lock tesseract: {e}");
+                }
+                match inner.try_borrow_mut() {
+                    Ok(state) => state.write().clear_sensitive(),
+                    Err(e) => log::error!("{e}"),
+                }
+                desktop.set_inner_size(LogicalSize::new(500.0, 300.0));
+                page.set(AuthPages::Unlock);
+                break;
+            }
+        }
+    });
+
     // control child windows
     let inner = state.inner();
     use_future(cx, (), |_| {
@@ -645,6 +783,52 @@ fn app(cx: Scope) -> Element {
         }
     });
 
+    // send the messages in the outbox, oldest first
+    let inner = state.inner();
+    use_future(cx, (), |_| {
+        to_owned![needs_update, chats_init];
+        async move {
+            loop {
+                let next = if *chats_init.read() {
+                    match inner.try_borrow_mut() {
+                        Ok(state) => state.write().chats.next_queued(),
+                        Err(e) => {
+                            log::error!("{e}");
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                let pending = match next {
+                    Some(p) => p,
+                    None => {
+                        sleep(Duration::from_millis(250)).await;
+                        continue;
+                    }
+                };
+                needs_update.set(true);
+
+                let status = warp_runner::send_pending_message(&pending).await;
+                match inner.try_borrow_mut() {
+                    Ok(state) => {
+                        state
+                            .write()
+                            .mutate(Action::SetPendingStatus(pending.id(), status));
+                        needs_update.set(true);
+                    }
+                    Err(e) => {
+                        log::error!("{e}");
+                    }
+                }
+                // wait for warp_runner to recover before trying again
+                if status == PendingStatus::Queued {
+                    sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+
     // initialize friends
     let inner = state.inner();
     use_future(cx, (), |_| {
@@ -653,17 +837,10 @@ fn app(cx: Scope) -> Element {
             if *friends_init.read() {
                 return;
             }
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
-            let (tx, rx) = oneshot::channel::<Result<friends::Friends, warp::error::Error>>();
-            if let Err(e) = warp_cmd_tx.send(WarpCmd::MultiPass(MultiPassCmd::InitializeFriends {
-                rsp: tx,
-            })) {
-                log::error!("failed to initialize Friends {}", e);
-                tokio::time::sleep(Duration::from_secs(1)).await;
-                return;
-            }
-
-            let res = rx.await.expect("failed to get response from warp_runner");
+            let res = warp_runner::run_cmd(|rsp| {
+                WarpCmd::MultiPass(MultiPassCmd::InitializeFriends { rsp })
+            })
+            .await;
 
             log::trace!("init friends");
             let friends = match res {
@@ -697,17 +874,10 @@ fn app(cx: Scope) -> Element {
             if *items_init.read() {
                 return;
             }
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
-            let (tx, rx) = oneshot::channel::<Result<storage::Storage, warp::error::Error>>();
-
-            if let Err(e) = warp_cmd_tx.send(WarpCmd::Constellation(
-                ConstellationCmd::GetItemsFromCurrentDirectory { rsp: tx },
-            )) {
-                log::error!("failed to initialize Files {}", e);
-                return;
-            }
-
-            let res = rx.await.expect("failed to get response from warp_runner");
+            let res = warp_runner::run_cmd(|rsp| {
+                WarpCmd::Constellation(ConstellationCmd::GetItemsFromCurrentDirectory { rsp })
+            })
+            .await;
 
             log::trace!("init items");
             match res {
@@ -739,26 +909,16 @@ fn app(cx: Scope) -> Element {
             if *chats_init.read() {
                 return;
             }
-            let warp_cmd_tx = WARP_CMD_CH.tx.clone();
             let res = loop {
-                let (tx, rx) = oneshot::channel::<
-                    Result<(state::Identity, HashMap<Uuid, state::Chat>), warp::error::Error>,
-                >();
-                if let Err(e) =
-                    warp_cmd_tx.send(WarpCmd::RayGun(RayGunCmd::InitializeConversations {
-                        rsp: tx,
-                    }))
+                match warp_runner::send_cmd(|rsp| {
+                    WarpCmd::RayGun(RayGunCmd::InitializeConversations { rsp })
+                })
+                .await
                 {
-                    log::error!("failed to init RayGun: {}", e);
-                    tokio::time::sleep(Duration::from_secs(1)).await;
-                    continue;
-                }
-
-                match rx.await {
                     Ok(r) => break r,
                     Err(e) => {
-                        log::error!("comamnd canceled: {}", e);
-                        tokio::time::sleep(std::time::Duration::from_secs(1)).await
+                        log::error!("failed to init RayGun: {}", e);
+                        tokio::time::sleep(Duration::from_secs(1)).await
                     }
                 }
             };
@@ -772,18 +932,80 @@ fn app(cx: Scope) -> Element {
                 }
             };
 
-            match inner.try_borrow_mut() {
-                Ok(state) => {
-                    // for all_chats, fill in participants and messages.
-                    for (k, v) in &state.read().chats.all {
-                        // the # of unread chats defaults to the length of the conversation. but this number
-                        // is stored in state
-                        if let Some(chat) = all_chats.get_mut(k) {
-                            chat.unreads = v.unreads;
+            let outbox_key = match warp_runner::run_cmd(|rsp| {
+                WarpCmd::Tesseract(TesseractCmd::OutboxKey { rsp })
+            })
+            .await
+            {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    log::error!(
+                        "failed to get the outbox key. unsent messages won't be saved: {}",
+                        e
+                    );
+                    None
+                }
+            };
+
+            let saved = match inner.try_borrow_mut() {
+                Ok(state) => state.read().chats.all.clone(),
+                Err(e) => {
+                    log::error!("{e}");
+                    return;
+                }
+            };
+            // the last-read markers and unread counts are stored in state. messages which arrived while Uplink was closed are counted too
+            let own_did = own_id.did_key();
+            let first_run = saved.is_empty();
+            for chat in all_chats.values_mut() {
+                match saved.get(&chat.id) {
+                    Some(v) => {
+                        chat.notifications = v.notifications;
+                        chat.unreads = v.unreads;
+                        // saved before last-read markers existed. keep its count and start tracking from the newest message
+                        if v.last_read.is_none() {
+                            chat.last_read = chat.newest_marker();
+                            continue;
                         }
+                        chat.last_read = v.last_read.clone();
+                    }
+                    // restoring an account shouldn't make its whole history unread
+                    None if first_run => {
+                        chat.mark_read();
+                        continue;
+                    }
+                    None => {}
+                }
+                if chat.holds_unreads() {
+                    chat.unreads = chat.count_unreads(&own_did);
+                    continue;
+                }
+                let (conv_id, after) = (chat.id, chat.last_read.as_ref().map(|r| r.date));
+                match warp_runner::run_cmd(|rsp| {
+                    WarpCmd::RayGun(RayGunCmd::CountUnreads {
+                        conv_id,
+                        after,
+                        own_did: own_did.clone(),
+                        rsp,
+                    })
+                })
+                .await
+                {
+                    Ok(count) => chat.unreads = count,
+                    Err(e) => {
+                        log::error!("failed to count unread messages: {}", e);
+                        chat.unreads = chat.unreads.max(chat.count_unreads(&own_did));
                     }
+                }
+            }
 
+            match inner.try_borrow_mut() {
+                Ok(state) => {
                     state.write().chats.all = all_chats;
+                    if let Some(key) = outbox_key {
+                        state.write().ui.notifications.unseal(key.clone());
+                        state.write().restore_outbox(key);
+                    }
                     state.write().account.identity = own_id;
                     state.write().chats.initialized = true;
                     //println!("{:#?}", state.read().chats);
@@ -941,6 +1163,19 @@ fn get_call_dialog(_cx: Scope) -> Element {
     None
 }
 
+// switches to the route requested with `Action::Navigate`, e.g. after an OS notification was clicked
+#[allow(non_snake_case)]
+fn RouteFollower(cx: Scope) -> Element {
+    let state = use_shared_state::<State>(cx)?;
+    let router = use_router(cx);
+    if state.read().route.pending.is_some() {
+        if let Some(to) = state.write_silent().route.pending.take() {
+            router.replace_route(&to, None, None);
+        }
+    }
+    None
+}
+
 fn get_router(cx: Scope) -> Element {
     let state = use_shared_state::<State>(cx)?;
     let pending_friends = state.read().friends.incoming_requests.len();
@@ -983,6 +1218,7 @@ fn get_router(cx: Scope) -> Element {
 
     cx.render(rsx!(
         Router {
+            RouteFollower {},
             Route {
                 to: UPLINK_ROUTES.chat,
                 ChatLayout {
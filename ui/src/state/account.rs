@@ -1,11 +1,37 @@
 use serde::{Deserialize, Serialize};
+use warp::multipass::identity::IdentityStatus;
 
 use super::identity::Identity;
 
+// The presence the user picked. The status friends see can differ from it, e.g. while do not disturb is active
+// or after the user went idle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Presence {
+    #[default]
+    Online,
+    Away,
+    Busy,
+    // appears offline to friends
+    Invisible,
+}
+
+impl Presence {
+    pub fn status(&self) -> IdentityStatus {
+        match self {
+            Presence::Online => IdentityStatus::Online,
+            Presence::Away => IdentityStatus::Away,
+            Presence::Busy => IdentityStatus::Busy,
+            Presence::Invisible => IdentityStatus::Offline,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Account {
     #[serde(default)]
     pub identity: Identity,
+    #[serde(default)]
+    pub presence: Presence,
     // pub settings: Option<CustomSettings>,
     // pub profile: Option<Profile>,
 }
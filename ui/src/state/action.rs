@@ -1,15 +1,16 @@
 use derive_more::Display;
 use dioxus_desktop::{tao::window::WindowId, DesktopContext};
 use either::Either;
-use std::rc::Weak;
+use std::{path::PathBuf, rc::Weak};
 use uuid::Uuid;
 use warp::raygun::Message;
 use wry::webview::WebView;
 
 use super::{
-    chats::Chat,
+    account::Presence,
+    chats::{Chat, ChatNotifications, PendingMessage, PendingStatus},
     identity::Identity,
-    notifications::NotificationKind,
+    notifications::{Notification, NotificationKind},
     route::To,
     ui::{Theme, ToastNotification, WindowMeta},
     State,
@@ -69,13 +70,21 @@ pub enum Action {
 
     // Notifications
     #[display(fmt = "AddNotification")]
-    AddNotification(NotificationKind, u32),
-    #[display(fmt = "RemoveNotification")]
-    RemoveNotification(NotificationKind, u32),
+    AddNotification(Notification),
+    #[display(fmt = "MarkNotificationRead")]
+    MarkNotificationRead(Uuid),
+    #[display(fmt = "MarkAllNotificationsRead")]
+    MarkAllNotificationsRead,
     #[display(fmt = "ClearNotification")]
     ClearNotification(NotificationKind),
     #[display(fmt = "ClearAllNotifications")]
     ClearAllNotifications,
+    /// the presence picked by the user
+    #[display(fmt = "SetPresence")]
+    SetPresence(Presence),
+    /// shows or hides the notification center
+    #[display(fmt = "ShowNotificationCenter")]
+    ShowNotificationCenter(bool),
     // Settings
     /// Sets the selected language.
     #[display(fmt = "SetLanguage")]
@@ -127,6 +136,12 @@ pub enum Action {
     /// Removes a chat from the sidebar, also removes the active chat if the chat being removed matches
     #[display(fmt = "RemoveFromSidebar")]
     RemoveFromSidebar(Uuid),
+    /// Replaces the notification settings of a chat
+    #[display(fmt = "SetChatNotifications")]
+    SetChatNotifications(Uuid, ChatNotifications),
+    /// Shows or hides the notification settings of the active chat
+    #[display(fmt = "ShowChatNotifications: {_0}")]
+    ShowChatNotifications(bool),
     /// Adds or removes a chat from the favorites page
     #[display(fmt = "ToggleFavorite")]
     ToggleFavorite(Chat),
@@ -142,18 +157,50 @@ pub enum Action {
     /// conversation id, message id, reaction
     #[display(fmt = "RemoveReaction")]
     RemoveReaction(Uuid, Uuid, String),
-    /// Reply to a given message by ID
-    #[display(fmt = "Reply")]
-    Reply(Chat, Message),
+    /// Adds a message to the outbox and shows it in the chat until warp confirms it
+    #[display(fmt = "QueueMessage")]
+    QueueMessage(PendingMessage),
+    /// message id, status
+    #[display(fmt = "SetPendingStatus")]
+    SetPendingStatus(Uuid, PendingStatus),
+    /// Removes a message which couldn't be sent from the outbox and the chat
+    #[display(fmt = "DiscardMessage")]
+    DiscardMessage(Uuid),
+    /// Shows files as being uploaded to a chat until warp reports that they were sent
+    /// (conversation id, files)
+    #[display(fmt = "AddUploads")]
+    AddUploads(Uuid, Vec<PathBuf>),
+    /// Moves an emoji to the front of the recently used list
+    #[display(fmt = "EmojiUsed")]
+    EmojiUsed(String),
     /// Prep the UI for a message reply.
     #[display(fmt = "StartReplying")]
     StartReplying(Chat, Message),
+    /// Adds a page of older messages, fetched from warp, to the front of the chat's message window.
+    /// ignored if the window moved since the page was requested.
+    /// (conversation id, id of the message the page was fetched before, messages, whether warp has older messages)
+    #[display(fmt = "PrependMessages")]
+    PrependMessages(Uuid, Option<Uuid>, Vec<Message>, bool),
+    /// Adds a page of newer messages, fetched from warp, to the end of the chat's message window.
+    /// ignored if the window moved since the page was requested.
+    /// (conversation id, id of the message the page was fetched after, messages, whether warp has newer messages)
+    #[display(fmt = "AppendMessages")]
+    AppendMessages(Uuid, Uuid, Vec<Message>, bool),
+    /// Replaces the chat's message window with messages fetched from warp, e.g. the page around a message to jump to.
+    /// (conversation id, messages, whether warp has older messages, whether warp has newer messages)
+    #[display(fmt = "ReplaceMessages")]
+    ReplaceMessages(Uuid, Vec<Message>, bool, bool),
+    /// Opens a chat and scrolls to the given message
+    /// (conversation id, message id)
+    #[display(fmt = "JumpToMessage")]
+    JumpToMessage(Uuid, Uuid),
     /// Clears the reply for a given chat
     #[display(fmt = "CancelReply")]
     CancelReply(Chat),
     /// fakes sending a message to the specified chat
     /// for normal operation, warp sends a message, Uplink receives an event when that message was sent, and state is updated accordingly.
     /// for mock data, warp is not used and this is needed to fake sending a message
+    /// if the chat is replying to a message, the fake message becomes a reply to it
     /// (Conversation Id, message)
     #[display(fmt = "MockSend")]
     MockSend(Uuid, Vec<String>),
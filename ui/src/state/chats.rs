@@ -1,16 +1,172 @@
 use std::{
     collections::{HashMap, VecDeque},
+    fmt,
+    path::PathBuf,
     time::Instant,
 };
 
-use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use uuid::Uuid;
-use warp::{crypto::DID, raygun::Message};
+use warp::{
+    crypto::{cipher::Cipher, DID},
+    logging::tracing::log,
+    raygun::{ConversationType, Message},
+};
+use zeroize::Zeroizing;
 
-use crate::STATIC_ARGS;
+use crate::{utils::mentions, STATIC_ARGS};
 
 use super::identity::Identity;
 
+// the number of messages fetched from warp at a time
+pub const MESSAGE_PAGE_SIZE: usize = 50;
+// the most messages a chat keeps in memory. past this, messages are evicted from the end the user isn't reading
+pub const MAX_MESSAGE_WINDOW: usize = 250;
+
+// the newest message the user has seen in a chat
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastRead {
+    pub message_id: Uuid,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingStatus {
+    // waiting to be sent. messages go back to this state if warp_runner couldn't be reached
+    Queued,
+    // being sent to warp
+    Sending,
+    // warp rejected the message. the user can retry or discard it
+    Failed,
+    // warp accepted the message. waiting for the MessageSent event. these aren't persisted
+    Sent,
+}
+
+// a message the user sent which hasn't been confirmed by warp. a placeholder with the same id is shown in the chat until then
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingMessage {
+    pub message: Message,
+    pub status: PendingStatus,
+}
+
+impl PendingMessage {
+    pub fn new(
+        conversation_id: Uuid,
+        sender: DID,
+        msg: Vec<String>,
+        reply_to: Option<Uuid>,
+    ) -> Self {
+        let mut message = Message::default();
+        message.set_conversation_id(conversation_id);
+        message.set_sender(sender);
+        message.set_replied(reply_to);
+        message.set_value(msg);
+        message.set_date(Utc::now());
+        Self {
+            message,
+            status: PendingStatus::Queued,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.message.id()
+    }
+
+    pub fn conversation_id(&self) -> Uuid {
+        self.message.conversation_id()
+    }
+}
+
+/// The key the outbox and the notification history are encrypted with before they're saved. It's kept in tesseract.
+#[derive(Clone, Default)]
+pub struct OutboxKey(Zeroizing<Vec<u8>>);
+
+impl OutboxKey {
+    pub fn new(key: Vec<u8>) -> Self {
+        Self(Zeroizing::new(key))
+    }
+
+    // encrypts a value as JSON. returns base64 so that it can be saved in state.json
+    pub fn seal<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, String> {
+        let json = Zeroizing::new(serde_json::to_vec(value).map_err(|e| e.to_string())?);
+        let sealed = Cipher::direct_encrypt(&json, &self.0).map_err(|e| e.to_string())?;
+        Ok(base64::encode(sealed))
+    }
+
+    pub fn unseal<T: DeserializeOwned>(&self, sealed: &str) -> Result<T, String> {
+        let cipher = base64::decode(sealed).map_err(|e| e.to_string())?;
+        let json =
+            Zeroizing::new(Cipher::direct_decrypt(&cipher, &self.0).map_err(|e| e.to_string())?);
+        serde_json::from_slice(&json).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Debug for OutboxKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OutboxKey")
+    }
+}
+
+// which messages in a chat raise a notification
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationLevel {
+    #[default]
+    All,
+    MentionsOnly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mute {
+    Forever,
+    Until(DateTime<Utc>),
+}
+
+// the notification settings of a single chat. they apply on top of the global notification settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatNotifications {
+    #[serde(default)]
+    pub level: NotificationLevel,
+    // a muted chat still counts unreads, but doesn't notify or play sounds
+    #[serde(default)]
+    pub muted: Option<Mute>,
+    #[serde(default = "bool_true")]
+    pub sounds: bool,
+}
+
+fn bool_true() -> bool {
+    true
+}
+
+impl Default for ChatNotifications {
+    fn default() -> Self {
+        Self {
+            level: NotificationLevel::All,
+            muted: None,
+            sounds: true,
+        }
+    }
+}
+
+impl ChatNotifications {
+    pub fn is_muted(&self) -> bool {
+        match self.muted {
+            Some(Mute::Forever) => true,
+            Some(Mute::Until(date)) => Utc::now() < date,
+            None => false,
+        }
+    }
+
+    // whether a new message should raise a notification
+    pub fn should_notify(&self, is_mention: bool) -> bool {
+        !self.is_muted() && (self.level == NotificationLevel::All || is_mention)
+    }
+
+    pub fn should_play_sound(&self, is_mention: bool) -> bool {
+        self.sounds && self.should_notify(is_mention)
+    }
+}
+
 // warning: Chat implements Serialize
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 pub struct Chat {
@@ -22,13 +178,36 @@ pub struct Chat {
     // these don't need to be stored in state either
     #[serde(default)]
     pub participants: Vec<Identity>,
+    // the name of a group conversation, if one was given. comes from warp
+    #[serde(default)]
+    pub name: Option<String>,
+    // a group stays a group even if only one other participant is left
+    #[serde(default)]
+    pub conversation_type: ConversationType,
     // Messages should only contain messages we want to render. Do not include the entire message history.
     // don't store the actual message in state
     #[serde(default)]
     pub messages: VecDeque<Message>,
+    // whether warp has messages older than the window. they're fetched as the user scrolls up
+    #[serde(skip)]
+    pub more_before: bool,
+    // whether messages newer than the window were evicted while the user was reading older ones.
+    // new messages aren't added to the window until the user scrolls back down to them
+    #[serde(skip)]
+    pub more_after: bool,
     // Unread count for this chat, should be cleared when we view the chat.
+    // counted as messages arrive, since the window doesn't hold every unread message
     #[serde(default)]
     pub unreads: u32,
+    // persisted so that unread counts survive a restart
+    #[serde(default)]
+    pub last_read: Option<LastRead>,
+    // the first message which was unread when the chat was opened. the "new messages" divider renders above it
+    #[serde(skip)]
+    pub first_unread: Option<Uuid>,
+    // a message to scroll to, picked from the search results. the page around it is fetched if it isn't in the window
+    #[serde(skip)]
+    pub jump_to: Option<Uuid>,
     // If a value exists, we will render the message we're replying to above the chatbar
     #[serde(skip)]
     pub replying_to: Option<Message>,
@@ -36,6 +215,11 @@ pub struct Chat {
     // (user id, last update time)
     #[serde(skip)]
     pub typing_indicator: HashMap<DID, Instant>,
+    // files being attached to a message, with their size in bytes. removed when warp reports that the upload is done
+    #[serde(skip)]
+    pub uploads: Vec<(PathBuf, u64)>,
+    #[serde(default)]
+    pub notifications: ChatNotifications,
 }
 
 // warning: Chats implements Serialize
@@ -59,7 +243,251 @@ pub struct Chats {
     // Favorite Chats
     #[serde(default)]
     pub favorites: Vec<Uuid>,
+    // messages which haven't been confirmed by warp, oldest first. persisted in `sealed_outbox` so that they aren't lost
+    #[serde(skip)]
+    pub outbox: Vec<PendingMessage>,
+    // the outbox as it was last saved, encrypted with `outbox_key`. message text is never saved in plaintext
+    #[serde(default)]
+    sealed_outbox: Option<String>,
+    // None until the user has logged in. until then, the outbox can't be read or saved
+    #[serde(skip)]
+    outbox_key: Option<OutboxKey>,
 }
+
+impl Chats {
+    pub fn pending_status(&self, message_id: Uuid) -> Option<PendingStatus> {
+        self.outbox
+            .iter()
+            .find(|p| p.id() == message_id)
+            .map(|p| p.status)
+    }
+
+    /// Decrypts the outbox saved by the last session. Messages queued since are kept after it.
+    pub fn unseal_outbox(&mut self, key: OutboxKey) {
+        if let Some(sealed) = self.sealed_outbox.take() {
+            match key.unseal::<Vec<PendingMessage>>(&sealed) {
+                Ok(mut outbox) => {
+                    outbox.append(&mut self.outbox);
+                    self.outbox = outbox;
+                }
+                Err(e) => log::error!("failed to read the outbox: {}", e),
+            }
+        }
+        self.outbox_key = Some(key);
+    }
+
+    // messages accepted by warp don't need to be sent again
+    fn seal_outbox(&self) -> Option<String> {
+        let key = match &self.outbox_key {
+            Some(k) => k,
+            // keep what the last session saved
+            None => return self.sealed_outbox.clone(),
+        };
+        let outbox: Vec<&PendingMessage> = self
+            .outbox
+            .iter()
+            .filter(|p| p.status != PendingStatus::Sent)
+            .collect();
+        if outbox.is_empty() {
+            return None;
+        }
+        match key.seal(&outbox) {
+            Ok(sealed) => Some(sealed),
+            Err(e) => {
+                log::error!("failed to save the outbox: {}", e);
+                None
+            }
+        }
+    }
+
+    // marks the oldest queued message as being sent and returns it
+    pub fn next_queued(&mut self) -> Option<PendingMessage> {
+        let pending = self
+            .outbox
+            .iter_mut()
+            .find(|p| p.status == PendingStatus::Queued)?;
+        pending.status = PendingStatus::Sending;
+        Some(pending.clone())
+    }
+}
+
+impl Chat {
+    pub fn is_group(&self) -> bool {
+        self.conversation_type == ConversationType::Group
+    }
+
+    // the participants who are currently typing
+    pub fn participants_typing(&self) -> Vec<&Identity> {
+        self.participants
+            .iter()
+            .filter(|p| self.typing_indicator.contains_key(&p.did_key()))
+            .collect()
+    }
+
+    pub fn has_older_messages(&self) -> bool {
+        self.more_before
+    }
+
+    pub fn has_newer_messages(&self) -> bool {
+        self.more_after
+    }
+
+    // the date of the oldest message in the window. older pages are fetched from before it
+    pub fn oldest_date(&self) -> Option<DateTime<Utc>> {
+        self.messages.front().map(|m| m.date())
+    }
+
+    // the id of the oldest message in the window. older pages are fetched from before it.
+    // None if the window only holds messages warp hasn't confirmed, in which case the newest page is fetched
+    pub fn older_cursor(&self, outbox: &[PendingMessage]) -> Option<Uuid> {
+        self.messages
+            .front()
+            .map(|m| m.id())
+            .filter(|id| !outbox.iter().any(|p| p.id() == *id))
+    }
+
+    // the id of the newest message in the window, if newer messages were evicted. newer pages are fetched from after it
+    pub fn newer_cursor(&self) -> Option<Uuid> {
+        self.messages
+            .back()
+            .map(|m| m.id())
+            .filter(|_| self.more_after)
+    }
+
+    // messages in the window which came after the last-read marker. messages older than the window aren't included,
+    // so `unreads` is the count to show
+    pub fn unread_messages<'a>(&'a self, own_did: &'a DID) -> impl Iterator<Item = &'a Message> {
+        self.messages.iter().filter(move |m| {
+            &m.sender() != own_did
+                && match &self.last_read {
+                    Some(r) => m.id() != r.message_id && m.date() > r.date,
+                    None => true,
+                }
+        })
+    }
+
+    pub fn has_unread_mentions(&self, own_did: &DID) -> bool {
+        self.unread_messages(own_did)
+            .any(|m| mentions::is_mentioned(&m.value(), own_did))
+    }
+
+    // only correct if the window holds every unread message. see `holds_unreads`
+    pub fn count_unreads(&self, own_did: &DID) -> u32 {
+        self.unread_messages(own_did).count() as u32
+    }
+
+    // whether every message after the last-read marker is in the window
+    pub fn holds_unreads(&self) -> bool {
+        if self.more_after {
+            return false;
+        }
+        if !self.more_before {
+            return true;
+        }
+        matches!((&self.last_read, self.oldest_date()), (Some(r), Some(d)) if d <= r.date)
+    }
+
+    // a last-read marker for the newest message in the window
+    pub fn newest_marker(&self) -> Option<LastRead> {
+        self.messages.back().map(|m| LastRead {
+            message_id: m.id(),
+            date: m.date(),
+        })
+    }
+
+    // moves the last-read marker to the newest message
+    pub fn mark_read(&mut self) {
+        // the newest messages aren't in the window. the marker moves once the user scrolls down to them
+        if !self.more_after {
+            if let Some(r) = self.newest_marker() {
+                self.last_read = Some(r);
+            }
+        }
+        self.unreads = 0;
+    }
+
+    // messages which are still in the outbox are shown after the newest ones
+    pub fn show_pending(&mut self, outbox: &[PendingMessage]) {
+        for pending in outbox.iter().filter(|p| p.conversation_id() == self.id) {
+            if !self.messages.iter().any(|m| m.id() == pending.id()) {
+                self.messages.push_back(pending.message.clone());
+            }
+        }
+    }
+
+    // adds a page of older messages, fetched from before `before`. the user is reading the oldest messages, so the
+    // newest are evicted if the window grows too large. returns false if the window moved since the page was requested
+    pub fn prepend_messages(
+        &mut self,
+        outbox: &[PendingMessage],
+        before: Option<Uuid>,
+        messages: Vec<Message>,
+        more_before: bool,
+    ) -> bool {
+        if self.older_cursor(outbox) != before {
+            return false;
+        }
+        self.more_before = more_before;
+        for message in messages.into_iter().rev() {
+            self.messages.push_front(message);
+        }
+        self.truncate_newer_messages(MAX_MESSAGE_WINDOW);
+        true
+    }
+
+    // adds a page of newer messages, fetched from after `after`. the user is reading the newest messages, so the
+    // oldest are evicted if the window grows too large. returns false if the window moved since the page was requested
+    pub fn append_messages(
+        &mut self,
+        outbox: &[PendingMessage],
+        after: Uuid,
+        messages: Vec<Message>,
+        more_after: bool,
+    ) -> bool {
+        if self.newer_cursor() != Some(after) {
+            return false;
+        }
+        self.messages.extend(messages);
+        self.more_after = more_after;
+        if !more_after {
+            self.show_pending(outbox);
+        }
+        self.truncate_messages(MAX_MESSAGE_WINDOW);
+        true
+    }
+
+    pub fn replace_messages(
+        &mut self,
+        outbox: &[PendingMessage],
+        messages: Vec<Message>,
+        more_before: bool,
+        more_after: bool,
+    ) {
+        self.messages = messages.into();
+        self.more_before = more_before;
+        self.more_after = more_after;
+        if !more_after {
+            self.show_pending(outbox);
+        }
+    }
+
+    // evicts the oldest messages until the window is no larger than `max`
+    pub fn truncate_messages(&mut self, max: usize) {
+        while self.messages.len() > max {
+            self.messages.pop_front();
+            self.more_before = true;
+        }
+    }
+
+    // evicts the newest messages until the window is no larger than `max`. used while the user reads older messages
+    pub fn truncate_newer_messages(&mut self, max: usize) {
+        while self.messages.len() > max {
+            self.messages.pop_back();
+            self.more_after = true;
+        }
+    }
+}
+
 pub enum Direction {
     Incoming,
     Outgoing,
@@ -70,7 +498,7 @@ impl Serialize for Chats {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Chats", 6)?;
+        let mut state = serializer.serialize_struct("Chats", 8)?;
 
         if STATIC_ARGS.use_mock {
             state.serialize_field("initialized", &self.initialized)?;
@@ -83,6 +511,9 @@ impl Serialize for Chats {
         state.skip_field("active_media")?;
         state.serialize_field("in_sidebar", &self.in_sidebar)?;
         state.serialize_field("favorites", &self.favorites)?;
+        state.skip_field("outbox")?;
+        state.serialize_field("sealed_outbox", &self.seal_outbox())?;
+        state.skip_field("outbox_key")?;
 
         state.end()
     }
@@ -94,19 +525,321 @@ impl Serialize for Chat {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Chat", 5)?;
+        let mut state = serializer.serialize_struct("Chat", 9)?;
         state.serialize_field("id", &self.id)?;
 
         if STATIC_ARGS.use_mock {
             state.serialize_field("participants", &self.participants)?;
+            state.serialize_field("name", &self.name)?;
+            state.serialize_field("conversation_type", &self.conversation_type)?;
             state.serialize_field("messages", &self.messages)?;
         } else {
             state.skip_field("participants")?;
+            state.skip_field("name")?;
+            state.skip_field("conversation_type")?;
             state.skip_field("messages")?;
         }
 
         state.serialize_field("unreads", &self.unreads)?;
+        state.serialize_field("last_read", &self.last_read)?;
+        state.serialize_field("notifications", &self.notifications)?;
         state.skip_field("replying_to")?;
         state.end()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn chat(id: Uuid) -> Chat {
+        Chat {
+            id,
+            participants: vec![],
+            name: None,
+            conversation_type: ConversationType::Direct,
+            messages: VecDeque::new(),
+            more_before: false,
+            more_after: false,
+            unreads: 0,
+            last_read: None,
+            first_unread: None,
+            jump_to: None,
+            replying_to: None,
+            typing_indicator: HashMap::new(),
+            uploads: vec![],
+            notifications: ChatNotifications::default(),
+        }
+    }
+
+    // messages `minutes` apart sort by date
+    fn message(conversation_id: Uuid, sender: &DID, minutes: i64) -> Message {
+        let mut message = Message::default();
+        message.set_conversation_id(conversation_id);
+        message.set_sender(sender.clone());
+        message.set_value(vec![format!("message {minutes}")]);
+        message.set_date(DateTime::<Utc>::default() + Duration::minutes(minutes));
+        message
+    }
+
+    fn messages(
+        conversation_id: Uuid,
+        sender: &DID,
+        minutes: std::ops::Range<i64>,
+    ) -> Vec<Message> {
+        minutes
+            .map(|m| message(conversation_id, sender, m))
+            .collect()
+    }
+
+    fn ids(chat: &Chat) -> Vec<Uuid> {
+        chat.messages.iter().map(|m| m.id()).collect()
+    }
+
+    fn marker(message: &Message) -> Option<LastRead> {
+        Some(LastRead {
+            message_id: message.id(),
+            date: message.date(),
+        })
+    }
+
+    #[test]
+    fn test_truncate_messages() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let all = messages(chat.id, &alice, 0..5);
+        chat.replace_messages(&[], all.clone(), false, false);
+
+        // a window which already fits isn't marked as missing anything
+        chat.truncate_messages(5);
+        chat.truncate_newer_messages(5);
+        assert!(!chat.has_older_messages());
+        assert!(!chat.has_newer_messages());
+
+        chat.truncate_messages(3);
+        assert_eq!(
+            ids(&chat),
+            all[2..].iter().map(|m| m.id()).collect::<Vec<_>>()
+        );
+        assert!(chat.has_older_messages());
+        assert!(!chat.has_newer_messages());
+
+        chat.truncate_newer_messages(2);
+        assert_eq!(
+            ids(&chat),
+            all[2..4].iter().map(|m| m.id()).collect::<Vec<_>>()
+        );
+        assert!(chat.has_newer_messages());
+        assert_eq!(chat.newer_cursor(), Some(all[3].id()));
+    }
+
+    #[test]
+    fn test_prepend_messages() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let all = messages(chat.id, &alice, 0..4);
+        chat.replace_messages(&[], all[2..].to_vec(), true, false);
+        assert_eq!(chat.older_cursor(&[]), Some(all[2].id()));
+
+        // a page fetched from before a message which is no longer the oldest is dropped
+        assert!(!chat.prepend_messages(&[], Some(all[3].id()), all[..2].to_vec(), false));
+        assert_eq!(chat.messages.len(), 2);
+        assert!(chat.has_older_messages());
+
+        assert!(chat.prepend_messages(&[], Some(all[2].id()), all[..2].to_vec(), false));
+        assert_eq!(ids(&chat), all.iter().map(|m| m.id()).collect::<Vec<_>>());
+        assert!(!chat.has_older_messages());
+        assert!(!chat.has_newer_messages());
+    }
+
+    #[test]
+    fn test_prepend_evicts_newest() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let window = MAX_MESSAGE_WINDOW as i64;
+        let newest = messages(chat.id, &alice, window..window + MESSAGE_PAGE_SIZE as i64);
+        let older = messages(chat.id, &alice, 0..window);
+        chat.replace_messages(&[], newest.clone(), true, false);
+
+        assert!(chat.prepend_messages(&[], Some(newest[0].id()), older.clone(), false));
+        assert_eq!(chat.messages.len(), MAX_MESSAGE_WINDOW);
+        assert_eq!(chat.messages.front().map(|m| m.id()), Some(older[0].id()));
+        assert_eq!(
+            chat.messages.back().map(|m| m.id()),
+            Some(older[window as usize - 1].id())
+        );
+        assert!(!chat.has_older_messages());
+        // the newest page was evicted. it's fetched again from after the newest message left in the window
+        assert!(chat.has_newer_messages());
+        assert_eq!(chat.newer_cursor(), Some(older[window as usize - 1].id()));
+    }
+
+    #[test]
+    fn test_append_messages() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let all = messages(chat.id, &alice, 0..6);
+        let pending = PendingMessage::new(chat.id, alice.clone(), vec!["hello".into()], None);
+        let outbox = vec![pending.clone()];
+        chat.replace_messages(&outbox, all[..2].to_vec(), false, true);
+        // the newest messages aren't in the window, so the pending one isn't shown yet
+        assert_eq!(chat.messages.len(), 2);
+        assert_eq!(chat.newer_cursor(), Some(all[1].id()));
+
+        // a page fetched from after a message which is no longer the newest is dropped
+        assert!(!chat.append_messages(&outbox, all[0].id(), all[2..4].to_vec(), true));
+        assert_eq!(chat.messages.len(), 2);
+
+        assert!(chat.append_messages(&outbox, all[1].id(), all[2..4].to_vec(), true));
+        assert_eq!(chat.messages.len(), 4);
+        assert!(chat.has_newer_messages());
+
+        // the last page reaches the newest message. pending messages go after it
+        assert!(chat.append_messages(&outbox, all[3].id(), all[4..].to_vec(), false));
+        assert!(!chat.has_newer_messages());
+        assert_eq!(chat.newer_cursor(), None);
+        let mut expected: Vec<Uuid> = all.iter().map(|m| m.id()).collect();
+        expected.push(pending.id());
+        assert_eq!(ids(&chat), expected);
+    }
+
+    #[test]
+    fn test_append_evicts_oldest() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let window = MAX_MESSAGE_WINDOW as i64;
+        let all = messages(chat.id, &alice, 0..window + 10);
+        chat.replace_messages(&[], all[..window as usize].to_vec(), false, true);
+
+        let last = all[window as usize - 1].id();
+        assert!(chat.append_messages(&[], last, all[window as usize..].to_vec(), false));
+        assert_eq!(chat.messages.len(), MAX_MESSAGE_WINDOW);
+        assert_eq!(chat.messages.front().map(|m| m.id()), Some(all[10].id()));
+        assert!(chat.has_older_messages());
+        assert!(!chat.has_newer_messages());
+        assert_eq!(chat.older_cursor(&[]), Some(all[10].id()));
+    }
+
+    #[test]
+    fn test_cursors_skip_pending_messages() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let pending = PendingMessage::new(chat.id, alice.clone(), vec!["hello".into()], None);
+        let outbox = vec![pending.clone()];
+
+        // the window only holds a message warp hasn't confirmed, so the newest page is fetched
+        chat.replace_messages(&outbox, vec![], true, false);
+        assert_eq!(ids(&chat), vec![pending.id()]);
+        assert_eq!(chat.older_cursor(&outbox), None);
+        assert!(chat.prepend_messages(&outbox, None, messages(chat.id, &alice, 0..2), true));
+        assert_eq!(chat.messages.len(), 3);
+        assert_eq!(chat.messages.back().map(|m| m.id()), Some(pending.id()));
+
+        // pending messages at the back don't move the older cursor
+        let oldest = chat.messages.front().map(|m| m.id());
+        assert_eq!(chat.older_cursor(&outbox), oldest);
+        // nor is the newer cursor set while the newest messages are in the window
+        assert_eq!(chat.newer_cursor(), None);
+    }
+
+    #[test]
+    fn test_replace_messages() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let all = messages(chat.id, &alice, 0..3);
+        let pending = PendingMessage::new(chat.id, alice.clone(), vec!["hello".into()], None);
+        let other_chat =
+            PendingMessage::new(Uuid::new_v4(), alice.clone(), vec!["hi".into()], None);
+        let outbox = vec![pending.clone(), other_chat];
+
+        chat.replace_messages(&outbox, all.clone(), true, false);
+        assert!(chat.has_older_messages());
+        assert!(!chat.has_newer_messages());
+        // only this chat's pending messages are shown
+        assert_eq!(chat.messages.len(), 4);
+        // showing them again doesn't add them twice
+        chat.show_pending(&outbox);
+        assert_eq!(chat.messages.len(), 4);
+
+        chat.replace_messages(&outbox, all[..1].to_vec(), false, true);
+        assert_eq!(ids(&chat), vec![all[0].id()]);
+        assert!(chat.has_newer_messages());
+    }
+
+    #[test]
+    fn test_holds_unreads() {
+        let alice = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let all = messages(chat.id, &alice, 0..4);
+
+        // the whole history is in the window
+        chat.replace_messages(&[], all.clone(), false, false);
+        assert!(chat.holds_unreads());
+
+        // older messages aren't in the window. without a marker, they could all be unread
+        chat.replace_messages(&[], all[1..].to_vec(), true, false);
+        assert!(!chat.holds_unreads());
+        chat.last_read = marker(&all[0]);
+        assert!(!chat.holds_unreads());
+        chat.last_read = marker(&all[1]);
+        assert!(chat.holds_unreads());
+        chat.last_read = marker(&all[3]);
+        assert!(chat.holds_unreads());
+
+        // the newest messages were evicted
+        chat.replace_messages(&[], all.clone(), false, true);
+        assert!(!chat.holds_unreads());
+    }
+
+    #[test]
+    fn test_unread_messages() {
+        let alice = DID::default();
+        let own = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let mut all = messages(chat.id, &alice, 0..4);
+        all.push(message(chat.id, &own, 4));
+        all.push(message(chat.id, &alice, 5));
+        chat.replace_messages(&[], all.clone(), false, false);
+
+        // without a marker, every message from someone else is unread
+        assert_eq!(chat.count_unreads(&own), 5);
+
+        chat.last_read = marker(&all[1]);
+        let unread: Vec<Uuid> = chat.unread_messages(&own).map(|m| m.id()).collect();
+        assert_eq!(unread, vec![all[2].id(), all[3].id(), all[5].id()]);
+        assert!(!chat.has_unread_mentions(&own));
+
+        let mut mention = message(chat.id, &alice, 6);
+        mention.set_value(vec![format!("hi {}", mentions::encode_mention(&own))]);
+        chat.messages.push_back(mention);
+        assert!(chat.has_unread_mentions(&own));
+        assert_eq!(chat.count_unreads(&own), 4);
+
+        chat.mark_read();
+        assert_eq!(chat.count_unreads(&own), 0);
+        assert!(!chat.has_unread_mentions(&own));
+    }
+
+    #[test]
+    fn test_mark_read_with_newest_evicted() {
+        let alice = DID::default();
+        let own = DID::default();
+        let mut chat = chat(Uuid::new_v4());
+        let all = messages(chat.id, &alice, 0..4);
+        chat.replace_messages(&[], all[..2].to_vec(), false, true);
+        chat.unreads = 2;
+
+        // the marker stays put until the user scrolls down to the newest messages
+        chat.mark_read();
+        assert_eq!(chat.unreads, 0);
+        assert_eq!(chat.last_read, None);
+        assert_eq!(chat.count_unreads(&own), 2);
+
+        assert!(chat.append_messages(&[], all[1].id(), all[2..].to_vec(), false));
+        chat.mark_read();
+        assert_eq!(chat.last_read, marker(&all[3]));
+        assert_eq!(chat.count_unreads(&own), 0);
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::config::Configuration as Config;
+use crate::config::{Configuration as Config, DndSchedule};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Configuration {
@@ -61,8 +61,38 @@ impl Configuration {
         let _ = self.config.save();
     }
 
+    pub fn set_message_previews(&mut self, message_previews: bool) {
+        self.config.notifications.message_previews = message_previews;
+        let _ = self.config.save();
+    }
+
     pub fn set_settings_notifications(&mut self, settings_notifications: bool) {
         self.config.notifications.settings_notifications = settings_notifications;
         let _ = self.config.save();
     }
+
+    pub fn set_auto_away_minutes(&mut self, auto_away_minutes: u32) {
+        self.config.general.auto_away_minutes = auto_away_minutes;
+        let _ = self.config.save();
+    }
+
+    pub fn set_auto_lock_minutes(&mut self, auto_lock_minutes: u32) {
+        self.config.privacy.auto_lock_minutes = auto_lock_minutes;
+        let _ = self.config.save();
+    }
+
+    pub fn set_wipe_after_failed_unlocks(&mut self, wipe_after_failed_unlocks: u32) {
+        self.config.privacy.wipe_after_failed_unlocks = wipe_after_failed_unlocks;
+        let _ = self.config.save();
+    }
+
+    pub fn set_do_not_disturb(&mut self, do_not_disturb: bool) {
+        self.config.notifications.do_not_disturb = do_not_disturb;
+        let _ = self.config.save();
+    }
+
+    pub fn set_dnd_schedule(&mut self, dnd_schedule: DndSchedule) {
+        self.config.notifications.dnd_schedule = dnd_schedule;
+        let _ = self.config.save();
+    }
 }
@@ -6,12 +6,13 @@ pub mod friends;
 pub mod identity;
 pub mod notifications;
 pub mod route;
+pub mod search;
 pub mod settings;
 pub mod storage;
 pub mod ui;
 
 // export specific structs which the UI expects. these structs used to be in src/state.rs, before state.rs was turned into the `state` folder
-pub use account::Account;
+pub use account::{Account, Presence};
 pub use action::Action;
 pub use chats::{Chat, Chats};
 use dioxus_desktop::tao::window::WindowId;
@@ -19,13 +20,14 @@ pub use friends::Friends;
 pub use identity::Identity;
 pub use route::Route;
 pub use settings::Settings;
-use shared::language::get_local_text;
+use shared::language::{get_local_text, get_local_text_with_args, FluentValue};
 pub use ui::{Theme, ToastNotification, UI};
 
 use crate::{
     testing::mock::generate_mock,
+    utils::{mentions, notifications::NotificationAction},
     warp_runner::{
-        ui_adapter::{MessageEvent, MultiPassEvent, RayGunEvent},
+        ui_adapter::{MessageEvent, MultiPassEvent, RayGunEvent, TransferEvent},
         WarpEvent,
     },
     STATIC_ARGS,
@@ -45,7 +47,16 @@ use warp::{
     raygun::{self, Message, Reaction},
 };
 
-use self::{action::ActionHook, chats::Direction, configuration::Configuration, ui::Call};
+use self::{
+    action::ActionHook,
+    chats::{Direction, PendingMessage, PendingStatus},
+    configuration::Configuration,
+    search::SearchHit,
+    ui::Call,
+};
+
+// the most characters of a message shown in its notification
+const MAX_PREVIEW_LENGTH: usize = 120;
 
 #[derive(Default, Deserialize, Serialize)]
 pub struct State {
@@ -65,6 +76,9 @@ pub struct State {
     pub ui: ui::UI,
     #[serde(default)]
     pub configuration: configuration::Configuration,
+    // rebuilt from the message history on startup
+    #[serde(skip)]
+    pub search_index: search::SearchIndex,
     #[serde(skip_serializing, skip_deserializing)]
     pub(crate) hooks: Vec<action::ActionHook>,
 }
@@ -89,6 +103,7 @@ impl Clone for State {
             chats: self.chats.clone(),
             friends: self.friends.clone(),
             storage: self.storage.clone(),
+            search_index: self.search_index.clone(),
             hooks: Default::default(),
             settings: Default::default(),
             ui: Default::default(),
@@ -114,10 +129,30 @@ impl State {
 
         match action {
             // ===== Notifications =====
-            Action::AddNotification(kind, count) => self.ui.notifications.increment(kind, count),
-            Action::RemoveNotification(kind, count) => self.ui.notifications.decrement(kind, count),
-            Action::ClearNotification(kind) => self.ui.notifications.clear_kind(kind),
-            Action::ClearAllNotifications => self.ui.notifications.clear_all(),
+            Action::AddNotification(notification) => self
+                .ui
+                .notifications
+                .add(notification, &self.configuration.config),
+            Action::MarkNotificationRead(id) => self
+                .ui
+                .notifications
+                .mark_read(id, &self.configuration.config),
+            Action::MarkAllNotificationsRead => self
+                .ui
+                .notifications
+                .mark_all_read(&self.configuration.config),
+            Action::ClearNotification(kind) => self
+                .ui
+                .notifications
+                .clear_kind(kind, &self.configuration.config),
+            Action::ClearAllNotifications => {
+                self.ui.notifications.clear_all(&self.configuration.config)
+            }
+            Action::ShowNotificationCenter(show) => self.ui.show_notification_center = show,
+            Action::SetPresence(presence) => {
+                self.account.presence = presence;
+                self.set_own_status(self.own_status(Instant::now()));
+            }
             Action::AddToastNotification(notification) => {
                 self.ui
                     .toast_notifications
@@ -155,10 +190,17 @@ impl State {
                 self.chats.all.entry(chat.id).or_insert(chat);
             }
             Action::SidebarHidden(hidden) => self.ui.sidebar_hidden = hidden,
+            Action::ShowChatNotifications(show) => self.ui.show_chat_notifications = show,
             // Navigation
             Action::Navigate(to) => self.set_active_route(to),
             // Generic UI
-            Action::SetMeta(metadata) => self.ui.metadata = metadata,
+            Action::SetMeta(metadata) => {
+                self.ui.metadata = metadata;
+                // messages which arrived while the window was unfocused are read once it regains focus
+                if let Some(id) = self.chats.active {
+                    self.update_unreads(id);
+                }
+            }
             Action::ClearPopout(window) => self.ui.clear_popout(window),
             Action::SetPopout(webview) => self.ui.set_popout(webview),
             // Development
@@ -173,21 +215,64 @@ impl State {
                 // warning: ensure that warp is used to get/create the chat which is passed in here
                 //todo: check if (for the side which created the conversation) a warp event comes in and consider using that instead
                 self.set_active_chat(&chat);
-                self.clear_unreads(&chat);
-                self.chats.all.entry(chat.id).or_insert(chat);
+                let id = chat.id;
+                self.chats.all.entry(id).or_insert(chat);
+                self.open_unreads(id);
+            }
+            Action::SetChatNotifications(chat_id, notifications) => {
+                if let Some(chat) = self.chats.all.get_mut(&chat_id) {
+                    chat.notifications = notifications;
+                }
             }
-            Action::NewMessage(_, _) => todo!(),
+            Action::NewMessage(chat, message) => self.add_msg_to_chat(chat.id, message),
             Action::StartReplying(chat, message) => self.start_replying(&chat, &message),
             Action::CancelReply(chat) => self.cancel_reply(&chat),
             Action::ClearUnreads(chat) => self.clear_unreads(&chat),
-            Action::AddReaction(_, _, _) => todo!(),
-            Action::RemoveReaction(_, _, _) => todo!(),
-            Action::Reply(_, _) => todo!(),
+            Action::AddReaction(chat_id, message_id, emoji) => {
+                self.add_message_reaction(chat_id, message_id, emoji)
+            }
+            Action::RemoveReaction(chat_id, message_id, emoji) => {
+                self.remove_message_reaction(chat_id, message_id, emoji)
+            }
+            Action::EmojiUsed(emoji) => self.ui.emoji_used(emoji),
+            Action::QueueMessage(pending) => self.queue_message(pending),
+            Action::SetPendingStatus(id, status) => self.set_pending_status(id, status),
+            Action::DiscardMessage(id) => self.discard_message(id),
+            Action::AddUploads(conv_id, files) => {
+                if let Some(chat) = self.chats.all.get_mut(&conv_id) {
+                    for file in files {
+                        if chat.uploads.iter().any(|(f, _)| f == &file) {
+                            continue;
+                        }
+                        // read once here rather than every time the chatbar renders
+                        let size = std::fs::metadata(&file)
+                            .map(|m| m.len())
+                            .unwrap_or_default();
+                        chat.uploads.push((file, size));
+                    }
+                }
+            }
+            Action::PrependMessages(conv_id, before, messages, more) => {
+                self.prepend_messages(conv_id, before, messages, more)
+            }
+            Action::AppendMessages(conv_id, after, messages, more) => {
+                self.append_messages(conv_id, after, messages, more)
+            }
+            Action::ReplaceMessages(conv_id, messages, more_before, more_after) => {
+                self.replace_messages(conv_id, messages, more_before, more_after)
+            }
+            Action::JumpToMessage(conv_id, message_id) => self.jump_to_message(conv_id, message_id),
             Action::MockSend(id, msg) => {
                 let sender = self.account.identity.did_key();
+                let replying_to = self
+                    .chats
+                    .all
+                    .get_mut(&id)
+                    .and_then(|c| c.replying_to.take());
                 let mut m = raygun::Message::default();
                 m.set_conversation_id(id);
                 m.set_sender(sender);
+                m.set_replied(replying_to.map(|r| r.id()));
                 m.set_value(msg);
                 self.add_msg_to_chat(id, m);
             }
@@ -214,6 +299,20 @@ impl State {
     /// * `chat` - The chat to set as the active chat.
     fn set_active_chat(&mut self, chat: &Chat) {
         //println!("set-active-chat: {:#?}", chat);
+        // shrink the window of the chat being left. its older messages are fetched again if the user scrolls back up.
+        // mock data can't be fetched again, so it is left alone
+        if let Some(prev) = self
+            .chats
+            .active
+            .filter(|id| *id != chat.id && !STATIC_ARGS.use_mock)
+        {
+            if let Some(prev) = self.chats.all.get_mut(&prev) {
+                prev.truncate_messages(chats::MESSAGE_PAGE_SIZE);
+            }
+        }
+        if self.chats.active != Some(chat.id) {
+            self.ui.show_chat_notifications = false;
+        }
         self.chats.active = Some(chat.id);
         if !self.chats.in_sidebar.contains(&chat.id) {
             self.chats.in_sidebar.push(chat.id);
@@ -263,7 +362,8 @@ impl State {
     ///
     /// * `to` - The route to set as the active route.
     fn set_active_route(&mut self, to: String) {
-        self.route.active = to;
+        self.route.active = to.clone();
+        self.route.pending = Some(to);
     }
 
     /// Adds the given chat to the user's favorites.
@@ -316,7 +416,49 @@ impl State {
     ///
     fn clear_unreads(&mut self, chat: &Chat) {
         if let Some(chat) = self.chats.all.get_mut(&chat.id) {
-            chat.unreads = 0;
+            chat.mark_read();
+        }
+        self.ui
+            .notifications
+            .mark_chat_read(chat.id, &self.configuration.config);
+    }
+
+    /// Remembers where the unread messages of a chat begin, so the "new messages" divider can be shown, then clears them.
+    fn open_unreads(&mut self, chat_id: Uuid) {
+        let own_did = self.account.identity.did_key();
+        if let Some(chat) = self.chats.all.get_mut(&chat_id) {
+            chat.first_unread = chat.unread_messages(&own_did).next().map(|m| m.id());
+            chat.mark_read();
+        }
+        self.ui
+            .notifications
+            .mark_chat_read(chat_id, &self.configuration.config);
+    }
+
+    fn is_viewing(&self, chat_id: Uuid) -> bool {
+        self.chats.active == Some(chat_id) && self.ui.metadata.focused
+    }
+
+    /// Marks the chat the user is looking at as read, along with its notifications.
+    fn update_unreads(&mut self, chat_id: Uuid) {
+        if !self.is_viewing(chat_id) {
+            return;
+        }
+        if let Some(chat) = self.chats.all.get_mut(&chat_id) {
+            chat.mark_read();
+        }
+        self.ui
+            .notifications
+            .mark_chat_read(chat_id, &self.configuration.config);
+    }
+
+    /// Counts a message which was received in a chat. Messages which arrive in the chat the user is looking at are read right away.
+    /// The count is kept separately from the message window, which only holds the most recent messages.
+    fn count_received(&mut self, chat_id: Uuid) {
+        if self.is_viewing(chat_id) {
+            self.update_unreads(chat_id);
+        } else if let Some(chat) = self.chats.all.get_mut(&chat_id) {
+            chat.unreads = chat.unreads.saturating_add(1);
         }
     }
 
@@ -406,7 +548,7 @@ impl State {
 
         // Check if there is a direct chat with the friend being removed
         let direct_chat = all_chats.values().find(|chat| {
-            chat.participants.len() == 2
+            !chat.is_group()
                 && chat
                     .participants
                     .iter()
@@ -443,10 +585,283 @@ impl State {
     fn add_msg_to_chat(&mut self, conversation_id: Uuid, message: raygun::Message) {
         if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
             chat.typing_indicator.remove(&message.sender());
+            // messages which warp hasn't confirmed aren't searchable
+            if !self.chats.outbox.iter().any(|p| p.id() == message.id()) {
+                self.search_index.insert(conversation_id, &message);
+            }
+            // the user is reading older messages. this one is fetched when they scroll back down to it
+            if chat.has_newer_messages() {
+                return;
+            }
             chat.messages.push_back(message);
+            chat.truncate_messages(chats::MAX_MESSAGE_WINDOW);
+        }
+    }
+
+    fn queue_message(&mut self, pending: PendingMessage) {
+        let conv_id = pending.conversation_id();
+        // the reply is cleared once it's sent, unless the user has since started replying to a different message
+        if let Some(chat) = self.chats.all.get_mut(&conv_id) {
+            if chat.replying_to.as_ref().map(|m| m.id()) == pending.message.replied() {
+                chat.replying_to = None;
+            }
+        }
+        let message = pending.message.clone();
+        self.chats.outbox.push(pending);
+        // sending while reading older messages goes back to the newest ones. the newest page is fetched again
+        if let Some(chat) = self.chats.all.get_mut(&conv_id) {
+            if chat.has_newer_messages() {
+                chat.messages.clear();
+                chat.more_before = true;
+                chat.more_after = false;
+            }
+        }
+        self.add_msg_to_chat(conv_id, message);
+    }
+
+    fn set_pending_status(&mut self, message_id: Uuid, status: PendingStatus) {
+        if let Some(pending) = self.chats.outbox.iter_mut().find(|p| p.id() == message_id) {
+            pending.status = status;
+        }
+    }
+
+    fn discard_message(&mut self, message_id: Uuid) {
+        let pos = match self.chats.outbox.iter().position(|p| p.id() == message_id) {
+            Some(p) => p,
+            None => return,
+        };
+        let pending = self.chats.outbox.remove(pos);
+        if let Some(chat) = self.chats.all.get_mut(&pending.conversation_id()) {
+            chat.messages.retain(|m| m.id() != message_id);
+        }
+    }
+
+    // removes the pending message which warp just confirmed, along with its placeholder.
+    // a message marked as failed after a timeout may still have been sent
+    fn confirm_pending(&mut self, conversation_id: Uuid, pending_id: Uuid) {
+        let pos = match self.chats.outbox.iter().position(|p| p.id() == pending_id) {
+            Some(p) => p,
+            None => return,
+        };
+        self.chats.outbox.remove(pos);
+        if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
+            chat.messages.retain(|m| m.id() != pending_id);
+        }
+    }
+
+    /// Decrypts the outbox and shows its messages in their chats again after a restart.
+    /// Messages which were being sent when Uplink closed may have been sent, so they're marked as failed rather than sent again.
+    pub fn restore_outbox(&mut self, key: chats::OutboxKey) {
+        self.chats.unseal_outbox(key);
+        for pending in self.chats.outbox.iter_mut() {
+            if pending.status == PendingStatus::Sending {
+                pending.status = PendingStatus::Failed;
+            }
+            if let Some(chat) = self.chats.all.get_mut(&pending.conversation_id()) {
+                if !chat.messages.iter().any(|m| m.id() == pending.id()) {
+                    chat.messages.push_back(pending.message.clone());
+                }
+            }
+        }
+    }
+
+    fn prepend_messages(
+        &mut self,
+        conversation_id: Uuid,
+        before: Option<Uuid>,
+        messages: Vec<Message>,
+        more_before: bool,
+    ) {
+        let chat = match self.chats.all.get_mut(&conversation_id) {
+            Some(c) => c,
+            None => return,
+        };
+        // the page is stale if messages were evicted or another page was added in the meantime
+        if !chat.prepend_messages(&self.chats.outbox, before, messages, more_before) {
+            log::debug!(
+                "ignoring stale page of messages for chat {}",
+                conversation_id
+            );
         }
     }
 
+    fn append_messages(
+        &mut self,
+        conversation_id: Uuid,
+        after: Uuid,
+        messages: Vec<Message>,
+        more_after: bool,
+    ) {
+        let chat = match self.chats.all.get_mut(&conversation_id) {
+            Some(c) => c,
+            None => return,
+        };
+        if !chat.append_messages(&self.chats.outbox, after, messages, more_after) {
+            log::debug!(
+                "ignoring stale page of messages for chat {}",
+                conversation_id
+            );
+            return;
+        }
+        // the user scrolled down to the newest messages
+        if !more_after {
+            self.update_unreads(conversation_id);
+        }
+    }
+
+    fn replace_messages(
+        &mut self,
+        conversation_id: Uuid,
+        messages: Vec<Message>,
+        more_before: bool,
+        more_after: bool,
+    ) {
+        if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
+            chat.replace_messages(&self.chats.outbox, messages, more_before, more_after);
+        }
+    }
+
+    fn jump_to_message(&mut self, conversation_id: Uuid, message_id: Uuid) {
+        let chat = match self.chats.all.get(&conversation_id) {
+            Some(c) => c.clone(),
+            None => return,
+        };
+        self.set_active_chat(&chat);
+        self.open_unreads(conversation_id);
+        if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
+            chat.jump_to = Some(message_id);
+        }
+    }
+
+    /// Whether notifications should be kept quiet, either because do not disturb was turned on or because of its schedule.
+    /// Unread counts and badges still update.
+    pub fn is_dnd_active(&self) -> bool {
+        self.configuration
+            .config
+            .notifications
+            .is_dnd_active(chrono::Local::now().naive_local())
+    }
+
+    // a period of 0 minutes never elapses
+    fn idle_for(&self, now: Instant, minutes: u32) -> bool {
+        match self.ui.last_activity {
+            Some(last) if minutes > 0 => {
+                now.duration_since(last) >= Duration::from_secs(minutes as u64 * 60)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the user hasn't been active for the auto-away period
+    pub fn is_idle(&self, now: Instant) -> bool {
+        self.idle_for(now, self.configuration.config.general.auto_away_minutes)
+    }
+
+    /// Whether the user hasn't been active for the auto-lock period
+    pub fn should_auto_lock(&self, now: Instant) -> bool {
+        self.idle_for(now, self.configuration.config.privacy.auto_lock_minutes)
+    }
+
+    /// Drops the messages held in memory before Uplink is locked. They are fetched from warp again after unlocking.
+    /// Doesn't save, so mock data on disk is left alone.
+    pub fn clear_sensitive(&mut self) {
+        for chat in self.chats.all.values_mut() {
+            chat.messages.clear();
+            chat.more_before = false;
+            chat.more_after = false;
+            chat.replying_to = None;
+            chat.first_unread = None;
+            chat.jump_to = None;
+            chat.typing_indicator.clear();
+        }
+    }
+
+    /// The status friends should see: the picked presence, overridden by do not disturb and auto-away.
+    /// Invisible always appears offline.
+    pub fn own_status(&self, now: Instant) -> IdentityStatus {
+        match self.account.presence {
+            Presence::Invisible => IdentityStatus::Offline,
+            _ if self.is_dnd_active() => IdentityStatus::Busy,
+            Presence::Online if self.is_idle(now) => IdentityStatus::Away,
+            presence => presence.status(),
+        }
+    }
+
+    pub fn set_own_status(&mut self, status: IdentityStatus) {
+        self.account.identity.set_identity_status(status);
+    }
+
+    /// Whether the message mentions the user
+    fn is_mention(&self, message: &Message) -> bool {
+        mentions::is_mentioned(&message.value(), &self.account.identity.did_key())
+    }
+
+    /// Builds the title and body of the notification for a new message.
+    /// The title names the sender, and the group for group chats. The body only shows the message if previews are enabled.
+    fn message_notification(&self, conversation_id: Uuid, message: &Message) -> (String, String) {
+        let chat = self.chats.all.get(&conversation_id);
+        let sender = chat
+            .and_then(|c| {
+                c.participants
+                    .iter()
+                    .find(|p| p.did_key() == message.sender())
+            })
+            .cloned()
+            .unwrap_or_else(|| self.get_friend_identity(&message.sender()));
+        let group_name = chat.filter(|c| c.is_group()).map(|c| {
+            c.name.clone().unwrap_or_else(|| {
+                self.get_without_me(&c.participants)
+                    .iter()
+                    .map(|p| p.username())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+        });
+
+        // mentions of the user are called out in the title
+        let title = match (&group_name, self.is_mention(message)) {
+            (Some(group), is_mention) => get_local_text_with_args(
+                if is_mention {
+                    "messages.notification-group-mention-title"
+                } else {
+                    "messages.notification-group-title"
+                },
+                vec![
+                    ("user", FluentValue::from(sender.username())),
+                    ("group", FluentValue::from(group.as_str())),
+                ],
+            ),
+            (None, true) => get_local_text_with_args(
+                "messages.notification-mention-title",
+                vec![("user", FluentValue::from(sender.username()))],
+            ),
+            (None, false) => sender.username(),
+        };
+
+        let participants = chat.map(|c| c.participants.as_slice()).unwrap_or_default();
+        let text =
+            mentions::decode_mentions(&message.value().join("\n"), participants, |m| m.to_string());
+        let attachments = message.attachments().len();
+        let body = if !self.configuration.config.notifications.message_previews {
+            match group_name {
+                Some(_) => get_local_text("messages.notification-group-hidden"),
+                None => get_local_text("messages.notification-hidden"),
+            }
+        } else if text.trim().is_empty() && attachments > 0 {
+            get_local_text_with_args(
+                "messages.notification-attachments",
+                vec![("count", FluentValue::from(attachments))],
+            )
+        } else if text.chars().count() > MAX_PREVIEW_LENGTH {
+            let preview: String = text.chars().take(MAX_PREVIEW_LENGTH).collect();
+            format!("{}…", preview.trim_end())
+        } else {
+            text
+        };
+
+        (title, body)
+    }
+
     /// Getters
     /// Getters are the only public facing methods besides dispatch.
     /// Getters help retrieve data from state in common ways preventing reused code.
@@ -481,7 +896,7 @@ impl State {
         self.chats
             .all
             .values()
-            .find(|chat| chat.participants.len() == 2 && chat.participants.contains(friend))
+            .find(|chat| !chat.is_group() && chat.participants.contains(friend))
             .cloned()
     }
 
@@ -514,7 +929,13 @@ impl State {
         };
 
         for message in chat.messages.clone() {
-            if message.sender() != current_group.sender {
+            // the "new messages" divider renders above the group which starts with the first unread message
+            let starts_group =
+                message.sender() != current_group.sender || Some(message.id()) == chat.first_unread;
+            if starts_group && !current_group.messages.is_empty() {
+                if let Some(last) = current_group.messages.last_mut() {
+                    last.is_last = true;
+                }
                 message_groups.push(current_group);
                 current_group = MessageGroup {
                     remote: self.has_friend_with_did(&message.sender()),
@@ -538,6 +959,69 @@ impl State {
         message_groups
     }
 
+    /// Searches the messages of every chat. Chats whose name or participants match the query come first,
+    /// followed by the chats with the newest matching messages.
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
+        let terms: Vec<String> = search::tokenize(query).collect();
+        if terms.is_empty() {
+            return vec![];
+        }
+        let usernames: HashMap<DID, String> = self
+            .chats
+            .all
+            .values()
+            .flat_map(|c| c.participants.iter())
+            .map(|p| (p.did_key(), p.username()))
+            .collect();
+
+        let mut results: Vec<SearchResult> = vec![];
+        for hit in self.search_index.search(query, &usernames) {
+            match results
+                .iter_mut()
+                .find(|r| r.chat_id == hit.conversation_id)
+            {
+                Some(r) if r.hits.len() >= search::MAX_HITS_PER_CHAT => {}
+                Some(r) => r.hits.push(hit),
+                None => results.push(SearchResult {
+                    chat_id: hit.conversation_id,
+                    participants_match: false,
+                    hits: vec![hit],
+                }),
+            }
+        }
+
+        // most recently active chats first
+        let mut chats: Vec<&Chat> = self.chats.all.values().collect();
+        chats.sort_by_key(|c| std::cmp::Reverse(c.messages.back().map(|m| m.date())));
+        for chat in chats {
+            let mut names: Vec<String> = self
+                .get_without_me(&chat.participants)
+                .iter()
+                .map(|p| p.username())
+                .collect();
+            names.extend(chat.name.clone());
+            let matches = terms.iter().all(|term| {
+                names
+                    .iter()
+                    .any(|n| search::tokenize(n).any(|t| t.starts_with(term)))
+            });
+            if !matches {
+                continue;
+            }
+            match results.iter_mut().find(|r| r.chat_id == chat.id) {
+                Some(r) => r.participants_match = true,
+                None => results.push(SearchResult {
+                    chat_id: chat.id,
+                    participants_match: true,
+                    hits: vec![],
+                }),
+            }
+        }
+        // hits are newest first, so the chats are already ordered by their newest hit. the sort is stable
+        results.sort_by_key(|r| !r.participants_match);
+        results
+    }
+
     pub fn get_friend_identity(&self, did: &DID) -> Identity {
         self.friends.all.get(did).cloned().unwrap_or_default()
     }
@@ -635,6 +1119,49 @@ impl State {
         needs_update
     }
 
+    /// Replaces a message which was edited, keeping its position in the chat
+    pub fn update_message(&mut self, chat_id: Uuid, message: Message) {
+        let conv = match self.chats.all.get_mut(&chat_id) {
+            Some(c) => c,
+            None => {
+                log::warn!("attempted to update message in nonexistent conversation");
+                return;
+            }
+        };
+
+        let message_id = message.id();
+        if let Some(msg) = conv.messages.iter_mut().find(|m| m.id() == message_id) {
+            *msg = message.clone();
+        }
+        self.search_index.update(&message);
+
+        if conv.replying_to.as_ref().map(|m| m.id()) == Some(message_id) {
+            conv.replying_to = Some(message);
+        }
+    }
+
+    pub fn delete_message(&mut self, chat_id: Uuid, message_id: Uuid) {
+        let own_did = self.account.identity.did_key();
+        let conv = match self.chats.all.get_mut(&chat_id) {
+            Some(c) => c,
+            None => {
+                log::warn!("attempted to delete message in nonexistent conversation");
+                return;
+            }
+        };
+
+        // an unread message which is deleted no longer counts. messages outside the window can't be checked
+        if conv.unread_messages(&own_did).any(|m| m.id() == message_id) {
+            conv.unreads = conv.unreads.saturating_sub(1);
+        }
+        conv.messages.retain(|m| m.id() != message_id);
+        self.search_index.remove(message_id);
+
+        if conv.replying_to.as_ref().map(|m| m.id()) == Some(message_id) {
+            conv.replying_to = None;
+        }
+    }
+
     pub fn add_message_reaction(&mut self, chat_id: Uuid, message_id: Uuid, emoji: String) {
         let user = self.account.identity.did_key();
         let conv = match self.chats.all.get_mut(&chat_id) {
@@ -749,13 +1276,16 @@ impl State {
     }
 
     fn load_mock() -> Self {
-        let contents = match fs::read_to_string(&STATIC_ARGS.mock_cache_path) {
-            Ok(r) => r,
-            Err(_) => {
-                return generate_mock();
-            }
+        let mut state: State = match fs::read_to_string(&STATIC_ARGS.mock_cache_path) {
+            Ok(r) => serde_json::from_str(&r).unwrap_or_else(|_| generate_mock()),
+            Err(_) => generate_mock(),
         };
-        serde_json::from_str(&contents).unwrap_or_else(|_| generate_mock())
+        // the mock chats hold their whole history
+        for chat in state.chats.all.values() {
+            let messages: Vec<Message> = chat.messages.iter().cloned().collect();
+            state.search_index.insert_all(chat.id, &messages);
+        }
+        state
     }
 
     pub fn process_warp_event(&mut self, event: WarpEvent) {
@@ -764,6 +1294,7 @@ impl State {
             WarpEvent::MultiPass(evt) => self.process_multipass_event(evt),
             WarpEvent::RayGun(evt) => self.process_raygun_event(evt),
             WarpEvent::Message(evt) => self.process_message_event(evt),
+            WarpEvent::Transfer(evt) => self.process_transfer_event(evt),
         };
 
         let _ = self.save();
@@ -775,10 +1306,15 @@ impl State {
             MultiPassEvent::FriendRequestReceived(identity) => {
                 self.friends.incoming_requests.insert(identity.clone());
 
-                self.mutate(Action::AddNotification(
+                self.mutate(Action::AddNotification(notifications::Notification::new(
                     notifications::NotificationKind::FriendRequest,
-                    1,
-                ));
+                    get_local_text("friends.new_request"),
+                    get_local_text_with_args(
+                        "friends.request-received",
+                        vec![("user", FluentValue::from(identity.username()))],
+                    ),
+                    notifications::NotificationLink::Friends,
+                )));
 
                 // TODO: Get state available in this scope.
                 // Dispatch notifications only when we're not already focused on the application.
@@ -788,12 +1324,28 @@ impl State {
                     .notifications
                     .friends_notifications;
 
-                if !self.ui.metadata.focused && notifications_enabled {
-                    crate::utils::notifications::push_notification(
-                        get_local_text("friends.new-request"),
-                        format!("{} sent a request.", identity.username()),
+                if !self.ui.metadata.focused && notifications_enabled && !self.is_dnd_active() {
+                    crate::utils::notifications::push_notification_with_actions(
+                        get_local_text("friends.new_request"),
+                        get_local_text_with_args(
+                            "friends.request-received",
+                            vec![("user", FluentValue::from(identity.username()))],
+                        ),
                         Some(crate::utils::sounds::Sounds::Notification),
                         notify_rust::Timeout::Milliseconds(4),
+                        Some(NotificationAction::Open(
+                            notifications::NotificationLink::Friends,
+                        )),
+                        vec![
+                            (
+                                get_local_text("friends.accept"),
+                                NotificationAction::AcceptRequest(identity.clone()),
+                            ),
+                            (
+                                get_local_text("friends.decline"),
+                                NotificationAction::DenyRequest(identity),
+                            ),
+                        ],
                     );
                 }
             }
@@ -831,13 +1383,59 @@ impl State {
         }
     }
 
+    fn process_transfer_event(&mut self, event: TransferEvent) {
+        let (key, file) = match event {
+            TransferEvent::Uploaded {
+                conversation_id,
+                files,
+                result,
+            } => {
+                if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
+                    chat.uploads.retain(|(f, _)| !files.contains(f));
+                }
+                let e = match result {
+                    Ok(_) => return,
+                    Err(e) => e,
+                };
+                log::error!("failed to send attachments: {}", e);
+                let names: Vec<String> = files
+                    .iter()
+                    .filter_map(|f| f.file_name())
+                    .map(|f| f.to_string_lossy().to_string())
+                    .collect();
+                ("files.upload-failed", names.join(", "))
+            }
+            TransferEvent::Downloaded { file, path, result } => match result {
+                Ok(_) => {
+                    log::debug!("saved {} to {}", file, path.display());
+                    ("files.download-finished", file)
+                }
+                Err(e) => {
+                    log::error!("failed to download attachment: {}", e);
+                    ("files.download-failed", file)
+                }
+            },
+        };
+        self.mutate(Action::AddToastNotification(ToastNotification::init(
+            "".into(),
+            get_local_text_with_args(key, vec![("file", FluentValue::from(file))]),
+            None,
+            5,
+        )));
+    }
+
     fn process_raygun_event(&mut self, event: RayGunEvent) {
         match event {
-            RayGunEvent::ConversationCreated(chat) => {
-                if !self.chats.in_sidebar.contains(&chat.id) {
-                    self.chats.in_sidebar.insert(0, chat.id);
+            RayGunEvent::ConversationCreated(mut chat) => {
+                let id = chat.id;
+                if !self.chats.in_sidebar.contains(&id) {
+                    self.chats.in_sidebar.insert(0, id);
                 }
-                self.chats.all.insert(chat.id, chat);
+                // a new conversation holds its whole history in the window
+                let own_did = self.account.identity.did_key();
+                chat.unreads = chat.count_unreads(&own_did);
+                self.chats.all.insert(id, chat);
+                self.update_unreads(id);
             }
             RayGunEvent::ConversationDeleted(id) => {
                 self.chats.in_sidebar.retain(|x| *x != id);
@@ -855,38 +1453,80 @@ impl State {
                 conversation_id,
                 message,
             } => {
-                // todo: don't load all the messages by default. if the user scrolled up, for example, this incoming message may not need to be fetched yet.
+                let (title, body) = self.message_notification(conversation_id, &message);
+                let is_mention = self.is_mention(&message);
+                let chat_notifications = self
+                    .chats
+                    .all
+                    .get(&conversation_id)
+                    .map(|c| c.notifications)
+                    .unwrap_or_default();
+                let message_id = message.id();
                 self.add_msg_to_chat(conversation_id, message);
-
-                self.mutate(Action::AddNotification(
-                    notifications::NotificationKind::Message,
-                    1,
-                ));
+                self.count_received(conversation_id);
+
+                // muted chats are silent, mentions included. a "mentions only" chat notifies only on mentions.
+                // messages in the chat the user is looking at are already read
+                if chat_notifications.should_notify(is_mention) && !self.is_viewing(conversation_id)
+                {
+                    let kind = if is_mention {
+                        notifications::NotificationKind::Mention
+                    } else {
+                        notifications::NotificationKind::Message
+                    };
+                    self.mutate(Action::AddNotification(notifications::Notification::new(
+                        kind,
+                        title.clone(),
+                        body.clone(),
+                        notifications::NotificationLink::Chat {
+                            conversation_id,
+                            message_id: Some(message_id),
+                        },
+                    )));
+                }
 
                 // TODO: Get state available in this scope.
                 // Dispatch notifications only when we're not already focused on the application.
-                let notifications_enabled = self
-                    .configuration
-                    .config
-                    .notifications
-                    .messages_notifications;
-                let should_play_sound = self.chats.active != Some(conversation_id)
-                    && self.configuration.config.audiovideo.message_sounds;
-                let should_dispatch_notification =
-                    notifications_enabled && !self.ui.metadata.focused;
+                let notifications_enabled = self.configuration.config.notifications.enabled
+                    && self
+                        .configuration
+                        .config
+                        .notifications
+                        .messages_notifications;
+                let is_dnd_active = self.is_dnd_active();
+                let should_play_sound = !is_dnd_active
+                    && self.chats.active != Some(conversation_id)
+                    && self.configuration.config.audiovideo.message_sounds
+                    && chat_notifications.should_play_sound(is_mention);
+                let should_dispatch_notification = notifications_enabled
+                    && !is_dnd_active
+                    && !self.ui.metadata.focused
+                    && chat_notifications.should_notify(is_mention);
 
                 // This should be called if we have notifications enabled for new messages
                 if should_dispatch_notification {
-                    let sound = if self.configuration.config.audiovideo.message_sounds {
+                    let sound = if self.configuration.config.audiovideo.message_sounds
+                        && chat_notifications.sounds
+                    {
                         Some(crate::utils::sounds::Sounds::Notification)
                     } else {
                         None
                     };
-                    crate::utils::notifications::push_notification(
-                        get_local_text("friends.new-request"),
-                        format!("{} sent a request.", "NOT YET IMPL"),
+                    crate::utils::notifications::push_notification_with_actions(
+                        title,
+                        body,
                         sound,
                         notify_rust::Timeout::Milliseconds(4),
+                        Some(NotificationAction::Open(
+                            notifications::NotificationLink::Chat {
+                                conversation_id,
+                                message_id: Some(message_id),
+                            },
+                        )),
+                        vec![(
+                            get_local_text("messages.reply"),
+                            NotificationAction::Reply(conversation_id),
+                        )],
                     );
                 // If we don't have notifications enabled, but we still have sounds enabled, we should play the sound as long as we're not already actively focused on the convo where the message came from.
                 } else if should_play_sound {
@@ -896,12 +1536,29 @@ impl State {
             MessageEvent::Sent {
                 conversation_id,
                 message,
+                pending_id,
             } => {
-                // todo: don't load all the messages by default. if the user scrolled up, for example, this incoming message may not need to be fetched yet.
+                if let Some(id) = pending_id {
+                    self.confirm_pending(conversation_id, id);
+                }
+                self.add_msg_to_chat(conversation_id, message);
+                // sending a message means the user has caught up on the chat
                 if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
-                    chat.messages.push_back(message);
+                    chat.mark_read();
                 }
             }
+            MessageEvent::Edited {
+                conversation_id,
+                message,
+            } => {
+                self.update_message(conversation_id, message);
+            }
+            MessageEvent::Deleted {
+                conversation_id,
+                message_id,
+            } => {
+                self.delete_message(conversation_id, message_id);
+            }
             MessageEvent::MessageReactionAdded {
                 conversation_id,
                 message_id,
@@ -935,10 +1592,29 @@ impl State {
                     }
                 }
             }
+            MessageEvent::ConversationUpdated {
+                conversation_id,
+                participants,
+                name,
+            } => {
+                if let Some(chat) = self.chats.all.get_mut(&conversation_id) {
+                    chat.participants = participants;
+                    chat.name = name;
+                }
+            }
         }
     }
 }
 
+// the search hits in a chat
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    pub chat_id: Uuid,
+    // the chat's name or participants matched the query
+    pub participants_match: bool,
+    pub hits: Vec<SearchHit>,
+}
+
 // Define a struct to represent a group of messages from the same sender.
 #[derive(Clone)]
 pub struct MessageGroup {
@@ -1,125 +1,219 @@
-use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
-use crate::{config::Configuration, utils::notifications::set_badge};
+use chrono::{DateTime, Utc};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use uuid::Uuid;
+use warp::logging::tracing::log;
+
+use crate::{config::Configuration, utils::notifications::set_badge, STATIC_ARGS};
+
+use super::chats::OutboxKey;
+
+// the most entries kept in the notification center. the oldest are dropped first
+pub const MAX_NOTIFICATIONS: usize = 100;
 
 // This kind is used to determine which notification kind to add to. It can also be used for querying specific notification counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum NotificationKind {
     FriendRequest,
     Message,
+    Mention,
+    Settings,
+}
+
+// where the notification center goes when an entry is clicked
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NotificationLink {
+    Friends,
+    Chat {
+        conversation_id: Uuid,
+        message_id: Option<Uuid>,
+    },
     Settings,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub text: String,
+    pub date: DateTime<Utc>,
+    pub link: NotificationLink,
+    pub read: bool,
+}
+
+impl Notification {
+    pub fn new(
+        kind: NotificationKind,
+        title: String,
+        text: String,
+        link: NotificationLink,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            title,
+            text,
+            date: Utc::now(),
+            link,
+            read: false,
+        }
+    }
+}
+
+// The history shown in the notification center, newest first. The per-kind counts are the unread entries.
+// warning: Notifications implements Serialize
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Notifications {
-    pub friends: u32, // For notifications about new friends, friend requests and related CTAs.
-    pub messages: u32, // For notifications about new messages, mentions.
-    pub settings: u32, // For notifications about updates, issues and more.
+    // entries hold message previews, so the history is saved in `sealed_history` instead.
+    // read from older versions, which saved it in plaintext
+    #[serde(default)]
+    pub history: VecDeque<Notification>,
+    // the history as it was last saved, encrypted with `key`
+    #[serde(default)]
+    sealed_history: Option<String>,
+    // None until the user has logged in. until then, the history can't be read or saved
+    #[serde(skip)]
+    key: Option<OutboxKey>,
 }
 
 impl Notifications {
     pub fn new() -> Self {
         // By default we'll say there are no notifications.
-        Self {
-            // Represents the total notification count for all friend events.
-            friends: 0,
-            // Represents the total notification count for all message events. Including all conversations and groups.
-            messages: 0,
-            // Represents total notification count for all settings events. E.g. updates, issues, etc.
-            settings: 0,
+        Self::default()
+    }
+
+    /// Decrypts the history saved by the last session. Entries added since are kept before it.
+    pub fn unseal(&mut self, key: OutboxKey) {
+        if let Some(sealed) = self.sealed_history.take() {
+            match key.unseal::<VecDeque<Notification>>(&sealed) {
+                Ok(history) => {
+                    self.history.extend(history);
+                    self.history.truncate(MAX_NOTIFICATIONS);
+                }
+                Err(e) => log::error!("failed to read the notification history: {}", e),
+            }
         }
+        self.key = Some(key);
     }
 
-    // This method is used for calculating the badge count for the app tray icon.
-    pub fn total(&self) -> u32 {
-        // Since this is read only, we can just load the config here.
-        let config = Configuration::load_or_default();
+    fn seal(&self) -> Option<String> {
+        let key = match &self.key {
+            Some(k) => k,
+            // keep what the last session saved
+            None => return self.sealed_history.clone(),
+        };
+        if self.history.is_empty() {
+            return None;
+        }
+        match key.seal(&self.history) {
+            Ok(sealed) => Some(sealed),
+            Err(e) => {
+                log::error!("failed to save the notification history: {}", e);
+                None
+            }
+        }
+    }
 
+    // This method is used for calculating the badge count for the app tray icon.
+    pub fn total(&self, config: &Configuration) -> u32 {
         let mut total = 0;
 
         // Only count notifications that are enabled in the config.
         if config.notifications.friends_notifications {
-            total += self.friends;
+            total += self.get(NotificationKind::FriendRequest);
         }
         if config.notifications.messages_notifications {
-            total += self.messages;
+            total += self.get(NotificationKind::Message);
+            total += self.get(NotificationKind::Mention);
         }
         if config.notifications.settings_notifications {
-            total += self.settings;
+            total += self.get(NotificationKind::Settings);
         }
 
         total
     }
 
-    // Adds notification(s) to the specified kind.
-    pub fn increment(&mut self, kind: NotificationKind, count: u32) {
-        match kind {
-            NotificationKind::FriendRequest => self.friends += count,
-            NotificationKind::Message => self.messages += count,
-            NotificationKind::Settings => self.settings += count,
-        };
-
-        // Update the badge any time notifications are added.
-        let _ = set_badge(self.total());
+    pub fn unread(&self) -> u32 {
+        self.history.iter().filter(|n| !n.read).count() as u32
     }
 
-    // Removes notification(s) from the specified kind.
-    // Prevent underflow using saturating_sub()
-    pub fn decrement(&mut self, kind: NotificationKind, count: u32) {
-        match kind {
-            NotificationKind::FriendRequest => {
-                self.friends = self.friends.saturating_sub(count);
-            }
-            NotificationKind::Message => {
-                self.messages = self.messages.saturating_sub(count);
-            }
-            NotificationKind::Settings => {
-                self.settings = self.settings.saturating_sub(count);
-            }
-        };
+    // Adds a notification to the top of the history.
+    pub fn add(&mut self, notification: Notification, config: &Configuration) {
+        self.history.push_front(notification);
+        self.history.truncate(MAX_NOTIFICATIONS);
 
-        // Update the badge any time notifications are removed.
-        let _ = set_badge(self.total());
+        // Update the badge any time notifications are added.
+        let _ = set_badge(self.total(config));
     }
 
-    // Sets a notification count for the specified kind.
-    pub fn set(&mut self, kind: NotificationKind, count: u32) {
-        match kind {
-            NotificationKind::FriendRequest => self.friends = count,
-            NotificationKind::Message => self.messages = count,
-            NotificationKind::Settings => self.settings = count,
-        };
+    pub fn mark_read(&mut self, id: Uuid, config: &Configuration) {
+        if let Some(n) = self.history.iter_mut().find(|n| n.id == id) {
+            n.read = true;
+        }
+        let _ = set_badge(self.total(config));
+    }
 
-        // Update the badge with new possible totals.
-        let _ = set_badge(self.total());
+    // Marks the messages and mentions from a chat as read, e.g. once the chat was opened.
+    pub fn mark_chat_read(&mut self, conversation_id: Uuid, config: &Configuration) {
+        for n in self.history.iter_mut() {
+            if matches!(n.link, NotificationLink::Chat { conversation_id: id, .. } if id == conversation_id)
+            {
+                n.read = true;
+            }
+        }
+        let _ = set_badge(self.total(config));
     }
 
-    // Returns the total count for a given notification kind.
+    // Returns the unread count for a given notification kind.
     pub fn get(&self, kind: NotificationKind) -> u32 {
-        match kind {
-            NotificationKind::FriendRequest => self.friends,
-            NotificationKind::Message => self.messages,
-            NotificationKind::Settings => self.settings,
+        self.history
+            .iter()
+            .filter(|n| n.kind == kind && !n.read)
+            .count() as u32
+    }
+
+    // Marks all notifications of the specified kind as read.
+    pub fn clear_kind(&mut self, kind: NotificationKind, config: &Configuration) {
+        for n in self.history.iter_mut().filter(|n| n.kind == kind) {
+            n.read = true;
         }
+        // Update the badge with new possible totals.
+        let _ = set_badge(self.total(config));
     }
 
-    // Clears all notifications for the specified kind.
-    pub fn clear_kind(&mut self, kind: NotificationKind) {
-        match kind {
-            NotificationKind::FriendRequest => self.friends = 0,
-            NotificationKind::Message => self.messages = 0,
-            NotificationKind::Settings => self.settings = 0,
-        };
-        // Upadte the badge with new possible totals.
-        let _ = set_badge(self.total());
+    // Marks all notifications as read.
+    pub fn mark_all_read(&mut self, config: &Configuration) {
+        for n in self.history.iter_mut() {
+            n.read = true;
+        }
+        let _ = set_badge(self.total(config));
     }
 
-    // Clears all notifications.
-    pub fn clear_all(&mut self) {
-        self.friends = 0;
-        self.messages = 0;
-        self.settings = 0;
+    // Removes all notifications from the history.
+    pub fn clear_all(&mut self, config: &Configuration) {
+        self.history.clear();
 
         // Clear the badge.
-        let _ = set_badge(self.total());
+        let _ = set_badge(self.total(config));
+    }
+}
+
+impl Serialize for Notifications {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Notifications", 3)?;
+        // mock data isn't sensitive, and there's no key to seal it with
+        if STATIC_ARGS.use_mock {
+            state.serialize_field("history", &self.history)?;
+        } else {
+            state.skip_field("history")?;
+        }
+        state.serialize_field("sealed_history", &self.seal())?;
+        state.skip_field("key")?;
+        state.end()
     }
 }
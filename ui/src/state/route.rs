@@ -5,6 +5,9 @@ pub struct Route {
     // String representation of the current active route.
     #[serde(default)]
     pub active: To,
+    // A route the router should switch to, for navigation which starts outside of the router, e.g. from an OS notification.
+    #[serde(skip)]
+    pub pending: Option<To>,
 }
 
 /// Alias for the type representing a route.
@@ -0,0 +1,307 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
+};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use warp::{crypto::DID, raygun::Message};
+
+use crate::utils::mentions;
+
+// the most messages shown per chat in the search results
+pub const MAX_HITS_PER_CHAT: usize = 20;
+
+#[derive(Clone, Debug)]
+struct Entry {
+    conversation_id: Uuid,
+    sender: DID,
+    date: DateTime<Utc>,
+    text: String,
+    tokens: HashSet<String>,
+    // the DIDs of the users mentioned in the message
+    mentioned: Vec<String>,
+}
+
+// a message which matched a search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub conversation_id: Uuid,
+    pub message_id: Uuid,
+    pub sender: DID,
+    pub date: DateTime<Utc>,
+    pub text: String,
+}
+
+// an in-memory inverted index over message bodies. it isn't persisted. the history of a chat is only fetched
+// and indexed the first time the user searches; messages which arrive before that are indexed as they come in
+#[derive(Clone, Debug, Default)]
+pub struct SearchIndex {
+    // lowercase word -> ids of the messages containing it
+    tokens: BTreeMap<String, HashSet<Uuid>>,
+    by_sender: HashMap<DID, HashSet<Uuid>>,
+    // DID of the mentioned user -> ids of the messages mentioning them
+    by_mention: HashMap<String, HashSet<Uuid>>,
+    entries: HashMap<Uuid, Entry>,
+    // conversations whose whole history has been indexed
+    indexed: HashSet<Uuid>,
+}
+
+// splits text into lowercase words
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+}
+
+impl SearchIndex {
+    // adds a message to the index, replacing it if it was already indexed
+    pub fn insert(&mut self, conversation_id: Uuid, message: &Message) {
+        self.remove(message.id());
+        let text = message.value().join("\n");
+        // mentions are searched by the username of the mentioned user, not by the words of their DID
+        let (stripped, mentioned) = mentions::strip_mentions(&text);
+        let tokens: HashSet<String> = tokenize(&stripped).collect();
+        for token in &tokens {
+            self.tokens
+                .entry(token.clone())
+                .or_default()
+                .insert(message.id());
+        }
+        for did in &mentioned {
+            self.by_mention
+                .entry(did.clone())
+                .or_default()
+                .insert(message.id());
+        }
+        self.by_sender
+            .entry(message.sender())
+            .or_default()
+            .insert(message.id());
+        self.entries.insert(
+            message.id(),
+            Entry {
+                conversation_id,
+                sender: message.sender(),
+                date: message.date(),
+                text,
+                tokens,
+                mentioned,
+            },
+        );
+    }
+
+    // indexes the whole history of a conversation
+    pub fn insert_all(&mut self, conversation_id: Uuid, messages: &[Message]) {
+        for message in messages {
+            self.insert(conversation_id, message);
+        }
+        self.indexed.insert(conversation_id);
+    }
+
+    pub fn is_indexed(&self, conversation_id: Uuid) -> bool {
+        self.indexed.contains(&conversation_id)
+    }
+
+    // re-indexes an edited message
+    pub fn update(&mut self, message: &Message) {
+        if let Some(conversation_id) = self.entries.get(&message.id()).map(|e| e.conversation_id) {
+            self.insert(conversation_id, message);
+        }
+    }
+
+    pub fn remove(&mut self, message_id: Uuid) {
+        let entry = match self.entries.remove(&message_id) {
+            Some(e) => e,
+            None => return,
+        };
+        for token in entry.tokens {
+            if let Some(ids) = self.tokens.get_mut(&token) {
+                ids.remove(&message_id);
+                if ids.is_empty() {
+                    self.tokens.remove(&token);
+                }
+            }
+        }
+        for did in entry.mentioned {
+            if let Some(ids) = self.by_mention.get_mut(&did) {
+                ids.remove(&message_id);
+            }
+        }
+        if let Some(ids) = self.by_sender.get_mut(&entry.sender) {
+            ids.remove(&message_id);
+        }
+    }
+
+    pub fn remove_conversation(&mut self, conversation_id: Uuid) {
+        let ids: Vec<Uuid> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.conversation_id == conversation_id)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            self.remove(id);
+        }
+        self.indexed.remove(&conversation_id);
+    }
+
+    // messages matching every word of the query, newest first.
+    // a word matches the start of a word in the message, or the username of its sender or of a user it mentions
+    pub fn search(&self, query: &str, usernames: &HashMap<DID, String>) -> Vec<SearchHit> {
+        let mut matches: Option<HashSet<Uuid>> = None;
+        for term in tokenize(query) {
+            let mut ids: HashSet<Uuid> = self
+                .tokens
+                .range::<str, _>((Bound::Included(term.as_str()), Bound::Unbounded))
+                .take_while(|(token, _)| token.starts_with(&term))
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            for (did, name) in usernames {
+                if tokenize(name).any(|t| t.starts_with(&term)) {
+                    if let Some(sent) = self.by_sender.get(did) {
+                        ids.extend(sent.iter().copied());
+                    }
+                    if let Some(mentioned) = self.by_mention.get(&did.to_string()) {
+                        ids.extend(mentioned.iter().copied());
+                    }
+                }
+            }
+            matches = Some(match matches {
+                Some(prev) => prev.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+
+        let mut hits: Vec<SearchHit> = matches
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|id| {
+                self.entries.get(&id).map(|e| SearchHit {
+                    conversation_id: e.conversation_id,
+                    message_id: id,
+                    sender: e.sender.clone(),
+                    date: e.date,
+                    text: e.text.clone(),
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.date.cmp(&a.date));
+        hits
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(conversation_id: Uuid, sender: &DID, text: &str) -> Message {
+        let mut message = Message::default();
+        message.set_conversation_id(conversation_id);
+        message.set_sender(sender.clone());
+        message.set_value(vec![text.into()]);
+        message
+    }
+
+    #[test]
+    fn test_search() {
+        let conv = Uuid::new_v4();
+        let alice = DID::default();
+        let usernames = HashMap::from([(alice.clone(), "Alice".to_string())]);
+        let first = message(conv, &alice, "Lunch at noon?");
+        let second = message(conv, &alice, "the lunchbox is in the fridge");
+        let mut index = SearchIndex::default();
+        index.insert_all(conv, &[first.clone(), second.clone()]);
+        assert!(index.is_indexed(conv));
+
+        assert_eq!(index.search("LUNCH", &usernames).len(), 2);
+        assert_eq!(index.search("lunch noon", &usernames).len(), 1);
+        assert_eq!(index.search("alice fridge", &usernames).len(), 1);
+        assert!(index.search("dinner", &usernames).is_empty());
+        assert!(index.search("", &usernames).is_empty());
+
+        let mut edited = first.clone();
+        edited.set_value(vec!["dinner instead".into()]);
+        index.update(&edited);
+        assert_eq!(index.search("dinner", &usernames).len(), 1);
+        assert_eq!(index.search("noon", &usernames).len(), 0);
+
+        index.remove(second.id());
+        assert!(index.search("fridge", &usernames).is_empty());
+        index.remove_conversation(conv);
+        assert!(index.search("alice", &usernames).is_empty());
+        assert!(!index.is_indexed(conv));
+    }
+
+    #[test]
+    fn test_search_deleted() {
+        let conv = Uuid::new_v4();
+        let alice = DID::default();
+        let usernames = HashMap::new();
+        let first = message(conv, &alice, "see you at the station");
+        let second = message(conv, &alice, "the station is closed");
+        let mut index = SearchIndex::default();
+        index.insert_all(conv, &[first.clone(), second.clone()]);
+
+        index.remove(first.id());
+        let hits = index.search("station", &usernames);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, second.id());
+        // editing a deleted message doesn't add it back
+        index.update(&first);
+        assert_eq!(index.search("station", &usernames).len(), 1);
+        // removing it twice is harmless
+        index.remove(first.id());
+        assert!(index.search("see", &usernames).is_empty());
+    }
+
+    #[test]
+    fn test_search_mentions() {
+        let conv = Uuid::new_v4();
+        let alice = DID::default();
+        let bob = DID::default();
+        let usernames = HashMap::from([(bob.clone(), "Bob".to_string())]);
+        let text = format!("thanks {}!", mentions::encode_mention(&bob));
+        let mentioned = message(conv, &alice, &text);
+        let mut index = SearchIndex::default();
+        index.insert(conv, &mentioned);
+
+        // the encoded DID isn't searchable
+        assert!(index.search("did", &usernames).is_empty());
+        assert!(index.search("key", &usernames).is_empty());
+        // the mentioned user's name is
+        let hits = index.search("thanks bob", &usernames);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, text);
+
+        index.remove(mentioned.id());
+        assert!(index.search("bob", &usernames).is_empty());
+    }
+
+    #[test]
+    fn test_search_multiple_chats() {
+        let (first_conv, second_conv) = (Uuid::new_v4(), Uuid::new_v4());
+        let alice = DID::default();
+        let usernames = HashMap::new();
+        let mut first = message(first_conv, &alice, "movie night?");
+        first.set_date(Utc::now() - chrono::Duration::minutes(1));
+        let second = message(second_conv, &alice, "which movie?");
+        let mut index = SearchIndex::default();
+        index.insert_all(first_conv, &[first.clone()]);
+        index.insert(second_conv, &second);
+        assert!(index.is_indexed(first_conv));
+        assert!(!index.is_indexed(second_conv));
+
+        // newest first, across chats
+        let hits = index.search("movie", &usernames);
+        let ids: Vec<Uuid> = hits.iter().map(|h| h.message_id).collect();
+        assert_eq!(ids, vec![second.id(), first.id()]);
+        assert_eq!(hits[0].conversation_id, second_conv);
+        assert_eq!(hits[1].conversation_id, first_conv);
+
+        index.remove_conversation(first_conv);
+        let hits = index.search("movie", &usernames);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, second_conv);
+    }
+}
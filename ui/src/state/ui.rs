@@ -1,12 +1,15 @@
 use dioxus_desktop::{tao::window::WindowId, DesktopContext};
 use kit::icons::Icon;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, rc::Weak};
+use std::{collections::HashMap, rc::Weak, time::Instant};
 use uuid::Uuid;
 use wry::webview::WebView;
 
 use super::notifications::Notifications;
 
+// how many emojis are kept in the recently used list
+pub const MAX_RECENT_EMOJIS: usize = 24;
+
 #[derive(Debug, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
 pub struct WindowMeta {
     pub focused: bool,
@@ -34,7 +37,21 @@ pub struct UI {
     pub theme: Option<Theme>,
     pub enable_overlay: bool,
     pub sidebar_hidden: bool,
+    // the notification settings of the active chat are shown above its messages
+    #[serde(skip)]
+    pub show_chat_notifications: bool,
+    #[serde(skip)]
+    pub show_notification_center: bool,
+    // the last time the user moved the mouse, typed, or focused the window. used for auto-away
+    #[serde(skip)]
+    pub last_activity: Option<Instant>,
+    // set by "Lock now". main.rs locks Uplink when it sees this
+    #[serde(skip)]
+    pub lock_requested: bool,
     pub metadata: WindowMeta,
+    // emojis picked for reactions, most recent first
+    #[serde(default)]
+    pub recent_emojis: Vec<String>,
     // overlays or other windows are created via DesktopContext::new_window. they are stored here so they can be closed later.
     #[serde(skip)]
     pub overlays: Vec<Weak<WebView>>,
@@ -70,6 +87,16 @@ impl UI {
         }
     }
 
+    pub fn record_activity(&mut self) {
+        self.last_activity = Some(Instant::now());
+    }
+
+    pub fn emoji_used(&mut self, emoji: String) {
+        self.recent_emojis.retain(|e| e != &emoji);
+        self.recent_emojis.insert(0, emoji);
+        self.recent_emojis.truncate(MAX_RECENT_EMOJIS);
+    }
+
     pub fn get_meta(&self) -> WindowMeta {
         self.metadata.clone()
     }
@@ -15,7 +15,7 @@ use uuid::Uuid;
 use warp::{
     constellation::{directory::Directory, file::File},
     multipass::identity::{Graphics, IdentityStatus, Platform},
-    raygun::Message,
+    raygun::{ConversationType, Message},
 };
 
 use crate::state::{
@@ -39,13 +39,11 @@ pub fn generate_mock() -> State {
         all_chats.insert(chat.id, chat);
     }
 
-    // let group_chat = generate_random_chat(me.clone(), &identities);
-    // let group_chat_sidebar = group_chat.clone();
-
-    // all_chats.insert(group_chat.id, group_chat);
-
-    let in_sidebar = vec![];
-    // in_sidebar.push(group_chat_sidebar.id);
+    let mut group_chat = generate_random_chat(me.clone(), &identities[..3]);
+    group_chat.conversation_type = ConversationType::Group;
+    group_chat.name = Some("Mock Group".into());
+    let in_sidebar = vec![group_chat.id];
+    all_chats.insert(group_chat.id, group_chat);
     let mut toast_notifications = HashMap::new();
     toast_notifications.insert(
         Uuid::new_v4(),
@@ -75,6 +73,7 @@ pub fn generate_mock() -> State {
             active_media: None,
             in_sidebar,
             favorites: vec![],
+            ..Default::default()
         },
         storage,
         friends: Friends {
@@ -113,10 +112,19 @@ fn generate_fake_chat(participants: Vec<Identity>, conversation: Uuid) -> Chat {
     Chat {
         id: conversation,
         participants,
+        name: None,
+        conversation_type: ConversationType::Direct,
         messages,
+        more_before: false,
+        more_after: false,
         unreads: rng.gen_range(0..2),
+        last_read: None,
+        first_unread: None,
+        jump_to: None,
+        notifications: Default::default(),
         replying_to: None,
         typing_indicator: HashMap::new(),
+        uploads: Vec::new(),
     }
 }
 
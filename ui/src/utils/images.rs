@@ -0,0 +1,96 @@
+// profile pictures and banners are stored in the identity as data URIs, so they're validated and downscaled before upload
+use std::io::Cursor;
+
+use derive_more::Display;
+use image::{DynamicImage, ImageOutputFormat};
+
+// larger files are rejected before they're decoded
+pub const MAX_IMAGE_FILE_SIZE: usize = 10 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Display)]
+pub enum ImageError {
+    #[display(
+        fmt = "the image is larger than {} MB",
+        "MAX_IMAGE_FILE_SIZE / 1024 / 1024"
+    )]
+    TooLarge,
+    #[display(fmt = "the file isn't a supported image")]
+    Unsupported,
+    #[display(fmt = "failed to encode the image: {_0}")]
+    Encode(image::ImageError),
+}
+
+impl std::error::Error for ImageError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileImage {
+    Picture,
+    Banner,
+}
+
+impl ProfileImage {
+    // (width, height) the image is scaled down to fit in
+    pub fn max_size(&self) -> (u32, u32) {
+        match self {
+            ProfileImage::Picture => (512, 512),
+            ProfileImage::Banner => (1500, 500),
+        }
+    }
+}
+
+// decodes the image, scales it down to fit `kind`, and returns it as a data URI.
+// images with transparency are kept as png, the rest are re-encoded as jpeg
+pub fn prepare_profile_image(bytes: &[u8], kind: ProfileImage) -> Result<String, ImageError> {
+    if bytes.len() > MAX_IMAGE_FILE_SIZE {
+        return Err(ImageError::TooLarge);
+    }
+    let image = image::load_from_memory(bytes).map_err(|_| ImageError::Unsupported)?;
+    let (max_width, max_height) = kind.max_size();
+    let image = if image.width() > max_width || image.height() > max_height {
+        image.thumbnail(max_width, max_height)
+    } else {
+        image
+    };
+
+    let mut encoded = Vec::new();
+    let mime = if image.color().has_alpha() {
+        image
+            .write_to(&mut Cursor::new(&mut encoded), ImageOutputFormat::Png)
+            .map_err(ImageError::Encode)?;
+        mime::IMAGE_PNG
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(
+                &mut Cursor::new(&mut encoded),
+                ImageOutputFormat::Jpeg(JPEG_QUALITY),
+            )
+            .map_err(ImageError::Encode)?;
+        mime::IMAGE_JPEG
+    };
+    Ok(format!("data:{mime};base64,{}", base64::encode(encoded)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_prepare_profile_image() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(2000, 1000))
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+
+        let uri = prepare_profile_image(&png, ProfileImage::Picture).unwrap();
+        let data = uri.strip_prefix("data:image/jpeg;base64,").unwrap();
+        let decoded = image::load_from_memory(&base64::decode(data).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (512, 256));
+
+        assert!(matches!(
+            prepare_profile_image(b"not an image", ProfileImage::Banner),
+            Err(ImageError::Unsupported)
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+// mentions are typed as "@username" and sent as "<@did:key:...>" so that receiving clients can tell who was mentioned,
+// even if usernames collide or change
+use warp::crypto::DID;
+
+use crate::state::Identity;
+
+const MENTION_START: &str = "<@";
+const MENTION_END: char = '>';
+
+pub fn encode_mention(did: &DID) -> String {
+    format!("{MENTION_START}{did}{MENTION_END}")
+}
+
+pub fn is_mentioned(lines: &[String], did: &DID) -> bool {
+    let mention = encode_mention(did);
+    lines.iter().any(|line| line.contains(&mention))
+}
+
+// the partial username after a trailing "@", e.g. "al" for "hi @al"
+pub fn mention_query(text: &str) -> Option<&str> {
+    let word = text.rsplit(char::is_whitespace).next()?;
+    word.strip_prefix('@').filter(|q| !q.contains('@'))
+}
+
+// replaces the trailing "@partial" with the full username
+pub fn complete_mention(text: &str, username: &str) -> String {
+    let query_len = mention_query(text).map(|q| q.len() + 1).unwrap_or_default();
+    format!("{}@{} ", &text[..text.len() - query_len], username)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// replaces "@username" with the encoded mention of each participant
+pub fn encode_mentions(lines: Vec<String>, participants: &[Identity]) -> Vec<String> {
+    // longer names first, so that "@bob" doesn't match the start of "@bobby"
+    let mut participants: Vec<&Identity> = participants
+        .iter()
+        .filter(|p| !p.username().is_empty())
+        .collect();
+    participants.sort_by_key(|p| std::cmp::Reverse(p.username().len()));
+    lines
+        .into_iter()
+        .map(|line| {
+            participants.iter().fold(line, |line, p| {
+                let typed = format!("@{}", p.username());
+                let mut encoded = String::new();
+                let mut rest = line.as_str();
+                while let Some(idx) = rest.find(&typed) {
+                    let after = &rest[idx + typed.len()..];
+                    encoded.push_str(&rest[..idx]);
+                    // part of a longer word, or of a mention which was already encoded
+                    if after.starts_with(is_word_char) || rest[..idx].ends_with('<') {
+                        encoded.push_str(&typed);
+                    } else {
+                        encoded.push_str(&encode_mention(&p.did_key()));
+                    }
+                    rest = after;
+                }
+                encoded.push_str(rest);
+                encoded
+            })
+        })
+        .collect()
+}
+
+// replaces encoded mentions with "@username" for display. `format` can be used to style the name
+pub fn decode_mentions(
+    text: &str,
+    participants: &[Identity],
+    format: impl Fn(&str) -> String,
+) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(MENTION_START) {
+        let after = &rest[start + MENTION_START.len()..];
+        let end = match after.find(MENTION_END) {
+            Some(e) => e,
+            None => break,
+        };
+        let did = &after[..end];
+        let name = participants
+            .iter()
+            .find(|p| p.did_key().to_string() == did)
+            .map(|p| p.username());
+        decoded.push_str(&rest[..start]);
+        match name {
+            Some(name) => decoded.push_str(&format(&format!("@{name}"))),
+            // not a mention, or the user left the chat
+            None => decoded.push_str(&rest[start..start + MENTION_START.len() + end + 1]),
+        }
+        rest = &after[end + 1..];
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// (encoded mention, "@username") for each participant mentioned in the text. the markdown renderer shows them
+// as mentions, so that usernames aren't parsed as markdown
+pub fn mentioned_names(text: &str, participants: &[Identity]) -> Vec<(String, String)> {
+    participants
+        .iter()
+        .map(|p| (encode_mention(&p.did_key()), format!("@{}", p.username())))
+        .filter(|(mention, _)| text.contains(mention.as_str()))
+        .collect()
+}
+
+// removes encoded mentions, so that their DIDs aren't searched as words. also returns the DIDs which were mentioned
+pub fn strip_mentions(text: &str) -> (String, Vec<String>) {
+    let mut stripped = String::new();
+    let mut mentioned = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find(MENTION_START) {
+        let after = &rest[start + MENTION_START.len()..];
+        let end = match after.find(MENTION_END) {
+            Some(e) => e,
+            None => break,
+        };
+        stripped.push_str(&rest[..start]);
+        stripped.push(' ');
+        mentioned.push(after[..end].to_string());
+        rest = &after[end + 1..];
+    }
+    stripped.push_str(rest);
+    (stripped, mentioned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mention_query() {
+        assert_eq!(mention_query("hi @al"), Some("al"));
+        assert_eq!(mention_query("@"), Some(""));
+        assert_eq!(mention_query("hi @al "), None);
+        assert_eq!(mention_query("mail a@b"), None);
+        assert_eq!(complete_mention("hi @al", "alice"), "hi @alice ");
+    }
+
+    #[test]
+    fn test_encode_decode_mentions() {
+        let mut identity = warp::multipass::identity::Identity::default();
+        identity.set_username("alice");
+        let alice = Identity::from(identity);
+        let did = alice.did_key();
+        let name = alice.username();
+        let lines = vec![format!("hey @{name}!"), format!("@{name}s")];
+        let encoded = encode_mentions(lines, &[alice.clone()]);
+        assert_eq!(encoded[0], format!("hey <@{did}>!"));
+        assert_eq!(encoded[1], format!("@{name}s"));
+        assert!(is_mentioned(&encoded, &did));
+        assert_eq!(
+            decode_mentions(&encoded[0], &[alice.clone()], |m| m.to_string()),
+            format!("hey @{name}!")
+        );
+        assert_eq!(
+            strip_mentions(&encoded[0]),
+            ("hey  !".to_string(), vec![did.to_string()])
+        );
+        assert_eq!(
+            mentioned_names(&encoded[0], &[alice.clone()]),
+            vec![(format!("<@{did}>"), format!("@{name}"))]
+        );
+        assert!(mentioned_names(&encoded[1], &[alice]).is_empty());
+    }
+}
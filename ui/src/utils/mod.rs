@@ -10,7 +10,10 @@ use crate::{
 use kit::User as UserInfo;
 
 pub mod format_timestamp;
+pub mod images;
+pub mod mentions;
 pub mod notifications;
+pub mod passphrase;
 pub mod sounds;
 
 pub fn get_available_themes() -> Vec<Theme> {
@@ -99,6 +102,15 @@ pub fn build_user_from_identity(identity: state::Identity) -> UserInfo {
     }
 }
 
+// the kind of a file, as shown to the user. taken from the extension, e.g. "PDF"
+pub fn get_file_kind(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_uppercase())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -114,4 +126,10 @@ mod test {
         let r = get_pretty_name("c:\\pretty\\name2.scss");
         assert_eq!(r, String::from("name2"));
     }
+
+    #[test]
+    fn test_get_file_kind() {
+        assert_eq!(get_file_kind("report.final.pdf"), String::from("PDF"));
+        assert_eq!(get_file_kind("Makefile"), String::new());
+    }
 }
@@ -1,5 +1,57 @@
 use super::sounds::{Play, Sounds};
 use notify_rust::Notification;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use uuid::Uuid;
+#[cfg(target_os = "linux")]
+use warp::logging::tracing::log;
+
+use crate::state::{notifications::NotificationLink, Identity};
+
+// What happens when an OS notification, or one of its buttons, is clicked
+#[derive(Clone, Debug)]
+pub enum NotificationAction {
+    // focuses the window and navigates to what the notification is about
+    Open(NotificationLink),
+    // focuses the window on the chat, ready to reply
+    Reply(Uuid),
+    AcceptRequest(Identity),
+    DenyRequest(Identity),
+}
+
+// the id notify-rust reports when the body of the notification is clicked
+#[cfg(target_os = "linux")]
+const DEFAULT_ACTION: &str = "default";
+
+// waiting for a notification's actions blocks a thread until the notification is closed. past this many open
+// notifications with actions, new ones are shown without them
+#[cfg(target_os = "linux")]
+const MAX_ACTION_WAITERS: usize = 8;
+#[cfg(target_os = "linux")]
+static ACTION_WAITERS: AtomicUsize = AtomicUsize::new(0);
+
+// frees a waiter slot when its thread exits
+#[cfg(target_os = "linux")]
+struct WaiterSlot;
+
+#[cfg(target_os = "linux")]
+impl WaiterSlot {
+    fn acquire() -> Option<Self> {
+        ACTION_WAITERS
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_ACTION_WAITERS).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| WaiterSlot)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for WaiterSlot {
+    fn drop(&mut self) {
+        ACTION_WAITERS.fetch_sub(1, Ordering::AcqRel);
+    }
+}
 
 // Implementation to create and push new notifications
 #[allow(non_snake_case)]
@@ -8,13 +60,77 @@ pub fn push_notification(
     content: String,
     notification_sound: Option<Sounds>,
     timeout: notify_rust::Timeout,
+) {
+    push_notification_with_actions(title, content, notification_sound, timeout, None, vec![]);
+}
+
+// Same as `push_notification`, but clicking the notification runs `on_click`, and each (label, action) is shown as a button.
+// Actions are only supported on Linux. Elsewhere the notification is shown without them.
+pub fn push_notification_with_actions(
+    title: String,
+    content: String,
+    notification_sound: Option<Sounds>,
+    timeout: notify_rust::Timeout,
+    on_click: Option<NotificationAction>,
+    actions: Vec<(String, NotificationAction)>,
 ) {
     let summary = format!("Uplink - {title}");
-    let _n = Notification::new()
+    let mut notification = Notification::new();
+    notification
         .summary(summary.as_ref())
         .body(&content)
-        .timeout(timeout)
-        .show();
+        .timeout(timeout);
+
+    #[cfg(target_os = "linux")]
+    {
+        let has_actions = on_click.is_some() || !actions.is_empty();
+        let slot = if has_actions {
+            WaiterSlot::acquire()
+        } else {
+            None
+        };
+        if has_actions && slot.is_none() {
+            log::warn!("too many open notifications. showing this one without actions");
+        }
+        if slot.is_some() {
+            if on_click.is_some() {
+                notification.action(DEFAULT_ACTION, "");
+            }
+            for (idx, (label, _)) in actions.iter().enumerate() {
+                notification.action(&idx.to_string(), label);
+            }
+        }
+        match (notification.show(), slot) {
+            // waiting for the action blocks until the notification is closed
+            (Ok(handle), Some(slot)) => {
+                std::thread::spawn(move || {
+                    let _slot = slot;
+                    handle.wait_for_action(|id| {
+                        let action = if id == DEFAULT_ACTION {
+                            on_click
+                        } else {
+                            id.parse::<usize>()
+                                .ok()
+                                .and_then(|idx| actions.into_iter().nth(idx))
+                                .map(|(_, action)| action)
+                        };
+                        if let Some(action) = action {
+                            let _ = crate::WINDOW_CMD_CH.tx.send(
+                                crate::window_manager::WindowManagerCmd::NotificationAction(action),
+                            );
+                        }
+                    });
+                });
+            }
+            (Ok(_), None) => {}
+            (Err(e), _) => log::error!("failed to show notification: {e}"),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (on_click, actions);
+        let _n = notification.show();
+    }
 
     if let Some(sound) = notification_sound {
         Play(sound);
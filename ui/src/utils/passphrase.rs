@@ -0,0 +1,68 @@
+// a rough guide to how hard a PIN would be to guess, shown while the user types a new one.
+// it only considers length and the kinds of characters used.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    Weak,
+    Fair,
+    Good,
+    Strong,
+}
+
+impl Strength {
+    // the number of filled segments in the strength meter
+    pub fn level(&self) -> usize {
+        *self as usize + 1
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Strength::Weak => "passphrase-strength.weak",
+            Strength::Fair => "passphrase-strength.fair",
+            Strength::Good => "passphrase-strength.good",
+            Strength::Strong => "passphrase-strength.strong",
+        }
+    }
+}
+
+pub fn strength(passphrase: &str) -> Strength {
+    let len = passphrase.chars().count();
+    let classes = [
+        passphrase.chars().any(|c| c.is_lowercase()),
+        passphrase.chars().any(|c| c.is_uppercase()),
+        passphrase.chars().any(|c| c.is_numeric()),
+        passphrase.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|x| **x)
+    .count();
+
+    let score = match len {
+        0..=5 => 0,
+        6..=9 => 1,
+        10..=15 => 2,
+        _ => 3,
+    } + classes.saturating_sub(1);
+
+    match score {
+        0..=1 => Strength::Weak,
+        2..=3 => Strength::Fair,
+        4 => Strength::Good,
+        _ => Strength::Strong,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_strength() {
+        assert_eq!(strength("1234"), Strength::Weak);
+        assert_eq!(strength("abcdefgh"), Strength::Weak);
+        assert_eq!(strength("abcdefgh12"), Strength::Fair);
+        assert_eq!(strength("Abcdefgh12"), Strength::Good);
+        assert_eq!(strength("Abcdefgh12!xyzwv"), Strength::Strong);
+        assert!(strength("correcthorsebatterystaple") > strength("correct"));
+    }
+}
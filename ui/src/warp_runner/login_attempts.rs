@@ -0,0 +1,129 @@
+//! Counts failed unlock attempts and makes the user wait longer after each one. The count is saved to disk,
+//! so restarting Uplink doesn't reset it.
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use warp::logging::tracing::log;
+
+use crate::STATIC_ARGS;
+
+// wrong PINs allowed before the user has to wait
+const FREE_ATTEMPTS: u32 = 3;
+// the wait after the first attempt past FREE_ATTEMPTS. it doubles after each one
+const BASE_DELAY: Duration = Duration::from_secs(5);
+const MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginAttempts {
+    failures: u32,
+    last_failure: Option<SystemTime>,
+}
+
+impl LoginAttempts {
+    pub fn load() -> Self {
+        match std::fs::read_to_string(&STATIC_ARGS.login_attempts_path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                // don't let a corrupted file reset the count
+                log::error!("failed to read login attempts: {}", e);
+                Self {
+                    failures: FREE_ATTEMPTS,
+                    last_failure: Some(SystemTime::now()),
+                }
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) {
+        let res = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|s| {
+                std::fs::write(&STATIC_ARGS.login_attempts_path, s).map_err(|e| e.to_string())
+            });
+        if let Err(e) = res {
+            log::error!("failed to save login attempts: {}", e);
+        }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    fn delay(&self) -> Duration {
+        match self.failures.checked_sub(FREE_ATTEMPTS) {
+            None => Duration::ZERO,
+            Some(extra) => BASE_DELAY
+                .checked_mul(2u32.saturating_pow(extra))
+                .map_or(MAX_DELAY, |d| d.min(MAX_DELAY)),
+        }
+    }
+
+    /// How long until the user may try again, if they have to wait
+    pub fn retry_in(&self, now: SystemTime) -> Option<Duration> {
+        let last = self.last_failure?;
+        // if the clock went backwards, wait out the delay from the last failure
+        let now = now.max(last);
+        (last + self.delay())
+            .duration_since(now)
+            .ok()
+            .filter(|d| !d.is_zero())
+    }
+
+    /// Whether the account should be deleted. 0 never deletes it
+    pub fn wipe_due(&self, wipe_after: u32) -> bool {
+        wipe_after > 0 && self.failures >= wipe_after
+    }
+
+    pub fn record_failure(&mut self, now: SystemTime) {
+        self.failures = self.failures.saturating_add(1);
+        self.last_failure = Some(now);
+        self.save();
+    }
+
+    pub fn reset(&mut self) {
+        if *self != Self::default() {
+            *self = Self::default();
+            self.save();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn failed(failures: u32, now: SystemTime) -> LoginAttempts {
+        LoginAttempts {
+            failures,
+            last_failure: Some(now),
+        }
+    }
+
+    #[test]
+    fn test_retry_in() {
+        let now = SystemTime::now();
+        assert_eq!(LoginAttempts::default().retry_in(now), None);
+        assert_eq!(failed(FREE_ATTEMPTS - 1, now).retry_in(now), None);
+        assert_eq!(failed(FREE_ATTEMPTS, now).retry_in(now), Some(BASE_DELAY));
+        assert_eq!(
+            failed(FREE_ATTEMPTS + 2, now).retry_in(now),
+            Some(BASE_DELAY * 4)
+        );
+        assert_eq!(failed(100, now).retry_in(now), Some(MAX_DELAY));
+
+        let later = now + Duration::from_secs(2);
+        assert_eq!(
+            failed(FREE_ATTEMPTS, now).retry_in(later),
+            Some(BASE_DELAY - Duration::from_secs(2))
+        );
+        assert_eq!(failed(FREE_ATTEMPTS, now).retry_in(now + BASE_DELAY), None);
+    }
+
+    #[test]
+    fn test_wipe_due() {
+        let now = SystemTime::now();
+        assert!(!failed(50, now).wipe_due(0));
+        assert!(!failed(9, now).wipe_due(10));
+        assert!(failed(10, now).wipe_due(10));
+    }
+}
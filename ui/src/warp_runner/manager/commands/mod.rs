@@ -6,5 +6,7 @@ mod tesseract_commands;
 // this shortens the path required to use the functions and structs
 pub use constellation_commands::{handle_constellation_cmd, ConstellationCmd};
 pub use multipass_commands::{handle_multipass_cmd, MultiPassCmd};
-pub use raygun_commands::{handle_raygun_cmd, RayGunCmd};
-pub use tesseract_commands::TesseractCmd;
+pub use raygun_commands::{handle_raygun_cmd, RayGunCmd, SentMessages};
+pub use tesseract_commands::{
+    checked_unlock, handle_tesseract_cmd, TesseractCmd, RECOVERY_PHRASE_KEY,
+};
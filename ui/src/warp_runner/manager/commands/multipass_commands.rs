@@ -2,28 +2,41 @@ use std::collections::{HashMap, HashSet};
 
 use derive_more::Display;
 use futures::channel::oneshot;
-use warp::{crypto::DID, error::Error, logging::tracing::log};
+use warp::{
+    crypto::DID,
+    error::Error,
+    logging::tracing::log,
+    multipass::identity::{IdentityStatus, IdentityUpdate},
+};
 
 use crate::{
     state::{self, friends},
     warp_runner::{
         ui_adapter::{did_to_identity, dids_to_identity},
-        Account,
+        Account, Passphrase, UnlockError,
     },
 };
 
 #[derive(Display)]
 pub enum MultiPassCmd {
     #[display(fmt = "CreateIdentity {{ username: {username} }} ")]
+    // responds with the recovery phrase, which the user is shown once
     CreateIdentity {
         username: String,
-        passphrase: String,
+        passphrase: Passphrase,
+        rsp: oneshot::Sender<Result<Passphrase, warp::error::Error>>,
+    },
+    // rebuilds the keypair from a recovery phrase, replacing any existing account
+    #[display(fmt = "RestoreIdentity")]
+    RestoreIdentity {
+        passphrase: Passphrase,
+        phrase: Passphrase,
         rsp: oneshot::Sender<Result<(), warp::error::Error>>,
     },
     #[display(fmt = "TryLogIn")]
     TryLogIn {
-        passphrase: String,
-        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+        passphrase: Passphrase,
+        rsp: oneshot::Sender<Result<(), UnlockError>>,
     },
     #[display(fmt = "RequestFriend {{ did: {did} }} ")]
     RequestFriend {
@@ -70,6 +83,33 @@ pub enum MultiPassCmd {
         did: DID,
         rsp: oneshot::Sender<Result<(), warp::error::Error>>,
     },
+    // the update commands return the refreshed identity
+    #[display(fmt = "UpdateUsername {{ username: {username} }} ")]
+    UpdateUsername {
+        username: String,
+        rsp: oneshot::Sender<Result<state::Identity, warp::error::Error>>,
+    },
+    #[display(fmt = "UpdateStatusMessage")]
+    UpdateStatusMessage {
+        status_message: Option<String>,
+        rsp: oneshot::Sender<Result<state::Identity, warp::error::Error>>,
+    },
+    // the picture and banner are data URIs
+    #[display(fmt = "UpdateProfilePicture")]
+    UpdateProfilePicture {
+        picture: String,
+        rsp: oneshot::Sender<Result<state::Identity, warp::error::Error>>,
+    },
+    #[display(fmt = "UpdateBanner")]
+    UpdateBanner {
+        banner: String,
+        rsp: oneshot::Sender<Result<state::Identity, warp::error::Error>>,
+    },
+    #[display(fmt = "SetStatus {{ status: {status:?} }} ")]
+    SetStatus {
+        status: IdentityStatus,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
 }
 
 // hide sensitive information from debug logs
@@ -82,7 +122,9 @@ impl std::fmt::Debug for MultiPassCmd {
 
 pub async fn handle_multipass_cmd(cmd: MultiPassCmd, warp: &mut super::super::Warp) {
     match cmd {
-        MultiPassCmd::CreateIdentity { .. } | MultiPassCmd::TryLogIn { .. } => {
+        MultiPassCmd::CreateIdentity { .. }
+        | MultiPassCmd::RestoreIdentity { .. }
+        | MultiPassCmd::TryLogIn { .. } => {
             // do nothing and drop the rsp channel
         }
         MultiPassCmd::RequestFriend { did, rsp } => {
@@ -125,9 +167,54 @@ pub async fn handle_multipass_cmd(cmd: MultiPassCmd, warp: &mut super::super::Wa
             let r = warp.multipass.close_request(&did).await;
             let _ = rsp.send(r);
         }
+        MultiPassCmd::UpdateUsername { username, rsp } => {
+            let r =
+                update_identity(&mut warp.multipass, IdentityUpdate::set_username(username)).await;
+            let _ = rsp.send(r);
+        }
+        MultiPassCmd::UpdateStatusMessage {
+            status_message,
+            rsp,
+        } => {
+            let r = update_identity(
+                &mut warp.multipass,
+                IdentityUpdate::set_status_message(status_message),
+            )
+            .await;
+            let _ = rsp.send(r);
+        }
+        MultiPassCmd::UpdateProfilePicture { picture, rsp } => {
+            let r = update_identity(
+                &mut warp.multipass,
+                IdentityUpdate::set_graphics_picture(picture),
+            )
+            .await;
+            let _ = rsp.send(r);
+        }
+        MultiPassCmd::UpdateBanner { banner, rsp } => {
+            let r = update_identity(
+                &mut warp.multipass,
+                IdentityUpdate::set_graphics_banner(banner),
+            )
+            .await;
+            let _ = rsp.send(r);
+        }
+        MultiPassCmd::SetStatus { status, rsp } => {
+            let r = warp.multipass.set_identity_status(status).await;
+            let _ = rsp.send(r);
+        }
     }
 }
 
+async fn update_identity(
+    account: &mut Account,
+    update: IdentityUpdate,
+) -> Result<state::Identity, Error> {
+    account.update_identity(update).await?;
+    let identity = account.get_own_identity().await?;
+    Ok(state::Identity::from(identity))
+}
+
 async fn multipass_initialize_friends(
     account: &mut Account,
 ) -> Result<state::friends::Friends, Error> {
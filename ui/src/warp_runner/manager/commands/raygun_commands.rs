@@ -1,17 +1,28 @@
+use chrono::{DateTime, Utc};
 use derive_more::Display;
 use futures::channel::oneshot;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Range,
+    path::PathBuf,
+    time::Instant,
+};
 use uuid::Uuid;
 use warp::{
     crypto::DID,
     error::Error,
     logging::tracing::log,
-    raygun::{self, ConversationType, ReactionState},
+    raygun::{self, ConversationType, MessageOptions, ReactionState},
 };
 
 use crate::{
     state::{self, chats},
-    warp_runner::{conv_stream, ui_adapter::conversation_to_chat, Account, Messaging},
+    warp_runner::{
+        conv_stream,
+        ui_adapter::{conversation_to_chat, TransferEvent},
+        Account, Messaging, WarpEvent, WARP_CMD_TIMEOUT,
+    },
+    WARP_EVENT_CH,
 };
 
 #[allow(clippy::large_enum_variant)]
@@ -31,12 +42,84 @@ pub enum RayGunCmd {
         recipient: DID,
         rsp: oneshot::Sender<Result<chats::Chat, warp::error::Error>>,
     },
-    #[display(fmt = "SendMessage {{ conv_id: {conv_id} }} ")]
+    // `pending_id` is the id the UI gave the message. it's reported back in MessageEvent::Sent
+    #[display(fmt = "SendMessage {{ conv_id: {conv_id}, pending_id: {pending_id} }} ")]
     SendMessage {
         conv_id: Uuid,
+        pending_id: Uuid,
+        msg: Vec<String>,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
+    #[display(
+        fmt = "Reply {{ conv_id: {conv_id}, pending_id: {pending_id}, reply_to: {reply_to} }} "
+    )]
+    Reply {
+        conv_id: Uuid,
+        pending_id: Uuid,
+        // id of the message being replied to
+        reply_to: Uuid,
         msg: Vec<String>,
         rsp: oneshot::Sender<Result<(), warp::error::Error>>,
     },
+    // sends a message with the given files attached. the files are uploaded by warp.
+    // responds once the upload has started. TransferEvent::Uploaded reports when it's done
+    #[display(fmt = "SendAttachments {{ conv_id: {conv_id}, files: {files:?} }} ")]
+    SendAttachments {
+        conv_id: Uuid,
+        files: Vec<PathBuf>,
+        msg: Vec<String>,
+        rsp: oneshot::Sender<()>,
+    },
+    // saves an attachment of the given message to `path`.
+    // responds once the download has started. TransferEvent::Downloaded reports when it's done
+    #[display(
+        fmt = "DownloadAttachment {{ conv_id: {conv_id}, message_id: {message_id}, file: {file} }} "
+    )]
+    DownloadAttachment {
+        conv_id: Uuid,
+        message_id: Uuid,
+        file: String,
+        path: PathBuf,
+        rsp: oneshot::Sender<()>,
+    },
+    // edits a message which the user sent
+    #[display(fmt = "EditMessage {{ conv_id: {conv_id}, message_id: {message_id} }} ")]
+    EditMessage {
+        conv_id: Uuid,
+        message_id: Uuid,
+        msg: Vec<String>,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
+    #[display(fmt = "DeleteMessage {{ conv_id: {conv_id}, message_id: {message_id} }} ")]
+    DeleteMessage {
+        conv_id: Uuid,
+        message_id: Uuid,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
+    #[display(fmt = "CreateGroupConversation")]
+    CreateGroupConversation {
+        recipients: Vec<DID>,
+        name: Option<String>,
+        rsp: oneshot::Sender<Result<chats::Chat, warp::error::Error>>,
+    },
+    #[display(fmt = "AddGroupParticipants {{ conv_id: {conv_id} }} ")]
+    AddGroupParticipants {
+        conv_id: Uuid,
+        recipients: Vec<DID>,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
+    #[display(fmt = "RemoveGroupParticipant {{ conv_id: {conv_id}, recipient: {recipient} }} ")]
+    RemoveGroupParticipant {
+        conv_id: Uuid,
+        recipient: DID,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
+    #[display(fmt = "SetGroupName {{ conv_id: {conv_id} }} ")]
+    SetGroupName {
+        conv_id: Uuid,
+        name: String,
+        rsp: oneshot::Sender<Result<(), warp::error::Error>>,
+    },
     // removes all direct conversations involving the recipient
     #[display(fmt = "RemoveDirectConvs {{ recipient: {recipient} }} ")]
     RemoveDirectConvs {
@@ -51,6 +134,50 @@ pub enum RayGunCmd {
         emoji: String,
         rsp: oneshot::Sender<Result<(), warp::error::Error>>,
     },
+    // fetches up to `limit` of the messages which come before the message `before`, oldest first. if it's None,
+    // the newest messages are fetched. paging from a message stays correct when messages are deleted or added in the meantime
+    #[display(fmt = "FetchMessages {{ conv_id: {conv_id}, before: {before:?}, limit: {limit} }} ")]
+    FetchMessages {
+        conv_id: Uuid,
+        before: Option<Uuid>,
+        limit: usize,
+        rsp: oneshot::Sender<Result<Vec<raygun::Message>, warp::error::Error>>,
+    },
+    // fetches up to `limit` of the messages which come after the message `after`, oldest first
+    #[display(
+        fmt = "FetchNewerMessages {{ conv_id: {conv_id}, after: {after}, limit: {limit} }} "
+    )]
+    FetchNewerMessages {
+        conv_id: Uuid,
+        after: Uuid,
+        limit: usize,
+        rsp: oneshot::Sender<Result<Vec<raygun::Message>, warp::error::Error>>,
+    },
+    // fetches up to `limit` messages around the given one, oldest first. used to jump to a message.
+    // response is (messages, whether there are older messages, whether there are newer messages)
+    #[display(fmt = "FetchMessagesAround {{ conv_id: {conv_id}, message_id: {message_id} }} ")]
+    FetchMessagesAround {
+        conv_id: Uuid,
+        message_id: Uuid,
+        limit: usize,
+        rsp: oneshot::Sender<Result<(Vec<raygun::Message>, bool, bool), warp::error::Error>>,
+    },
+    // counts the messages from other users which were sent after `after`, or all of them if it's None.
+    // used when more messages arrived while Uplink was closed than are loaded at startup.
+    // past MAX_MESSAGE_WINDOW unread messages, the user's own messages are counted as well
+    #[display(fmt = "CountUnreads {{ conv_id: {conv_id} }} ")]
+    CountUnreads {
+        conv_id: Uuid,
+        after: Option<DateTime<Utc>>,
+        own_did: DID,
+        rsp: oneshot::Sender<Result<u32, warp::error::Error>>,
+    },
+    // fetches every message of the conversation, oldest first. used to build the search index the first time the user searches
+    #[display(fmt = "FetchAllMessages {{ conv_id: {conv_id} }} ")]
+    FetchAllMessages {
+        conv_id: Uuid,
+        rsp: oneshot::Sender<Result<Vec<raygun::Message>, warp::error::Error>>,
+    },
     #[display(fmt = "SendEvent {{ conv_id: {conv_id} }} ")]
     SendEvent {
         conv_id: Uuid,
@@ -65,9 +192,55 @@ impl std::fmt::Debug for RayGunCmd {
     }
 }
 
+/// RayGun picks the id of a sent message, so the id the UI gave it is matched up with warp's MessageSent event here.
+/// warp_runner handles commands and events one at a time, so a message's event always comes after its send returned.
+#[derive(Default)]
+pub struct SentMessages {
+    // (pending id, text, when it was sent) of the messages sent to each conversation, oldest first
+    sent: HashMap<Uuid, VecDeque<(Uuid, Vec<String>, Instant)>>,
+}
+
+impl SentMessages {
+    fn push(&mut self, conv_id: Uuid, pending_id: Uuid, msg: Vec<String>) {
+        let now = Instant::now();
+        self.expire(now);
+        self.sent
+            .entry(conv_id)
+            .or_default()
+            .push_back((pending_id, msg, now));
+    }
+
+    /// The UI's id for a message warp reported as sent. The text only tells apart messages sent by
+    /// warp_runner from ones sent some other way, such as attachments.
+    pub fn take(&mut self, conv_id: Uuid, msg: &[String]) -> Option<Uuid> {
+        self.expire(Instant::now());
+        let sent = self.sent.get_mut(&conv_id)?;
+        let pos = sent.iter().position(|(_, m, _)| m == msg)?;
+        let pending_id = sent.remove(pos).map(|(id, _, _)| id);
+        if sent.is_empty() {
+            self.sent.remove(&conv_id);
+        }
+        pending_id
+    }
+
+    // the event comes right after the send returns. one still unmatched after the send timeout never will be, so
+    // it isn't kept around, along with its text
+    fn expire(&mut self, now: Instant) {
+        self.sent.retain(|_, sent| {
+            while sent.front().map_or(false, |(_, _, at)| {
+                now.duration_since(*at) > WARP_CMD_TIMEOUT
+            }) {
+                sent.pop_front();
+            }
+            !sent.is_empty()
+        });
+    }
+}
+
 pub async fn handle_raygun_cmd(
     cmd: RayGunCmd,
     stream_manager: &mut conv_stream::Manager,
+    sent_messages: &mut SentMessages,
     account: &mut Account,
     messaging: &mut Messaging,
 ) {
@@ -93,8 +266,115 @@ pub async fn handle_raygun_cmd(
             };
             let _ = rsp.send(r);
         }
-        RayGunCmd::SendMessage { conv_id, msg, rsp } => {
-            let r = messaging.send(conv_id, None, msg).await;
+        RayGunCmd::SendMessage {
+            conv_id,
+            pending_id,
+            msg,
+            rsp,
+        } => {
+            let r = messaging.send(conv_id, None, msg.clone()).await;
+            if r.is_ok() {
+                sent_messages.push(conv_id, pending_id, msg);
+            }
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::Reply {
+            conv_id,
+            pending_id,
+            reply_to,
+            msg,
+            rsp,
+        } => {
+            let r = messaging.reply(conv_id, reply_to, msg.clone()).await;
+            if r.is_ok() {
+                sent_messages.push(conv_id, pending_id, msg);
+            }
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::SendAttachments {
+            conv_id,
+            files,
+            msg,
+            rsp,
+        } => {
+            // a large upload would hold up every other command
+            let mut messaging = messaging.clone();
+            tokio::spawn(async move {
+                let result = messaging.attach(conv_id, files.clone(), msg).await;
+                send_transfer_event(TransferEvent::Uploaded {
+                    conversation_id: conv_id,
+                    files,
+                    result,
+                });
+            });
+            let _ = rsp.send(());
+        }
+        RayGunCmd::DownloadAttachment {
+            conv_id,
+            message_id,
+            file,
+            path,
+            rsp,
+        } => {
+            let mut messaging = messaging.clone();
+            tokio::spawn(async move {
+                let result = messaging
+                    .download(conv_id, message_id, file.clone(), path.clone())
+                    .await;
+                send_transfer_event(TransferEvent::Downloaded { file, path, result });
+            });
+            let _ = rsp.send(());
+        }
+        RayGunCmd::EditMessage {
+            conv_id,
+            message_id,
+            msg,
+            rsp,
+        } => {
+            // RayGun edits a message when send is given the id of an existing message
+            let r = messaging.send(conv_id, Some(message_id), msg).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::DeleteMessage {
+            conv_id,
+            message_id,
+            rsp,
+        } => {
+            let r = messaging.delete(conv_id, Some(message_id)).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::CreateGroupConversation {
+            recipients,
+            name,
+            rsp,
+        } => {
+            let r = raygun_create_group_conversation(recipients, name, account, messaging).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::AddGroupParticipants {
+            conv_id,
+            recipients,
+            rsp,
+        } => {
+            let mut r = Ok(());
+            for recipient in recipients {
+                r = messaging.add_recipient(conv_id, &recipient).await;
+                if r.is_err() {
+                    break;
+                }
+            }
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::RemoveGroupParticipant {
+            conv_id,
+            recipient,
+            rsp,
+        } => {
+            let r = messaging.remove_recipient(conv_id, &recipient).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::SetGroupName { conv_id, name, rsp } => {
+            let r = messaging.update_conversation_name(conv_id, &name).await;
             let _ = rsp.send(r);
         }
         RayGunCmd::RemoveDirectConvs { recipient, rsp } => {
@@ -113,6 +393,52 @@ pub async fn handle_raygun_cmd(
                 .await;
             let _ = rsp.send(r);
         }
+        RayGunCmd::FetchMessages {
+            conv_id,
+            before,
+            limit,
+            rsp,
+        } => {
+            let r = fetch_messages(conv_id, before, limit, messaging).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::FetchNewerMessages {
+            conv_id,
+            after,
+            limit,
+            rsp,
+        } => {
+            let r = fetch_newer_messages(conv_id, after, limit, messaging).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::FetchMessagesAround {
+            conv_id,
+            message_id,
+            limit,
+            rsp,
+        } => {
+            let r = fetch_messages_around(conv_id, message_id, limit, messaging).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::CountUnreads {
+            conv_id,
+            after,
+            own_did,
+            rsp,
+        } => {
+            let r = count_unreads(conv_id, after, &own_did, messaging).await;
+            let _ = rsp.send(r);
+        }
+        RayGunCmd::FetchAllMessages { conv_id, rsp } => {
+            // a long history would hold up every other command
+            let messaging = messaging.clone();
+            tokio::spawn(async move {
+                let r = messaging
+                    .get_messages(conv_id, MessageOptions::default())
+                    .await;
+                let _ = rsp.send(r);
+            });
+        }
         RayGunCmd::SendEvent {
             conv_id,
             event,
@@ -124,6 +450,12 @@ pub async fn handle_raygun_cmd(
     }
 }
 
+fn send_transfer_event(evt: TransferEvent) {
+    if WARP_EVENT_CH.tx.send(WarpEvent::Transfer(evt)).is_err() {
+        log::error!("failed to send warp_event");
+    }
+}
+
 async fn raygun_initialize_conversations(
     convs: &[raygun::Conversation],
     stream_manager: &mut conv_stream::Manager,
@@ -153,6 +485,140 @@ async fn raygun_initialize_conversations(
     Ok((state::Identity::from(own_identity), all_chats))
 }
 
+async fn raygun_create_group_conversation(
+    recipients: Vec<DID>,
+    name: Option<String>,
+    account: &Account,
+    messaging: &mut Messaging,
+) -> Result<chats::Chat, Error> {
+    let conv = messaging.create_group_conversation(recipients).await?;
+    let conv = match name.filter(|n| !n.trim().is_empty()) {
+        Some(name) => {
+            messaging.update_conversation_name(conv.id(), &name).await?;
+            messaging.get_conversation(conv.id()).await?
+        }
+        None => conv,
+    };
+    conversation_to_chat(&conv, account, messaging).await
+}
+
+// the number of messages at the start of the history which `is_before` holds for. warp keeps messages in date order,
+// so this is a binary search over single message ranges rather than a fetch of the whole history
+async fn partition_point(
+    conv_id: Uuid,
+    total: usize,
+    messaging: &mut Messaging,
+    is_before: impl Fn(&raygun::Message) -> bool,
+) -> Result<usize, Error> {
+    let (mut low, mut high) = (0, total);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let page = messaging
+            .get_messages(conv_id, MessageOptions::default().set_range(mid..mid + 1))
+            .await?;
+        match page.first() {
+            Some(m) if is_before(m) => low = mid + 1,
+            _ => high = mid,
+        }
+    }
+    Ok(low)
+}
+
+// the position of a message in the history
+async fn message_index(
+    conv_id: Uuid,
+    message_id: Uuid,
+    total: usize,
+    messaging: &mut Messaging,
+) -> Result<usize, Error> {
+    let date = messaging.get_message(conv_id, message_id).await?.date();
+    let start = partition_point(conv_id, total, messaging, |m| m.date() < date).await?;
+    // messages sent at the same time are told apart by id
+    let end = total.min(start + chats::MESSAGE_PAGE_SIZE);
+    if start < end {
+        let same_date = messaging
+            .get_messages(conv_id, MessageOptions::default().set_range(start..end))
+            .await?;
+        if let Some(pos) = same_date.iter().position(|m| m.id() == message_id) {
+            return Ok(start + pos);
+        }
+    }
+    Err(Error::MessageNotFound)
+}
+
+// the number of messages after `after`. it's counted from the position of the last-read message, rather than by
+// fetching the unread messages. only a window's worth of them is fetched to leave out the user's own messages
+async fn count_unreads(
+    conv_id: Uuid,
+    after: Option<DateTime<Utc>>,
+    own_did: &DID,
+    messaging: &mut Messaging,
+) -> Result<u32, Error> {
+    let total = messaging.get_message_count(conv_id).await?;
+    let start = match after {
+        Some(date) => partition_point(conv_id, total, messaging, |m| m.date() <= date).await?,
+        None => 0,
+    };
+    if total - start > chats::MAX_MESSAGE_WINDOW {
+        return Ok((total - start) as u32);
+    }
+    let unread = messages_in_range(conv_id, start..total, messaging).await?;
+    Ok(unread.iter().filter(|m| &m.sender() != own_did).count() as u32)
+}
+
+// the messages in the range. warp doesn't check the range bounds
+async fn messages_in_range(
+    conv_id: Uuid,
+    range: Range<usize>,
+    messaging: &mut Messaging,
+) -> Result<Vec<raygun::Message>, Error> {
+    if range.is_empty() {
+        return Ok(vec![]);
+    }
+    messaging
+        .get_messages(conv_id, MessageOptions::default().set_range(range))
+        .await
+}
+
+async fn fetch_messages(
+    conv_id: Uuid,
+    before: Option<Uuid>,
+    limit: usize,
+    messaging: &mut Messaging,
+) -> Result<Vec<raygun::Message>, Error> {
+    let total = messaging.get_message_count(conv_id).await?;
+    let end = match before {
+        Some(id) => message_index(conv_id, id, total, messaging).await?,
+        None => total,
+    };
+    messages_in_range(conv_id, end.saturating_sub(limit)..end, messaging).await
+}
+
+async fn fetch_newer_messages(
+    conv_id: Uuid,
+    after: Uuid,
+    limit: usize,
+    messaging: &mut Messaging,
+) -> Result<Vec<raygun::Message>, Error> {
+    let total = messaging.get_message_count(conv_id).await?;
+    let start = message_index(conv_id, after, total, messaging).await? + 1;
+    messages_in_range(conv_id, start..total.min(start + limit), messaging).await
+}
+
+async fn fetch_messages_around(
+    conv_id: Uuid,
+    message_id: Uuid,
+    limit: usize,
+    messaging: &mut Messaging,
+) -> Result<(Vec<raygun::Message>, bool, bool), Error> {
+    let total = messaging.get_message_count(conv_id).await?;
+    let index = message_index(conv_id, message_id, total, messaging).await?;
+    let half = limit / 2;
+    let (start, end) = (index.saturating_sub(half), total.min(index + half + 1));
+    let messages = messages_in_range(conv_id, start..end, messaging).await?;
+    Ok((messages, start > 0, end < total))
+}
+
 async fn raygun_remove_direct_convs(
     recipient: DID,
     messaging: &mut Messaging,
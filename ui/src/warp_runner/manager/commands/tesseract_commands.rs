@@ -1,10 +1,52 @@
+use std::time::{Duration, SystemTime};
+
 use derive_more::Display;
 use futures::channel::oneshot;
+use rand::RngCore;
+use warp::{error::Error, logging::tracing::log, tesseract::Tesseract};
+use zeroize::Zeroizing;
+
+use crate::{
+    state::chats::OutboxKey,
+    warp_runner::{login_attempts::LoginAttempts, save_tesseract, Passphrase, UnlockError},
+    STATIC_ARGS,
+};
+
+/// the key under which the recovery phrase is kept in tesseract
+pub const RECOVERY_PHRASE_KEY: &str = "recovery_phrase";
+// the key under which the outbox encryption key is kept in tesseract
+const OUTBOX_KEY: &str = "outbox_key";
 
 #[derive(Display)]
 pub enum TesseractCmd {
     #[display(fmt = "AccountExists")]
     AccountExists { rsp: oneshot::Sender<bool> },
+    // the key the outbox is encrypted with in state.json. created the first time it's asked for
+    #[display(fmt = "OutboxKey")]
+    OutboxKey {
+        rsp: oneshot::Sender<Result<OutboxKey, Error>>,
+    },
+    // warp_runner keeps running while Uplink is locked. MultiPassCmd::TryLogIn unlocks it again
+    #[display(fmt = "Lock")]
+    Lock { rsp: oneshot::Sender<()> },
+    // how long until another PIN may be tried, after too many wrong ones
+    #[display(fmt = "UnlockRetryIn")]
+    UnlockRetryIn {
+        rsp: oneshot::Sender<Option<Duration>>,
+    },
+    // the passphrase is checked again before the phrase is handed out
+    #[display(fmt = "GetRecoveryPhrase")]
+    GetRecoveryPhrase {
+        passphrase: Passphrase,
+        rsp: oneshot::Sender<Result<Passphrase, UnlockError>>,
+    },
+    // re-encrypts the keystore with the new passphrase and saves it
+    #[display(fmt = "ChangePassphrase")]
+    ChangePassphrase {
+        old: Passphrase,
+        new: Passphrase,
+        rsp: oneshot::Sender<Result<(), UnlockError>>,
+    },
 }
 
 impl std::fmt::Debug for TesseractCmd {
@@ -12,3 +54,108 @@ impl std::fmt::Debug for TesseractCmd {
         write!(f, "{self}")
     }
 }
+
+pub fn handle_tesseract_cmd(cmd: TesseractCmd, tesseract: &Tesseract) {
+    match cmd {
+        TesseractCmd::AccountExists { rsp } => {
+            let _ = rsp.send(tesseract.exist("keypair"));
+        }
+        TesseractCmd::OutboxKey { rsp } => {
+            let _ = rsp.send(outbox_key(tesseract));
+        }
+        TesseractCmd::Lock { rsp } => {
+            tesseract.lock();
+            let _ = rsp.send(());
+        }
+        TesseractCmd::UnlockRetryIn { rsp } => {
+            let _ = rsp.send(LoginAttempts::load().retry_in(SystemTime::now()));
+        }
+        TesseractCmd::GetRecoveryPhrase { passphrase, rsp } => {
+            let _ = rsp.send(get_recovery_phrase(&passphrase));
+        }
+        TesseractCmd::ChangePassphrase { old, new, rsp } => {
+            let _ = rsp.send(change_passphrase(tesseract, &old, &new));
+        }
+    }
+}
+
+fn outbox_key(tesseract: &Tesseract) -> Result<OutboxKey, Error> {
+    if tesseract.exist(OUTBOX_KEY) {
+        let encoded = Zeroizing::new(tesseract.retrieve(OUTBOX_KEY)?);
+        return base64::decode(encoded.as_bytes())
+            .map(OutboxKey::new)
+            .map_err(|e| {
+                log::error!("failed to decode the outbox key: {}", e);
+                Error::CorruptedDataStore
+            });
+    }
+    let mut key = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    let encoded = Zeroizing::new(base64::encode(&key));
+    tesseract.set(OUTBOX_KEY, &encoded)?;
+    save_tesseract(tesseract)?;
+    Ok(OutboxKey::new(key))
+}
+
+// also checks the passphrase against the keypair
+fn unlock(tesseract: &Tesseract, passphrase: &str) -> Result<(), Error> {
+    tesseract.unlock(passphrase.as_bytes())?;
+    // unlocking doesn't always check the passphrase. decrypting the keypair does
+    if let Err(e) = tesseract.retrieve("keypair") {
+        tesseract.lock();
+        return Err(e);
+    }
+    Ok(())
+}
+
+// wrong passphrases count towards the unlock backoff wherever they're entered
+pub fn checked_unlock(tesseract: &Tesseract, passphrase: &str) -> Result<(), UnlockError> {
+    let mut attempts = LoginAttempts::load();
+    if let Some(wait) = attempts.retry_in(SystemTime::now()) {
+        log::warn!("unlock attempted {}s too early", wait.as_secs());
+        return Err(UnlockError::RetryIn(wait));
+    }
+    match unlock(tesseract, passphrase) {
+        Ok(_) => {
+            attempts.reset();
+            Ok(())
+        }
+        Err(Error::DecryptionError) => {
+            attempts.record_failure(SystemTime::now());
+            Err(UnlockError::WrongPin)
+        }
+        Err(e) => Err(UnlockError::Warp(e)),
+    }
+}
+
+// the running tesseract is already unlocked, so passphrases are checked against a copy loaded from the keystore file
+fn load_keystore(passphrase: &str) -> Result<Tesseract, UnlockError> {
+    let mut file = std::fs::File::open(&STATIC_ARGS.tesseract_path).map_err(|e| {
+        log::error!("failed to open tesseract keystore: {}", e);
+        Error::CorruptedDataStore
+    })?;
+    let tesseract = Tesseract::from_reader(&mut file)?;
+    checked_unlock(&tesseract, passphrase)?;
+    Ok(tesseract)
+}
+
+// accounts created before recovery phrases were saved won't have one.
+fn get_recovery_phrase(passphrase: &str) -> Result<Passphrase, UnlockError> {
+    let tesseract = load_keystore(passphrase)?;
+    let phrase = tesseract.retrieve(RECOVERY_PHRASE_KEY);
+    tesseract.lock();
+    Ok(Passphrase::new(phrase?))
+}
+
+fn change_passphrase(tesseract: &Tesseract, old: &str, new: &str) -> Result<(), UnlockError> {
+    load_keystore(old)?.lock();
+    tesseract.update_unlock(old.as_bytes(), new.as_bytes())?;
+    if let Err(e) = save_tesseract(tesseract) {
+        // keep the running keystore in sync with the one on disk
+        if let Err(e) = tesseract.update_unlock(new.as_bytes(), old.as_bytes()) {
+            log::error!("failed to restore the old passphrase: {}", e);
+        }
+        return Err(UnlockError::Warp(e));
+    }
+    Ok(())
+}
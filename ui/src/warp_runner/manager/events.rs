@@ -1,3 +1,4 @@
+use futures::channel::oneshot;
 use warp::{
     logging::tracing::log,
     multipass::MultiPassEventKind,
@@ -7,14 +8,18 @@ use warp::{
 use crate::{
     warp_runner::{
         conv_stream,
-        ui_adapter::{self, did_to_identity, MultiPassEvent},
-        WarpCmd, WarpEvent,
+        login_attempts::LoginAttempts,
+        ui_adapter::{self, did_to_identity, MessageEvent, MultiPassEvent},
+        wipe_due, UnlockError, WarpCmd, WarpEvent,
     },
     WARP_EVENT_CH,
 };
 
 use super::{
-    commands::{handle_constellation_cmd, handle_multipass_cmd, handle_raygun_cmd},
+    commands::{
+        checked_unlock, handle_constellation_cmd, handle_multipass_cmd, handle_raygun_cmd,
+        handle_tesseract_cmd, SentMessages,
+    },
     MultiPassCmd,
 };
 
@@ -79,6 +84,7 @@ pub async fn handle_raygun_event(
 pub async fn handle_message_event(
     evt: Option<MessageEventKind>,
     warp: &mut super::Warp,
+    sent_messages: &mut SentMessages,
 ) -> Result<(), ()> {
     let msg = match evt {
         Some(e) => e,
@@ -87,12 +93,21 @@ pub async fn handle_message_event(
     log::debug!("received message event: {:?}", &msg);
     let warp_event_tx = WARP_EVENT_CH.tx.clone();
     match ui_adapter::convert_message_event(msg, &mut warp.multipass, &mut warp.raygun).await {
-        Ok(evt) => {
+        Ok(Some(mut evt)) => {
+            if let MessageEvent::Sent {
+                conversation_id,
+                message,
+                pending_id,
+            } = &mut evt
+            {
+                *pending_id = sent_messages.take(*conversation_id, &message.value());
+            }
             if warp_event_tx.send(WarpEvent::Message(evt)).is_err() {
                 log::error!("failed to send warp_event");
                 return Err(());
             }
         }
+        Ok(None) => {}
         Err(e) => {
             log::error!("failed to convert message event: {}", e);
         }
@@ -105,6 +120,8 @@ pub async fn handle_warp_command(
     evt: Option<WarpCmd>,
     warp: &mut super::Warp,
     stream_manager: &mut conv_stream::Manager,
+    sent_messages: &mut SentMessages,
+    wipe: &mut Option<oneshot::Sender<Result<(), UnlockError>>>,
 ) -> Result<(), ()> {
     let cmd = match evt {
         Some(e) => e,
@@ -113,8 +130,16 @@ pub async fn handle_warp_command(
     log::debug!("WARP CMD: {}", &cmd);
     let warp_event_tx = WARP_EVENT_CH.tx.clone();
     match cmd {
-        WarpCmd::Tesseract(_cmd) => {
-            // not accepted at this stage of the program. do nothing and drop the rsp channel
+        WarpCmd::Tesseract(cmd) => handle_tesseract_cmd(cmd, &warp.tesseract),
+        // after logging in, this unlocks Uplink again. the passphrase is zeroed when dropped at the end of this arm
+        WarpCmd::MultiPass(MultiPassCmd::TryLogIn { passphrase, rsp }) => {
+            match checked_unlock(&warp.tesseract, &passphrase) {
+                // the account can't be deleted while warp is running. manager::run stops and hands rsp on
+                Err(UnlockError::WrongPin) if wipe_due(&LoginAttempts::load()) => *wipe = Some(rsp),
+                res => {
+                    let _ = rsp.send(res);
+                }
+            }
         }
         WarpCmd::MultiPass(cmd) => {
             // if a command to block a user comes in, need to update the UI because warp doesn't generate an event for a user being blocked.
@@ -145,7 +170,14 @@ pub async fn handle_warp_command(
         }
 
         WarpCmd::RayGun(cmd) => {
-            handle_raygun_cmd(cmd, stream_manager, &mut warp.multipass, &mut warp.raygun).await
+            handle_raygun_cmd(
+                cmd,
+                stream_manager,
+                sent_messages,
+                &mut warp.multipass,
+                &mut warp.raygun,
+            )
+            .await
         }
 
         WarpCmd::Constellation(cmd) => handle_constellation_cmd(cmd, &mut warp.constellation).await,
@@ -2,7 +2,7 @@
 
 pub mod commands;
 mod events;
-use futures::StreamExt;
+use futures::{channel::oneshot, StreamExt};
 use std::sync::Arc;
 use tokio::sync::Notify;
 use warp::{
@@ -10,10 +10,10 @@ use warp::{
     tesseract::Tesseract,
 };
 
-use super::{conv_stream, Account, Messaging, Storage};
+use super::{conv_stream, Account, Messaging, Storage, UnlockError};
 use crate::WARP_CMD_CH;
 
-pub use commands::{ConstellationCmd, MultiPassCmd, RayGunCmd, TesseractCmd};
+pub use commands::{ConstellationCmd, MultiPassCmd, RayGunCmd, TesseractCmd, RECOVERY_PHRASE_KEY};
 
 /// Contains the structs needed for run() to handle various events
 pub struct Warp {
@@ -23,7 +23,12 @@ pub struct Warp {
     pub constellation: Storage,
 }
 
-pub async fn run(mut warp: Warp, notify: Arc<Notify>) {
+// returns the unlock responder if too many wrong PINs mean the account has to be deleted. warp has to stop first,
+// so handle_login deletes it and answers
+pub async fn run(
+    mut warp: Warp,
+    notify: Arc<Notify>,
+) -> Option<oneshot::Sender<Result<(), UnlockError>>> {
     // receive command from Uplink
     let warp_cmd_rx = WARP_CMD_CH.rx.clone();
 
@@ -33,6 +38,8 @@ pub async fn run(mut warp: Warp, notify: Arc<Notify>) {
     // gather incoming messages from all conversations and read them from conversation_msg_rx
     let (conversation_msg_tx, mut conversation_msg_rx) = tokio::sync::mpsc::unbounded_channel();
     let mut conversation_manager = conv_stream::Manager::new(conversation_msg_tx.clone());
+    let mut sent_messages = commands::SentMessages::default();
+    let mut wipe = None;
 
     // receive events from RayGun and MultiPass
     let mut raygun_stream = get_raygun_stream(&mut warp.raygun).await;
@@ -52,12 +59,16 @@ pub async fn run(mut warp: Warp, notify: Arc<Notify>) {
                 }
             },
             opt = conversation_msg_rx.recv() => {
-                if events::handle_message_event(opt, &mut warp).await.is_err() {
+                if events::handle_message_event(opt, &mut warp, &mut sent_messages).await.is_err() {
                     break;
                 }
             }
             opt = warp_cmd_rx.recv() => {
-                if events::handle_warp_command(opt, &mut warp, &mut conversation_manager).await.is_err() {
+                if events::handle_warp_command(opt, &mut warp, &mut conversation_manager, &mut sent_messages, &mut wipe).await.is_err() {
+                    break;
+                }
+                if wipe.is_some() {
+                    log::warn!("too many wrong PINs. deleting the account");
                     break;
                 }
             } ,
@@ -67,6 +78,7 @@ pub async fn run(mut warp: Warp, notify: Arc<Notify>) {
     }
 
     log::debug!("terminating warp_runner thread");
+    wipe
 }
 
 async fn get_raygun_stream(rg: &mut Messaging) -> RayGunEventStream {
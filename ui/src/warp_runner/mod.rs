@@ -1,28 +1,50 @@
 //! Defines important types and structs, and spawns the main task for warp_runner - manager::run.
 use derive_more::Display;
-use std::sync::Arc;
+use futures::channel::oneshot;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tokio::sync::{
     mpsc::{UnboundedReceiver, UnboundedSender},
     Mutex, Notify,
 };
 use warp::{
-    constellation::Constellation, error::Error, logging::tracing::log, multipass::MultiPass,
-    raygun::RayGun, tesseract::Tesseract,
+    constellation::Constellation,
+    crypto::keypair::{generate_mnemonic_phrase, mnemonic_into_tesseract, PhraseType},
+    error::Error,
+    logging::tracing::log,
+    multipass::MultiPass,
+    raygun::RayGun,
+    tesseract::Tesseract,
 };
 use warp_fs_ipfs::config::FsIpfsConfig;
 use warp_mp_ipfs::config::MpIpfsConfig;
 use warp_rg_ipfs::config::RgIpfsConfig;
+use zeroize::Zeroizing;
 
-use crate::{STATIC_ARGS, WARP_CMD_CH};
+use crate::{
+    config::Configuration,
+    state::chats::{PendingMessage, PendingStatus},
+    STATIC_ARGS, WARP_CMD_CH,
+};
 
-use self::ui_adapter::{MultiPassEvent, RayGunEvent};
+use self::{
+    login_attempts::LoginAttempts,
+    ui_adapter::{MultiPassEvent, RayGunEvent, TransferEvent},
+};
 
 mod conv_stream;
+mod login_attempts;
 mod manager;
 pub mod ui_adapter;
 
+use manager::RECOVERY_PHRASE_KEY;
 pub use manager::{ConstellationCmd, MultiPassCmd, RayGunCmd, TesseractCmd};
 
+/// PINs and recovery phrases are zeroed when dropped, so they don't linger in memory after use
+pub type Passphrase = Zeroizing<String>;
+
 pub type WarpCmdTx = UnboundedSender<WarpCmd>;
 pub type WarpCmdRx = Arc<Mutex<UnboundedReceiver<WarpCmd>>>;
 pub type WarpEventTx = UnboundedSender<WarpEvent>;
@@ -47,6 +69,7 @@ pub enum WarpEvent {
     RayGun(RayGunEvent),
     Message(ui_adapter::MessageEvent),
     MultiPass(MultiPassEvent),
+    Transfer(TransferEvent),
 }
 
 #[derive(Display)]
@@ -61,6 +84,167 @@ pub enum WarpCmd {
     Constellation(ConstellationCmd),
 }
 
+/// how long the UI waits for warp_runner to respond to a command before giving up
+pub const WARP_CMD_TIMEOUT: Duration = Duration::from_secs(30);
+/// creating an account or logging in also waits for multipass to start, which can take a while
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(120);
+/// uploads are bounded by file size rather than by warp_runner being responsive
+pub const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 30);
+
+/// The reasons a command sent to warp_runner can fail, as seen by the UI.
+#[derive(Debug, Display)]
+pub enum WarpCmdError {
+    #[display(fmt = "warp_runner is not running")]
+    ChannelClosed,
+    // warp_runner dropped the responder. happens if, for example, a command comes in before warp is initialized
+    #[display(fmt = "warp_runner dropped the command")]
+    Canceled,
+    #[display(fmt = "timed out waiting for warp_runner")]
+    Timeout,
+    #[display(fmt = "{_0}")]
+    Warp(Error),
+}
+
+impl std::error::Error for WarpCmdError {}
+
+/// Why a PIN wasn't accepted.
+#[derive(Debug, Display)]
+pub enum UnlockError {
+    #[display(fmt = "wrong PIN")]
+    WrongPin,
+    // too many wrong PINs were entered. another one may be tried after the wait
+    #[display(fmt = "too early. another PIN may be tried in {}s", _0.as_secs())]
+    RetryIn(Duration),
+    // too many wrong PINs were entered, so the account was deleted, as set in the privacy settings
+    #[display(fmt = "the account was deleted after too many wrong PINs")]
+    AccountWiped,
+    #[display(fmt = "{_0}")]
+    Warp(Error),
+}
+
+impl std::error::Error for UnlockError {}
+
+impl From<Error> for UnlockError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::DecryptionError => UnlockError::WrongPin,
+            e => UnlockError::Warp(e),
+        }
+    }
+}
+
+/// Sends a command to warp_runner and waits up to `timeout` for its response. `build` is given the
+/// responder and returns the command. Never panics, even if warp_runner drops the responder.
+pub async fn send_cmd_with_timeout<T>(
+    build: impl FnOnce(oneshot::Sender<T>) -> WarpCmd,
+    timeout: Duration,
+) -> Result<T, WarpCmdError> {
+    let (tx, rx) = oneshot::channel::<T>();
+    let cmd = build(tx);
+    if let Err(e) = WARP_CMD_CH.tx.send(cmd) {
+        log::error!("failed to send warp command: {}", e);
+        return Err(WarpCmdError::ChannelClosed);
+    }
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(rsp)) => Ok(rsp),
+        Ok(Err(_)) => Err(WarpCmdError::Canceled),
+        Err(_) => Err(WarpCmdError::Timeout),
+    }
+}
+
+/// Same as `send_cmd_with_timeout`, using `WARP_CMD_TIMEOUT`.
+pub async fn send_cmd<T>(
+    build: impl FnOnce(oneshot::Sender<T>) -> WarpCmd,
+) -> Result<T, WarpCmdError> {
+    send_cmd_with_timeout(build, WARP_CMD_TIMEOUT).await
+}
+
+/// For the common case where warp_runner responds with a `Result`: flattens the warp error into `WarpCmdError::Warp`.
+pub async fn run_cmd_with_timeout<T>(
+    build: impl FnOnce(oneshot::Sender<Result<T, Error>>) -> WarpCmd,
+    timeout: Duration,
+) -> Result<T, WarpCmdError> {
+    send_cmd_with_timeout(build, timeout)
+        .await?
+        .map_err(WarpCmdError::Warp)
+}
+
+/// Same as `run_cmd_with_timeout`, using `WARP_CMD_TIMEOUT`.
+pub async fn run_cmd<T>(
+    build: impl FnOnce(oneshot::Sender<Result<T, Error>>) -> WarpCmd,
+) -> Result<T, WarpCmdError> {
+    run_cmd_with_timeout(build, WARP_CMD_TIMEOUT).await
+}
+
+/// Whether an account has been created on this device. Errs on the side of true, so an existing account is never
+/// overwritten without asking.
+pub async fn account_exists() -> bool {
+    send_cmd(|rsp| WarpCmd::Tesseract(TesseractCmd::AccountExists { rsp }))
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("failed to check for an account: {}", e);
+            true
+        })
+}
+
+/// How long the unlock page has to wait before another PIN can be tried, if at all.
+pub async fn unlock_retry_in() -> Option<Duration> {
+    send_cmd(|rsp| WarpCmd::Tesseract(TesseractCmd::UnlockRetryIn { rsp }))
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("failed to check unlock attempts: {}", e);
+            None
+        })
+}
+
+/// Sends a message from the outbox and returns its new status. The message goes back into the queue only if it
+/// never reached warp. Otherwise it's marked as failed, so that it isn't sent twice. If warp did send it, the
+/// MessageSent event still removes it from the outbox.
+pub async fn send_pending_message(pending: &PendingMessage) -> PendingStatus {
+    let conv_id = pending.conversation_id();
+    let pending_id = pending.id();
+    let msg = pending.message.value();
+    let rsp = match pending.message.replied() {
+        Some(reply_to) => {
+            run_cmd(|rsp| {
+                WarpCmd::RayGun(RayGunCmd::Reply {
+                    conv_id,
+                    pending_id,
+                    reply_to,
+                    msg,
+                    rsp,
+                })
+            })
+            .await
+        }
+        None => {
+            run_cmd(|rsp| {
+                WarpCmd::RayGun(RayGunCmd::SendMessage {
+                    conv_id,
+                    pending_id,
+                    msg,
+                    rsp,
+                })
+            })
+            .await
+        }
+    };
+    match rsp {
+        Ok(_) => PendingStatus::Sent,
+        // warp_runner isn't running, or dropped the command before logging in
+        Err(e @ WarpCmdError::ChannelClosed) | Err(e @ WarpCmdError::Canceled) => {
+            log::warn!("message queued, couldn't reach warp_runner: {}", e);
+            PendingStatus::Queued
+        }
+        // on a timeout, warp may still send the message
+        Err(e) => {
+            log::error!("failed to send message: {}", e);
+            PendingStatus::Failed
+        }
+    }
+}
+
 /// Spawns a task which manages multiple streams, channels, and tasks related to warp
 pub struct WarpRunner {
     // perhaps collecting a JoinHandle and calling abort() would be better than using Notify.
@@ -89,18 +273,26 @@ impl WarpRunner {
 
         let notify = self.notify.clone();
         tokio::spawn(async move {
-            handle_login(notify.clone()).await;
+            // too many wrong PINs after logging in delete the account. warp then starts over, waiting for a new one
+            let mut wiped = None;
+            while let Some(rsp) = handle_login(notify.clone(), wiped.take()).await {
+                wiped = Some(rsp);
+            }
         });
     }
 }
 
-// handle_login calls manager::run, which continues to process warp commands
-async fn handle_login(notify: Arc<Notify>) {
+// handle_login calls manager::run, which continues to process warp commands. if manager::run stopped because the
+// account has to be deleted, returns the unlock responder, and the next call deletes it and answers.
+async fn handle_login(
+    notify: Arc<Notify>,
+    wiped: Option<oneshot::Sender<Result<(), UnlockError>>>,
+) -> Option<oneshot::Sender<Result<(), UnlockError>>> {
     let warp_cmd_rx = WARP_CMD_CH.rx.clone();
     // be sure to drop this channel before calling manager::run()
     let mut warp_cmd_rx = warp_cmd_rx.lock().await;
 
-    let tesseract = init_tesseract(false)
+    let tesseract = init_tesseract(wiped.is_some())
         .await
         .expect("failed to initialize tesseract");
 
@@ -108,11 +300,17 @@ async fn handle_login(notify: Arc<Notify>) {
         Ok(w) => w,
         Err(e) => {
             log::error!("warp init failed: {}", e);
-            return;
+            if let Some(rsp) = wiped {
+                let _ = rsp.send(Err(UnlockError::Warp(e)));
+            }
+            return None;
         }
     };
+    if let Some(rsp) = wiped {
+        let _ = rsp.send(Err(UnlockError::AccountWiped));
+    }
 
-    let account_exists = warp.tesseract.exist("keypair");
+    let mut account_exists = warp.tesseract.exist("keypair");
 
     // until the user logs in, raygun and multipass are no use.
     let warp: Option<manager::Warp> = loop {
@@ -130,28 +328,33 @@ async fn handle_login(notify: Arc<Notify>) {
                     })) => {
                         if account_exists {
                             log::debug!("attempting to overwrite old account");
-                            let tesseract = init_tesseract(true)
-                                .await
-                                .expect("failed to initialize tesseract");
-                            warp = match warp_initialization(tesseract, false).await {
-                                Ok(w) => w,
+                            match reset_account().await {
+                                Ok(w) => warp = w,
                                 Err(e) => {
-                                    log::error!("warp init failed: {}", e);
-                                    return;
+                                    let _ = rsp.send(Err(e));
+                                    continue;
                                 }
-                            };
+                            }
                         }
 
-                        if let Err(e) = warp.tesseract.unlock(passphrase.as_bytes()) {
+                        let unlocked = warp.tesseract.unlock(passphrase.as_bytes());
+                        drop(passphrase);
+                        if let Err(e) = unlocked {
                             log::info!("unlock failed: {:?}", e);
                             let _ = rsp.send(Err(e));
                             continue;
                         };
-                        match warp.multipass.create_identity(Some(&username), None).await {
+                        let phrase = Passphrase::new(generate_mnemonic_phrase(PhraseType::Standard).into_phrase());
+                        if let Err(e) = warp.tesseract.set(RECOVERY_PHRASE_KEY, &phrase) {
+                            log::error!("failed to store recovery phrase: {}", e);
+                            let _ = rsp.send(Err(e));
+                            continue;
+                        }
+                        match warp.multipass.create_identity(Some(&username), Some(phrase.as_str())).await {
                             Ok(_id) =>  match wait_for_multipass(&mut warp, notify.clone()).await {
                                 Ok(_) => match save_tesseract(&warp.tesseract) {
                                     Ok(_) => {
-                                        let _ = rsp.send(Ok(()));
+                                        let _ = rsp.send(Ok(phrase));
                                         break Some(warp);
                                     }
                                     Err(e) => {
@@ -171,20 +374,66 @@ async fn handle_login(notify: Arc<Notify>) {
                             }
                         }
                     }
+                    Some(WarpCmd::MultiPass(MultiPassCmd::RestoreIdentity {
+                        passphrase,
+                        phrase,
+                        rsp,
+                    })) => {
+                        match restore_identity(&passphrase, &phrase, notify.clone()).await {
+                            Ok(restored) => {
+                                let _ = rsp.send(Ok(()));
+                                break Some(restored);
+                            }
+                            Err(e) => {
+                                log::error!("failed to restore account: {}", e);
+                                let _ = rsp.send(Err(e));
+                            }
+                        }
+                    }
                     Some(WarpCmd::MultiPass(MultiPassCmd::TryLogIn { passphrase, rsp })) => {
-                        if let Err(e) = warp.tesseract.unlock(passphrase.as_bytes()) {
-                            log::info!("unlock failed: {:?}", e);
-                            let _ = rsp.send(Err(e));
+                        let mut attempts = LoginAttempts::load();
+                        if let Some(wait) = attempts.retry_in(SystemTime::now()) {
+                            log::warn!("unlock attempted {}s too early", wait.as_secs());
+                            let _ = rsp.send(Err(UnlockError::RetryIn(wait)));
                             continue;
+                        }
+                        let unlocked = warp.tesseract.unlock(passphrase.as_bytes());
+                        drop(passphrase);
+                        let res = match unlocked {
+                            Ok(_) => wait_for_multipass(&mut warp, notify.clone()).await,
+                            Err(e) => {
+                                log::info!("unlock failed: {:?}", e);
+                                Err(e)
+                            }
                         };
-                        match wait_for_multipass(&mut warp, notify.clone()).await {
+                        match res {
                             Ok(_) => {
+                                attempts.reset();
                                 let _ = rsp.send(Ok(()));
                                 break Some(warp);
                             },
                             Err(e) => {
                                 warp.tesseract.lock();
-                                let _ = rsp.send(Err(e));
+                                if !matches!(e, Error::DecryptionError) {
+                                    let _ = rsp.send(Err(UnlockError::Warp(e)));
+                                    continue;
+                                }
+                                attempts.record_failure(SystemTime::now());
+                                if !wipe_due(&attempts) {
+                                    let _ = rsp.send(Err(UnlockError::WrongPin));
+                                    continue;
+                                }
+                                log::warn!("{} failed unlock attempts. deleting the account", attempts.failures());
+                                match reset_account().await {
+                                    Ok(w) => {
+                                        warp = w;
+                                        account_exists = false;
+                                        let _ = rsp.send(Err(UnlockError::AccountWiped));
+                                    }
+                                    Err(e) => {
+                                        let _ = rsp.send(Err(UnlockError::Warp(e)));
+                                    }
+                                }
                                 continue;
                             }
                         }
@@ -192,6 +441,9 @@ async fn handle_login(notify: Arc<Notify>) {
                     Some(WarpCmd::Tesseract(TesseractCmd::AccountExists { rsp }))  => {
                         let _ = rsp.send(account_exists);
                     }
+                    Some(WarpCmd::Tesseract(TesseractCmd::UnlockRetryIn { rsp }))  => {
+                        let _ = rsp.send(LoginAttempts::load().retry_in(SystemTime::now()));
+                    }
                     _ => {}
                 }
             },
@@ -204,10 +456,62 @@ async fn handle_login(notify: Arc<Notify>) {
     drop(warp_cmd_rx);
 
     if let Some(warp) = warp {
-        manager::run(warp, notify).await;
+        manager::run(warp, notify).await
     } else {
         log::info!("warp_runner terminated during initialization");
+        None
+    }
+}
+
+// whether enough wrong PINs were entered in a row to delete the account, as set in the privacy settings
+fn wipe_due(attempts: &LoginAttempts) -> bool {
+    let wipe_after = Configuration::load_or_default()
+        .privacy
+        .wipe_after_failed_unlocks;
+    attempts.wipe_due(wipe_after)
+}
+
+// deletes the account on disk and starts warp with an empty keystore. on failure, the caller keeps its old instance
+// rather than stopping warp_runner
+async fn reset_account() -> Result<manager::Warp, Error> {
+    let tesseract = init_tesseract(true).await.map_err(|e| {
+        log::error!("failed to initialize tesseract: {}", e);
+        e
+    })?;
+    warp_initialization(tesseract, false).await.map_err(|e| {
+        log::error!("warp init failed: {}", e);
+        e
+    })
+}
+
+// replaces whatever account is on disk with the one the recovery phrase belongs to
+async fn restore_identity(
+    passphrase: &str,
+    phrase: &str,
+    notify: Arc<Notify>,
+) -> Result<manager::Warp, Error> {
+    // check the phrase against a throwaway keystore first. a mistyped phrase must not delete the existing account
+    let mut scratch = Tesseract::default();
+    scratch.unlock(passphrase.as_bytes())?;
+    let valid = mnemonic_into_tesseract(&mut scratch, phrase, None, false, false);
+    scratch.lock();
+    if let Err(e) = valid {
+        log::warn!("invalid recovery phrase: {}", e);
+        return Err(e);
     }
+
+    let mut tesseract = init_tesseract(true).await?;
+    tesseract.unlock(passphrase.as_bytes())?;
+    mnemonic_into_tesseract(&mut tesseract, phrase, None, false, true)?;
+    tesseract.set(RECOVERY_PHRASE_KEY, phrase)?;
+
+    let mut warp = warp_initialization(tesseract, false).await?;
+    if let Err(e) = wait_for_multipass(&mut warp, notify).await {
+        warp.tesseract.lock();
+        return Err(e);
+    }
+    save_tesseract(&warp.tesseract)?;
+    Ok(warp)
 }
 
 async fn wait_for_multipass(warp: &mut manager::Warp, notify: Arc<Notify>) -> Result<(), Error> {
@@ -239,7 +543,7 @@ async fn wait_for_multipass(warp: &mut manager::Warp, notify: Arc<Notify>) -> Re
 }
 
 // don't set file or autosave until tesseract is unlocked
-// assumes that all anyone needs from tesseract is "keypair"
+// assumes that all anyone needs from tesseract is "keypair" and the recovery phrase
 // otherwise, Tesseract::to_file probably needs to call file.sync_all()
 async fn init_tesseract(overwrite_old_account: bool) -> Result<Tesseract, Error> {
     log::trace!("initializing tesseract");
@@ -341,29 +645,35 @@ async fn warp_initialization(
     })
 }
 
+// writes to a temporary file and renames it over the keystore, so an interrupted save can't leave a partial file behind
 pub fn save_tesseract(tesseract: &warp::tesseract::Tesseract) -> Result<(), Error> {
     log::info!("saving tesseract");
-    let mut file = match std::fs::OpenOptions::new()
-        .write(true)
-        .append(false)
-        .create(false)
-        .open(&STATIC_ARGS.tesseract_path)
-    {
+    let tmp_path = STATIC_ARGS.tesseract_path.with_extension("json.tmp");
+    let mut file = match std::fs::File::create(&tmp_path) {
         Ok(f) => f,
         Err(e) => {
-            log::error!("failed to open tesseract keystore for saving: {}", e);
-            return Err(Error::CorruptedDataStore);
+            log::error!("failed to create temporary tesseract keystore: {}", e);
+            return Err(Error::CannotSaveTesseract);
         }
     };
     if let Err(e) = tesseract.to_writer(&mut file) {
         log::error!("tesseract.to_writer() failed: {}", e);
+        let _ = std::fs::remove_file(&tmp_path);
         return Err(e);
     }
 
     if let Err(e) = file.sync_all() {
         log::error!("failed to sync tesseract: {}", e);
+        let _ = std::fs::remove_file(&tmp_path);
         return Err(Error::CorruptedDataStore);
     }
+    drop(file);
+
+    if let Err(e) = std::fs::rename(&tmp_path, &STATIC_ARGS.tesseract_path) {
+        log::error!("failed to replace tesseract keystore: {}", e);
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(Error::CannotSaveTesseract);
+    }
 
     Ok(())
 }
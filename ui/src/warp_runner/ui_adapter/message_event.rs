@@ -6,6 +6,10 @@ use warp::{
     raygun::{self, MessageEventKind},
 };
 
+use crate::state;
+
+use super::dids_to_identity;
+
 pub enum MessageEvent {
     Received {
         conversation_id: Uuid,
@@ -14,6 +18,16 @@ pub enum MessageEvent {
     Sent {
         conversation_id: Uuid,
         message: raygun::Message,
+        // the id the UI gave the message in its outbox. filled in by warp_runner
+        pending_id: Option<Uuid>,
+    },
+    Edited {
+        conversation_id: Uuid,
+        message: raygun::Message,
+    },
+    Deleted {
+        conversation_id: Uuid,
+        message_id: Uuid,
     },
     MessageReactionAdded {
         conversation_id: Uuid,
@@ -29,13 +43,20 @@ pub enum MessageEvent {
         conversation_id: Uuid,
         participant: DID,
     },
+    // participants were added to or removed from a group, or it was renamed
+    ConversationUpdated {
+        conversation_id: Uuid,
+        participants: Vec<state::Identity>,
+        name: Option<String>,
+    },
 }
 
+// returns None for events the UI doesn't handle yet. they're logged and ignored
 pub async fn convert_message_event(
     event: warp::raygun::MessageEventKind,
-    _account: &mut super::super::Account,
+    account: &mut super::super::Account,
     messaging: &mut super::super::Messaging,
-) -> Result<MessageEvent, Error> {
+) -> Result<Option<MessageEvent>, Error> {
     log::debug!("got event: {:?}", &event);
     let evt = match event {
         MessageEventKind::MessageReceived {
@@ -58,8 +79,26 @@ pub async fn convert_message_event(
             MessageEvent::Sent {
                 conversation_id,
                 message,
+                pending_id: None,
             }
         }
+        MessageEventKind::MessageEdited {
+            conversation_id,
+            message_id,
+        } => {
+            let message = messaging.get_message(conversation_id, message_id).await?;
+            MessageEvent::Edited {
+                conversation_id,
+                message,
+            }
+        }
+        MessageEventKind::MessageDeleted {
+            conversation_id,
+            message_id,
+        } => MessageEvent::Deleted {
+            conversation_id,
+            message_id,
+        },
         MessageEventKind::MessageReactionAdded {
             conversation_id,
             message_id,
@@ -90,11 +129,28 @@ pub async fn convert_message_event(
                 participant: did_key,
             },
         },
+        MessageEventKind::RecipientAdded {
+            conversation_id, ..
+        }
+        | MessageEventKind::RecipientRemoved {
+            conversation_id, ..
+        }
+        | MessageEventKind::ConversationNameUpdated {
+            conversation_id, ..
+        } => {
+            let conv = messaging.get_conversation(conversation_id).await?;
+            let participants = dids_to_identity(&conv.recipients(), account).await?;
+            MessageEvent::ConversationUpdated {
+                conversation_id,
+                participants,
+                name: conv.name(),
+            }
+        }
         _ => {
-            println!("evt received: {event:?}");
-            todo!();
+            log::warn!("ignoring unhandled message event: {:?}", event);
+            return Ok(None);
         }
     };
 
-    Ok(evt)
+    Ok(Some(evt))
 }
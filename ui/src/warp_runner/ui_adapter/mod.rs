@@ -5,10 +5,12 @@
 mod message_event;
 mod multipass_event;
 mod raygun_event;
+mod transfer_event;
 
 pub use message_event::{convert_message_event, MessageEvent};
 pub use multipass_event::{convert_multipass_event, MultiPassEvent};
 pub use raygun_event::{convert_raygun_event, RayGunEvent};
+pub use transfer_event::TransferEvent;
 
 use std::collections::{HashMap, VecDeque};
 
@@ -59,19 +61,38 @@ pub async fn conversation_to_chat(
         participants.push(identity);
     }
 
-    // todo: warp doesn't support paging yet. it also doesn't check the range bounds
-    let unreads = messaging.get_message_count(conv.id()).await?;
-    let messages: VecDeque<raygun::Message> = messaging
-        .get_messages(conv.id(), MessageOptions::default().set_range(0..unreads))
-        .await?
-        .into();
+    // only load the most recent page. older messages are fetched as the user scrolls up.
+    // warp doesn't check the range bounds
+    let total = messaging.get_message_count(conv.id()).await?;
+    let messages_start = total.saturating_sub(chats::MESSAGE_PAGE_SIZE);
+    let messages: VecDeque<raygun::Message> = if messages_start < total {
+        messaging
+            .get_messages(
+                conv.id(),
+                MessageOptions::default().set_range(messages_start..total),
+            )
+            .await?
+            .into()
+    } else {
+        VecDeque::new()
+    };
 
     Ok(chats::Chat {
         id: conv.id(),
         participants,
+        name: conv.name(),
+        conversation_type: conv.conversation_type(),
         messages,
-        unreads: unreads as u32,
+        more_before: messages_start > 0,
+        more_after: false,
+        // computed by State from the last-read marker
+        unreads: 0,
+        last_read: None,
+        first_unread: None,
+        jump_to: None,
+        notifications: Default::default(),
         replying_to: None,
         typing_indicator: HashMap::new(),
+        uploads: Vec::new(),
     })
 }
@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use uuid::Uuid;
+use warp::error::Error;
+
+// attachments are sent and saved in their own tasks, so that warp_runner keeps handling other commands while a file
+// is transferred. warp doesn't report how far along a transfer is, only when it's done
+pub enum TransferEvent {
+    Uploaded {
+        conversation_id: Uuid,
+        files: Vec<PathBuf>,
+        result: Result<(), Error>,
+    },
+    Downloaded {
+        file: String,
+        path: PathBuf,
+        result: Result<(), Error>,
+    },
+}
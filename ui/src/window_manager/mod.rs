@@ -7,7 +7,14 @@ use tokio::sync::{
     Mutex,
 };
 
-use crate::state::{Action, State};
+use warp::logging::tracing::log;
+
+use crate::{
+    state::{notifications::NotificationLink, Action, State},
+    utils::notifications::NotificationAction,
+    warp_runner::{self, MultiPassCmd, WarpCmd},
+    STATIC_ARGS, UPLINK_ROUTES,
+};
 
 pub type WindowManagerCmdTx = UnboundedSender<WindowManagerCmd>;
 pub type WindowManagerCmdRx = Arc<Mutex<UnboundedReceiver<WindowManagerCmd>>>;
@@ -20,6 +27,8 @@ pub struct WindowManagerCmdChannels {
 pub enum WindowManagerCmd {
     ClosePopout,
     CloseDebugLogger,
+    // an OS notification, or one of its buttons, was clicked
+    NotificationAction(NotificationAction),
 }
 
 pub async fn handle_cmd(
@@ -42,5 +51,98 @@ pub async fn handle_cmd(
                 //todo: add logging
             }
         }
+        WindowManagerCmd::NotificationAction(action) => {
+            handle_notification_action(state, action, desktop)
+        }
     }
 }
+
+fn handle_notification_action(
+    state: Rc<RefCell<ProvidedStateInner<State>>>,
+    action: NotificationAction,
+    desktop: DesktopContext,
+) {
+    let link = match action {
+        NotificationAction::Open(link) => link,
+        NotificationAction::Reply(conversation_id) => NotificationLink::Chat {
+            conversation_id,
+            message_id: None,
+        },
+        // friend requests are answered without bringing up the window
+        NotificationAction::AcceptRequest(identity) => {
+            if STATIC_ARGS.use_mock {
+                if let Ok(s) = state.try_borrow_mut() {
+                    s.write().mutate(Action::AcceptRequest(identity));
+                }
+            } else {
+                // spawned so that a slow response doesn't hold up the other window commands
+                tokio::spawn(async move {
+                    if let Err(e) = warp_runner::run_cmd(|rsp| {
+                        WarpCmd::MultiPass(MultiPassCmd::AcceptRequest {
+                            did: identity.did_key(),
+                            rsp,
+                        })
+                    })
+                    .await
+                    {
+                        log::error!("failed to accept request: {}", e);
+                    }
+                });
+            }
+            return;
+        }
+        NotificationAction::DenyRequest(identity) => {
+            if STATIC_ARGS.use_mock {
+                if let Ok(s) = state.try_borrow_mut() {
+                    s.write().mutate(Action::DenyRequest(identity));
+                }
+            } else {
+                tokio::spawn(async move {
+                    if let Err(e) = warp_runner::run_cmd(|rsp| {
+                        WarpCmd::MultiPass(MultiPassCmd::DenyRequest {
+                            did: identity.did_key(),
+                            rsp,
+                        })
+                    })
+                    .await
+                    {
+                        log::error!("failed to deny request: {}", e);
+                    }
+                });
+            }
+            return;
+        }
+    };
+
+    desktop.set_visible(true);
+    desktop.set_minimized(false);
+    desktop.focus();
+
+    let s = match state.try_borrow_mut() {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("{e}");
+            return;
+        }
+    };
+    let route = match link {
+        NotificationLink::Friends => UPLINK_ROUTES.friends,
+        NotificationLink::Settings => UPLINK_ROUTES.settings,
+        NotificationLink::Chat {
+            conversation_id,
+            message_id,
+        } => {
+            let chat = s.write().chats.all.get(&conversation_id).cloned();
+            match (chat, message_id) {
+                (Some(_), Some(message_id)) => s
+                    .write()
+                    .mutate(Action::JumpToMessage(conversation_id, message_id)),
+                (Some(chat), None) => s.write().mutate(Action::ChatWith(chat)),
+                // the chat was deleted or left
+                (None, _) => return,
+            }
+            UPLINK_ROUTES.chat
+        }
+    };
+    s.write().mutate(Action::Navigate(route.to_string()));
+}